@@ -1,15 +1,19 @@
 use std::{
     io::{Write, stdin, stdout},
+    net::{IpAddr, SocketAddr},
     path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use qobuz_player_controls::{
-    AudioQuality, client::Client, database::Database, notification::NotificationBroadcast,
-    player::Player,
+    AudioQuality, Connection, Status, client::Client, database::Database,
+    notification::NotificationBroadcast, output::Output, player::Player,
 };
+use qobuz_player_models::TrackStatus;
 use qobuz_player_rfid::RfidState;
+use qobuz_player_web::{BindAddress, Peer, ServerConfig, TlsConfig, ZonesConfig};
 use snafu::prelude::*;
 use tokio_schedule::{Job, every};
 
@@ -24,65 +28,170 @@ struct Cli {
     command: Option<Commands>,
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Default. Starts the player
-    Open {
-        /// Provide a username. (overrides any configured value)
-        #[clap(short, long)]
-        username: Option<String>,
-
-        #[clap(short, long)]
-        /// Provide a password. (overrides any configured value)
-        password: Option<String>,
-
-        #[clap(short, long)]
-        /// Provide max audio quality. (overrides any configured value)
-        max_audio_quality: Option<AudioQuality>,
-
-        #[clap(short, long, default_value_t = false)]
-        /// Disable the TUI interface.
-        disable_tui: bool,
-
-        #[cfg(target_os = "linux")]
-        #[clap(long, default_value_t = false)]
-        /// Disable the mpris interface.
-        disable_mpris: bool,
-
-        #[clap(short, long, default_value_t = false)]
-        /// Start web server with websocket API and embedded UI.
-        web: bool,
-
-        #[clap(long)]
-        /// Secret used for web ui auth.
-        web_secret: Option<String>,
-
-        #[clap(long, default_value_t = false)]
-        /// Enable rfid interface.
-        rfid: bool,
-
-        #[cfg(feature = "gpio")]
-        #[clap(long, default_value_t = false)]
-        /// Enable gpio interface for raspberry pi. Pin 16 (gpio-23) will be high when playing.
-        gpio: bool,
+#[derive(Args)]
+struct OpenArgs {
+    /// Provide a username. (overrides any configured value)
+    #[clap(short, long)]
+    username: Option<String>,
 
-        #[clap(long, default_value_t = 9888)]
-        /// Specify port for the web server.
-        port: u16,
+    #[clap(short, long)]
+    /// Provide a password. (overrides any configured value)
+    password: Option<String>,
 
-        #[clap(long)]
-        /// Cache audio files in directory.
-        audio_cache: Option<PathBuf>,
+    #[clap(short, long)]
+    /// Provide max audio quality. (overrides any configured value)
+    max_audio_quality: Option<AudioQuality>,
+
+    #[clap(long, env = "QOBUZ_PLAYER_API_URL")]
+    /// Base url for the Qobuz api. (overrides any configured value)
+    api_url: Option<String>,
+
+    #[clap(long, env = "QOBUZ_PLAYER_PLAY_URL")]
+    /// Base url for the Qobuz web player, used to fetch app secrets. (overrides any configured value)
+    play_url: Option<String>,
+
+    #[clap(short, long, default_value_t = false)]
+    /// Disable the TUI interface.
+    disable_tui: bool,
+
+    #[cfg(target_os = "linux")]
+    #[clap(long, default_value_t = false)]
+    /// Disable the mpris interface.
+    disable_mpris: bool,
+
+    #[clap(short, long, default_value_t = false)]
+    /// Start web server with websocket API and embedded UI.
+    web: bool,
+
+    #[clap(long)]
+    /// Secret used for web ui auth. (overrides any configured value)
+    web_secret: Option<String>,
+
+    #[clap(long, default_value_t = false)]
+    /// Enable rfid interface.
+    rfid: bool,
+
+    #[cfg(feature = "gpio")]
+    #[clap(long, default_value_t = false)]
+    /// Enable gpio interface for raspberry pi. Pin 16 (gpio-23) will be high when playing.
+    gpio: bool,
+
+    #[clap(long, default_value_t = 9888)]
+    /// Specify port for the web server.
+    port: u16,
+
+    #[clap(long, default_value = "0.0.0.0")]
+    /// Address for the web server: an ip address (v4 or v6), optionally with a port, or `unix:<path>` for a unix socket.
+    interface: String,
+
+    #[clap(long, requires = "tls_key")]
+    /// Pem encoded certificate chain. Serves the web ui over https together with --tls-key.
+    tls_cert: Option<PathBuf>,
+
+    #[clap(long, requires = "tls_cert")]
+    /// Pem encoded private key for --tls-cert.
+    tls_key: Option<PathBuf>,
+
+    #[clap(long, env = "QOBUZ_PLAYER_BASE_PATH")]
+    /// Serve the web ui below a path, e.g. `/music` behind a reverse proxy.
+    base_path: Option<String>,
+
+    #[clap(
+        long = "trusted-proxy",
+        env = "QOBUZ_PLAYER_TRUSTED_PROXIES",
+        value_delimiter = ','
+    )]
+    /// Ip address of a reverse proxy whose X-Forwarded-For header names the client, so failed logins lock out that client instead of everyone behind the proxy. Can be repeated.
+    trusted_proxies: Vec<IpAddr>,
+
+    #[clap(long, default_value = "This player")]
+    /// Name of this player in the web ui zone list.
+    zone_name: String,
+
+    #[clap(long = "peer", env = "QOBUZ_PLAYER_PEERS", value_delimiter = ',')]
+    /// Other player to control from the web ui, as NAME=URL. Append #<api token> when the peer has a web secret. Can be repeated.
+    peers: Vec<Peer>,
+
+    #[clap(long, conflicts_with = "sync_leader")]
+    /// Lead a sync group: followers connecting to this address, e.g. `0.0.0.0:9889`, play the same queue in sync.
+    sync_listen: Option<String>,
+
+    #[clap(long)]
+    /// Join the sync group led by the player at this address, e.g. `livingroom:9889`. Append #<api token> when the leader has a web secret.
+    sync_leader: Option<String>,
+
+    #[clap(long, default_value = "device")]
+    /// Where audio goes: `device`, `null`, `file:<path>` (.wav or .flac), `stdout` or `pipe:<path>`. Everything but the device gets 48 kHz 16 bit stereo; raw pcm is little endian. `stdout` disables the TUI.
+    output: Output,
+
+    #[clap(long, default_value_t = 10)]
+    /// Seconds skipped by jump forward and jump backward.
+    jump_interval: u64,
+
+    #[clap(long)]
+    /// Cache audio files in directory.
+    audio_cache: Option<PathBuf>,
+
+    #[clap(long, default_value_t = false)]
+    /// Do not clean up audio cache
+    no_clean_up_audio_cache: bool,
+}
 
-        #[clap(long, default_value_t = false)]
-        /// Do not clean up audio cache
-        no_clean_up_audio_cache: bool,
+#[derive(Subcommand)]
+enum Commands {
+    /// Default. Starts the player
+    Open(OpenArgs),
+    /// Starts the player and plays a Qobuz share link
+    Play {
+        /// Album, artist, playlist or track link, e.g. https://play.qobuz.com/album/<id>
+        url: String,
+
+        #[clap(flatten)]
+        args: OpenArgs,
     },
     /// Persist configurations
     Config {
         #[clap(subcommand)]
         command: ConfigCommands,
     },
+    /// Control an already running player
+    #[cfg(unix)]
+    Ctl {
+        #[clap(subcommand)]
+        command: CtlCommands,
+    },
+}
+
+#[cfg(unix)]
+#[derive(Subcommand)]
+pub enum CtlCommands {
+    /// Resume playback.
+    Play,
+    /// Pause playback.
+    Pause,
+    /// Skip to the next track.
+    Next,
+    /// Go back to the previous track.
+    Prev,
+    /// Seek to a position in the current track.
+    #[clap(value_parser)]
+    Seek { seconds: u64 },
+    /// Set volume in percent.
+    #[clap(value_parser)]
+    Volume {
+        #[clap(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+    },
+    /// Show what is playing.
+    Status,
+    /// List the tracks in the queue.
+    Queue,
+    /// Play an album by id.
+    #[clap(value_parser)]
+    PlayAlbum { id: String },
+    /// Play the work of an album containing the track at an index.
+    #[clap(value_parser)]
+    PlayWork { album_id: String, index: u32 },
 }
 
 #[derive(Subcommand)]
@@ -99,6 +208,15 @@ pub enum ConfigCommands {
         #[clap(value_enum)]
         quality: AudioQuality,
     },
+    /// Set base url for the Qobuz api. Leave empty to use the default.
+    #[clap(value_parser)]
+    ApiUrl { url: Option<String> },
+    /// Set base url for the Qobuz web player. Leave empty to use the default.
+    #[clap(value_parser)]
+    PlayUrl { url: Option<String> },
+    /// Set secret for web ui auth. Leave empty to get a prompt, submit an empty secret to disable auth.
+    #[clap(value_parser)]
+    WebSecret { secret: Option<String> },
 }
 
 #[derive(Debug, Snafu)]
@@ -113,6 +231,10 @@ pub enum Error {
     PasswordMissing,
     #[snafu(display("Error reading error prompt"))]
     PasswordError,
+    #[snafu(display("Unable to play {url}: {error}"))]
+    InvalidUrl { url: String, error: String },
+    #[snafu(display("Invalid interface {interface}. Use an ip address or unix:<path>"))]
+    InvalidInterface { interface: String },
 }
 
 impl From<qobuz_player_controls::error::Error> for Error {
@@ -131,210 +253,20 @@ pub async fn run() -> Result<(), Error> {
     tracing_subscriber::fmt()
         .with_max_level(cli.verbosity)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .compact()
         .init();
 
-    match cli.command.unwrap_or(Commands::Open {
-        username: Default::default(),
-        password: Default::default(),
-        max_audio_quality: Default::default(),
-        disable_tui: Default::default(),
-        #[cfg(target_os = "linux")]
-        disable_mpris: Default::default(),
-        web: Default::default(),
-        web_secret: Default::default(),
-        rfid: Default::default(),
-        port: Default::default(),
-        #[cfg(feature = "gpio")]
-        gpio: Default::default(),
-        audio_cache: Default::default(),
-        no_clean_up_audio_cache: Default::default(),
-    }) {
-        Commands::Open {
-            username,
-            password,
-            max_audio_quality,
-            disable_tui,
-            #[cfg(target_os = "linux")]
-            disable_mpris,
-            web,
-            web_secret,
-            rfid,
-            port,
-            #[cfg(feature = "gpio")]
-            gpio,
-            audio_cache,
-            no_clean_up_audio_cache,
-        } => {
-            let database_credentials = database.get_credentials().await?;
-            let database_configuration = database.get_configuration().await?;
-            let tracklist = database.get_tracklist().await.unwrap_or_default();
-            let volume = database.get_volume().await.unwrap_or(1.0);
-
-            let audio_cache = audio_cache.unwrap_or_else(|| {
-                let mut cache_dir = std::env::temp_dir();
-                cache_dir.push("qobuz-player-cache");
-                cache_dir
-            });
-
-            let username = match username {
-                Some(username) => username,
-                None => database_credentials
-                    .username
-                    .ok_or(Error::UsernameMissing)?,
-            };
-
-            let password = match password {
-                Some(p) => p,
-                None => database_credentials
-                    .password
-                    .ok_or(Error::PasswordMissing)?,
-            };
-
-            let max_audio_quality = max_audio_quality.unwrap_or_else(|| {
-                database_configuration
-                    .max_audio_quality
-                    .try_into()
-                    .expect("This should always convert")
-            });
-
-            let client = Arc::new(Client::new(username, password, max_audio_quality));
-
-            let broadcast = Arc::new(NotificationBroadcast::new());
-            let mut player = Player::new(
-                tracklist,
-                client.clone(),
-                volume,
-                broadcast.clone(),
-                audio_cache,
-                database.clone(),
-            )?;
-
-            let rfid_state = rfid.then(RfidState::default);
-
-            #[cfg(target_os = "linux")]
-            if !disable_mpris {
-                let position_receiver = player.position();
-                let tracklist_receiver = player.tracklist();
-                let volume_receiver = player.volume();
-                let status_receiver = player.status();
-                let controls = player.controls();
-                tokio::spawn(async move {
-                    if let Err(e) = qobuz_player_mpris::init(
-                        position_receiver,
-                        tracklist_receiver,
-                        volume_receiver,
-                        status_receiver,
-                        controls,
-                    )
-                    .await
-                    {
-                        exit(!disable_tui && !rfid, e.into());
-                    }
-                });
-            }
-
-            if web {
-                let position_receiver = player.position();
-                let tracklist_receiver = player.tracklist();
-                let volume_receiver = player.volume();
-                let status_receiver = player.status();
-                let controls = player.controls();
-                let rfid_state = rfid_state.clone();
-                let broadcast = broadcast.clone();
-                let client = client.clone();
-
-                tokio::spawn(async move {
-                    if let Err(e) = qobuz_player_web::init(
-                        controls,
-                        position_receiver,
-                        tracklist_receiver,
-                        volume_receiver,
-                        status_receiver,
-                        port,
-                        web_secret,
-                        rfid_state,
-                        broadcast,
-                        client,
-                    )
-                    .await
-                    {
-                        exit(!disable_tui && !rfid, e.into());
-                    }
-                });
-            }
-
-            #[cfg(feature = "gpio")]
-            if gpio {
-                let status_receiver = player.status();
-                tokio::spawn(async move {
-                    if let Err(e) = qobuz_player_gpio::init(status_receiver).await {
-                        exit(!disable_tui && !rfid, e.into());
-                    }
-                });
-            }
-
-            if let Some(rfid_state) = rfid_state {
-                let tracklist_receiver = player.tracklist();
-                let controls = player.controls();
-                let database = database.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = qobuz_player_rfid::init(
-                        rfid_state,
-                        tracklist_receiver,
-                        controls,
-                        database,
-                        broadcast,
-                    )
-                    .await
-                    {
-                        exit(!disable_tui && !rfid, e.into());
-                    }
-                });
-            } else if !disable_tui {
-                let position_receiver = player.position();
-                let tracklist_receiver = player.tracklist();
-                let status_receiver = player.status();
-                let controls = player.controls();
-                let client = client.clone();
-                let broadcast = broadcast.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = qobuz_player_tui::init(
-                        client,
-                        broadcast,
-                        controls,
-                        position_receiver,
-                        tracklist_receiver,
-                        status_receiver,
-                    )
-                    .await
-                    {
-                        exit(!disable_tui && !rfid, e.into());
-                    };
-                });
-            };
-
-            if !no_clean_up_audio_cache {
-                let clean_up_schedule = every(1).hour().perform(move || {
-                    let database = database.clone();
-                    async move {
-                        if let Ok(deleted_paths) = database
-                            .clean_up_cache_entries(time::Duration::hours(1))
-                            .await
-                        {
-                            for path in deleted_paths {
-                                _ = tokio::fs::remove_file(path.as_path()).await;
-                            }
-                        };
-                    }
-                });
+    // Without a subcommand, run `open` so its defaults and env vars come from clap too.
+    let command = cli.command.unwrap_or_else(|| {
+        Cli::parse_from(["qobuz-player", "open"])
+            .command
+            .expect("open is a command")
+    });
 
-                tokio::spawn(clean_up_schedule);
-            }
-
-            player.player_loop().await?;
-            Ok(())
-        }
+    match command {
+        Commands::Open(args) => open(database, args, None).await,
+        Commands::Play { url, args } => open(database, args, Some(url)).await,
         Commands::Config { command } => match command {
             ConfigCommands::Username { username } => {
                 database.set_username(username).await?;
@@ -344,15 +276,7 @@ pub async fn run() -> Result<(), Error> {
             ConfigCommands::Password { password } => {
                 let password = match password {
                     Some(password) => password,
-                    None => {
-                        print!("Password: ");
-                        stdout().flush().or(Err(Error::PasswordError))?;
-                        stdin()
-                            .lines()
-                            .next()
-                            .expect("encountered EOF")
-                            .or(Err(Error::PasswordError))?
-                    }
+                    None => prompt("Password: ")?,
                 };
                 database.set_password(password).await?;
                 println!("Password saved.");
@@ -365,8 +289,447 @@ pub async fn run() -> Result<(), Error> {
 
                 Ok(())
             }
+            ConfigCommands::ApiUrl { url } => {
+                database.set_api_url(url).await?;
+
+                println!("Api url saved.");
+
+                Ok(())
+            }
+            ConfigCommands::PlayUrl { url } => {
+                database.set_play_url(url).await?;
+
+                println!("Play url saved.");
+
+                Ok(())
+            }
+            ConfigCommands::WebSecret { secret } => {
+                let secret = match secret {
+                    Some(secret) => secret,
+                    None => prompt("Web secret: ")?,
+                };
+
+                match secret.is_empty() {
+                    true => {
+                        database.set_web_secret(None).await?;
+                        println!("Web secret removed. Restart a running player to apply.");
+                    }
+                    false => {
+                        database.set_web_secret(Some(secret)).await?;
+                        println!("Web secret saved. Restart a running player to apply.");
+                    }
+                }
+
+                Ok(())
+            }
+        },
+        #[cfg(unix)]
+        Commands::Ctl { command } => ctl(command).await,
+    }
+}
+
+fn prompt(label: &str) -> Result<String, Error> {
+    print!("{label}");
+    stdout().flush().or(Err(Error::PasswordError))?;
+    stdin()
+        .lines()
+        .next()
+        .expect("encountered EOF")
+        .or(Err(Error::PasswordError))
+}
+
+#[cfg(unix)]
+async fn ctl(command: CtlCommands) -> Result<(), Error> {
+    use qobuz_player_controls::remote::{self, Request, Response};
+
+    let request = match command {
+        CtlCommands::Play => Request::Play,
+        CtlCommands::Pause => Request::Pause,
+        CtlCommands::Next => Request::Next,
+        CtlCommands::Prev => Request::Previous,
+        CtlCommands::Seek { seconds } => Request::Seek { seconds },
+        CtlCommands::Volume { percent } => Request::Volume {
+            volume: percent as f32 / 100.0,
         },
+        CtlCommands::Status => Request::Status,
+        CtlCommands::Queue => Request::Queue,
+        CtlCommands::PlayAlbum { id } => Request::PlayAlbum { id },
+        CtlCommands::PlayWork { album_id, index } => Request::PlayWork { album_id, index },
+    };
+
+    match remote::send(&remote::socket_path(), &request).await? {
+        Response::Ok => {}
+        Response::Status(status) => {
+            let state = match status.status {
+                Status::Playing => "Playing",
+                Status::Buffering => "Buffering",
+                Status::Paused => "Paused",
+            };
+            let volume = (status.volume * 100.0).round();
+
+            match status.track {
+                Some(track) => {
+                    println!("{state}: {}", track_line(&track));
+                    println!(
+                        "{} / {}, volume {volume}%",
+                        format_seconds(status.position_seconds),
+                        format_seconds(track.duration_seconds as u64)
+                    );
+                }
+                None => println!("{state}, volume {volume}%"),
+            }
+        }
+        Response::Queue { tracks } => {
+            for (index, track) in tracks.iter().enumerate() {
+                let marker = match track.status {
+                    TrackStatus::Playing => ">",
+                    _ => " ",
+                };
+                println!("{marker} {:>3}. {}", index + 1, track_line(track));
+            }
+        }
+        Response::Error { message } => return Err(Error::PlayerError { error: message }),
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn track_line(track: &qobuz_player_models::Track) -> String {
+    match &track.artist_name {
+        Some(artist_name) => format!("{artist_name} - {}", track.title),
+        None => track.title.clone(),
+    }
+}
+
+#[cfg(unix)]
+fn format_seconds(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Start the player and every enabled interface, playing `url` once the player is ready.
+async fn open(database: Arc<Database>, args: OpenArgs, url: Option<String>) -> Result<(), Error> {
+    let OpenArgs {
+        username,
+        password,
+        max_audio_quality,
+        api_url,
+        play_url,
+        disable_tui,
+        #[cfg(target_os = "linux")]
+        disable_mpris,
+        web,
+        web_secret,
+        rfid,
+        port,
+        interface,
+        tls_cert,
+        tls_key,
+        base_path,
+        trusted_proxies,
+        zone_name,
+        peers,
+        sync_listen,
+        sync_leader,
+        output,
+        jump_interval,
+        #[cfg(feature = "gpio")]
+        gpio,
+        audio_cache,
+        no_clean_up_audio_cache,
+    } = args;
+
+    let database_credentials = database.get_credentials().await?;
+    let database_configuration = database.get_configuration().await?;
+    let tracklist = database.get_tracklist().await.unwrap_or_default();
+    let volume = database.get_volume().await.unwrap_or(1.0);
+
+    let audio_cache = audio_cache.unwrap_or_else(|| {
+        let mut cache_dir = std::env::temp_dir();
+        cache_dir.push("qobuz-player-cache");
+        cache_dir
+    });
+
+    let username = match username {
+        Some(username) => username,
+        None => database_credentials
+            .username
+            .ok_or(Error::UsernameMissing)?,
+    };
+
+    let password = match password {
+        Some(p) => p,
+        None => database_credentials
+            .password
+            .ok_or(Error::PasswordMissing)?,
+    };
+
+    let max_audio_quality = max_audio_quality.unwrap_or_else(|| {
+        database_configuration
+            .max_audio_quality
+            .try_into()
+            .expect("This should always convert")
+    });
+
+    let api_url = api_url
+        .or(database_configuration.api_url)
+        .unwrap_or_else(|| qobuz_player_client::transport::DEFAULT_API_URL.to_string());
+
+    let play_url = play_url
+        .or(database_configuration.play_url)
+        .unwrap_or_else(|| qobuz_player_client::transport::DEFAULT_PLAY_URL.to_string());
+
+    let connection = Connection::new(&api_url, &play_url);
+
+    let client = Arc::new(Client::new(
+        username,
+        password,
+        max_audio_quality,
+        connection,
+    ));
+
+    let broadcast = Arc::new(NotificationBroadcast::new());
+    // The pcm would end up in the terminal.
+    let disable_tui = disable_tui || output == Output::Stdout;
+
+    let mut player = Player::new(
+        output,
+        tracklist,
+        client.clone(),
+        volume,
+        broadcast.clone(),
+        audio_cache,
+        database.clone(),
+    )?;
+    player.set_jump_interval(Duration::from_secs(jump_interval));
+
+    if let Some(url) = url {
+        player
+            .controls()
+            .play_url(&url)
+            .map_err(|error| Error::InvalidUrl {
+                url: url.clone(),
+                error: error.to_string(),
+            })?;
     }
+
+    #[cfg(unix)]
+    match qobuz_player_controls::remote::bind(&qobuz_player_controls::remote::socket_path()).await {
+        Ok(listener) => {
+            let position_receiver = player.position();
+            let tracklist_receiver = player.tracklist();
+            let volume_receiver = player.volume();
+            let status_receiver = player.status();
+            let controls = player.controls();
+            tokio::spawn(async move {
+                if let Err(e) = qobuz_player_controls::remote::serve(
+                    listener,
+                    controls,
+                    position_receiver,
+                    tracklist_receiver,
+                    volume_receiver,
+                    status_receiver,
+                )
+                .await
+                {
+                    tracing::error!("{e}");
+                }
+            });
+        }
+        Err(e) => tracing::warn!("{e}"),
+    }
+
+    if let Some(address) = sync_listen {
+        let listener = qobuz_player_controls::sync::bind(&address).await?;
+        let leader = qobuz_player_controls::sync::Leader {
+            clock_receiver: player.clock(),
+            tracklist_receiver: player.tracklist(),
+            status_receiver: player.status(),
+        };
+        // Like api clients, followers need an api token once the web ui has a secret.
+        let has_web_secret =
+            web_secret.is_some() || database.get_web_auth().await?.secret_hash.is_some();
+        let tokens = has_web_secret.then(|| database.clone());
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_controls::sync::lead(listener, leader, tokens).await {
+                tracing::error!("{e}");
+            }
+        });
+    }
+
+    if let Some(leader) = sync_leader {
+        let (address, token) = match leader.split_once('#') {
+            Some((address, token)) => (address.to_string(), Some(token.to_string())),
+            None => (leader, None),
+        };
+        let controls = player.controls();
+        tokio::spawn(async move {
+            qobuz_player_controls::sync::follow(&address, token, controls).await
+        });
+    }
+
+    let rfid_state = rfid.then(RfidState::default);
+
+    #[cfg(target_os = "linux")]
+    if !disable_mpris {
+        let position_receiver = player.position();
+        let seeked_receiver = player.seeked();
+        let tracklist_receiver = player.tracklist();
+        let volume_receiver = player.volume();
+        let status_receiver = player.status();
+        let controls = player.controls();
+        let play_url = play_url.clone();
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_mpris::init(
+                position_receiver,
+                seeked_receiver,
+                tracklist_receiver,
+                volume_receiver,
+                status_receiver,
+                controls,
+                play_url,
+            )
+            .await
+            {
+                exit(!disable_tui && !rfid, e.into());
+            }
+        });
+    }
+
+    if web {
+        let server = ServerConfig {
+            address: bind_address(&interface, port)?,
+            tls: tls_cert
+                .zip(tls_key)
+                .map(|(cert, key)| TlsConfig { cert, key }),
+            base_path,
+            trusted_proxies,
+        };
+        let zones = ZonesConfig {
+            name: zone_name,
+            peers,
+        };
+        let position_receiver = player.position();
+        let tracklist_receiver = player.tracklist();
+        let volume_receiver = player.volume();
+        let status_receiver = player.status();
+        let controls = player.controls();
+        let rfid_state = rfid_state.clone();
+        let broadcast = broadcast.clone();
+        let client = client.clone();
+        let database = database.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_web::init(
+                controls,
+                position_receiver,
+                tracklist_receiver,
+                volume_receiver,
+                status_receiver,
+                server,
+                zones,
+                web_secret,
+                rfid_state,
+                broadcast,
+                client,
+                database,
+            )
+            .await
+            {
+                exit(!disable_tui && !rfid, e.into());
+            }
+        });
+    }
+
+    #[cfg(feature = "gpio")]
+    if gpio {
+        let status_receiver = player.status();
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_gpio::init(status_receiver).await {
+                exit(!disable_tui && !rfid, e.into());
+            }
+        });
+    }
+
+    if let Some(rfid_state) = rfid_state {
+        let tracklist_receiver = player.tracklist();
+        let controls = player.controls();
+        let database = database.clone();
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_rfid::init(
+                rfid_state,
+                tracklist_receiver,
+                controls,
+                database,
+                broadcast,
+            )
+            .await
+            {
+                exit(!disable_tui && !rfid, e.into());
+            }
+        });
+    } else if !disable_tui {
+        let position_receiver = player.position();
+        let tracklist_receiver = player.tracklist();
+        let status_receiver = player.status();
+        let controls = player.controls();
+        let client = client.clone();
+        let broadcast = broadcast.clone();
+        tokio::spawn(async move {
+            if let Err(e) = qobuz_player_tui::init(
+                client,
+                broadcast,
+                controls,
+                position_receiver,
+                tracklist_receiver,
+                status_receiver,
+            )
+            .await
+            {
+                exit(!disable_tui && !rfid, e.into());
+            };
+        });
+    };
+
+    if !no_clean_up_audio_cache {
+        let clean_up_schedule = every(1).hour().perform(move || {
+            let database = database.clone();
+            async move {
+                if let Ok(deleted_paths) = database
+                    .clean_up_cache_entries(time::Duration::hours(1))
+                    .await
+                {
+                    for path in deleted_paths {
+                        _ = tokio::fs::remove_file(path.as_path()).await;
+                    }
+                };
+            }
+        });
+
+        tokio::spawn(clean_up_schedule);
+    }
+
+    player.player_loop().await?;
+    Ok(())
+}
+
+/// Parse `--interface`, which is an ip address, a socket address overriding `port`, or
+/// `unix:<path>`.
+fn bind_address(interface: &str, port: u16) -> Result<BindAddress, Error> {
+    #[cfg(unix)]
+    if let Some(path) = interface.strip_prefix("unix:") {
+        return Ok(BindAddress::Unix(PathBuf::from(path)));
+    }
+
+    let ip = interface.trim_start_matches('[').trim_end_matches(']');
+
+    ip.parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, port))
+        .or_else(|_| interface.parse::<SocketAddr>())
+        .map(BindAddress::Tcp)
+        .map_err(|_| Error::InvalidInterface {
+            interface: interface.to_string(),
+        })
 }
 
 fn exit(cli: bool, error: Error) {
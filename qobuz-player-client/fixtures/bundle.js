@@ -0,0 +1,3 @@
+var config={production:{api:{appId:"123456789",appSecret:"0123456789abcdef0123456789abcdef"}}};
+a.initialSeed("Zml4dHVyZS1zZWNyZXQ=",window.utimezone.berlin);
+var timezones=[{offset:"GMT+01:00",name:"Europe/Berlin",info:"aaaaaaaaaaaaaaaaaaaaaa",extras:"bbbbbbbbbbbbbbbbbbbbbb"}];
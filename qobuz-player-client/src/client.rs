@@ -2,35 +2,42 @@ use crate::{
     Error, Result,
     qobuz_models::{
         TrackURL,
+        album::AlbumSearchResults,
         album_suggestion::{
             AlbumOfTheWeekQuery, AlbumSuggestion, AlbumSuggestionResponse, ReleaseQuery,
         },
-        artist::{self, ArtistsResponse},
+        artist::{self, ArtistSearchResults, ArtistsResponse},
         artist_page,
         favorites::Favorites,
         featured::{FeaturedAlbumsResponse, FeaturedPlaylistsResponse},
+        genre::{self, GenreListResponse},
         playlist::{self, UserPlaylistsResult},
-        search_results::SearchAllResults,
+        search_results::{PlaylistSearchResults, SearchAllResults, TrackSearchResults},
         track,
     },
+    transport::Connection,
 };
 use base64::{Engine as _, engine::general_purpose};
-use reqwest::{
-    Method, Response, StatusCode,
-    header::{HeaderMap, HeaderValue},
-};
+use futures::future::try_join_all;
+use qobuz_player_models::{ArtistReleases, ReleaseType, SearchFilter, SearchPage};
+use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{collections::HashMap, fmt::Display};
 use time::macros::format_description;
 use tokio::try_join;
 
+/// Pages fetched at most for one page of filtered search results.
+const MAX_FILTERED_SEARCH_PAGES: usize = 5;
+
+/// The largest page Qobuz returns for playlist and user playlist listings.
+const PAGE_SIZE: u32 = 500;
+
 #[derive(Debug)]
 pub struct Client {
     active_secret: String,
     app_id: String,
-    base_url: String,
-    http_client: reqwest::Client,
+    connection: Connection,
     user_token: String,
     user_id: i64,
     max_audio_quality: AudioQuality,
@@ -69,43 +76,62 @@ impl TryFrom<i64> for AudioQuality {
     }
 }
 
+/// Order of an artist's releases, named as in the Qobuz api.
+#[derive(
+    Default, Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseSort {
+    /// Newest first.
+    #[default]
+    ReleaseDate,
+    /// By release date, the releases Qobuz ranks higher first.
+    ReleaseDateByPriority,
+    /// Most popular first.
+    Relevant,
+}
+
+impl Display for ReleaseSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ReleaseSort::ReleaseDate => "release_date",
+            ReleaseSort::ReleaseDateByPriority => "release_date_by_priority",
+            ReleaseSort::Relevant => "relevant",
+        })
+    }
+}
+
 pub async fn new(
     username: &str,
     password: &str,
     max_audio_quality: AudioQuality,
+    connection: Connection,
 ) -> Result<Client> {
-    let http_client = reqwest::Client::builder()
-        .cookie_store(true)
-        .build()
-        .expect("infailable");
-
-    let Secrets { secrets, app_id } = get_secrets(&http_client).await?;
+    let Secrets { secrets, app_id } = get_secrets(&connection).await?;
 
     tracing::debug!("Got login secrets");
 
-    let base_url = "https://www.qobuz.com/api.json/0.2/".to_string();
-
-    let login = login(username, password, &app_id, &base_url, &http_client).await?;
+    let login = login(username, password, &app_id, &connection).await?;
     tracing::debug!("Logged in");
 
     let active_secret =
-        find_active_secret(secrets, &base_url, &http_client, &app_id, &login.user_token).await?;
+        find_active_secret(secrets, &connection, &app_id, &login.user_token).await?;
 
     tracing::debug!("Found active secrets");
 
     let client = Client {
-        http_client,
+        connection,
         active_secret,
         user_token: login.user_token,
         user_id: login.user_id,
         app_id,
-        base_url,
         max_audio_quality,
     };
 
     Ok(client)
 }
 
+#[derive(Clone, Copy)]
 enum Endpoint {
     Album,
     ArtistPage,
@@ -116,12 +142,16 @@ enum Endpoint {
     Track,
     TrackURL,
     Playlist,
-    // PlaylistCreate,
+    PlaylistCreate,
     // PlaylistDelete,
-    // PlaylistAddTracks,
+    PlaylistAddTracks,
     // PlaylistDeleteTracks,
     // PlaylistUpdatePosition,
     Search,
+    AlbumSearch,
+    ArtistSearch,
+    TrackSearch,
+    PlaylistSearch,
     Favorites,
     FavoriteAdd,
     FavoriteRemove,
@@ -131,6 +161,7 @@ enum Endpoint {
     AlbumFeatured,
     AlbumOfTheWeek,
     PlaylistFeatured,
+    GenreList,
 }
 
 impl Display for Endpoint {
@@ -142,12 +173,16 @@ impl Display for Endpoint {
             Endpoint::SimilarArtists => "artist/getSimilarArtists",
             Endpoint::Login => "user/login",
             Endpoint::Playlist => "playlist/get",
-            // Endpoint::PlaylistCreate => "playlist/create",
+            Endpoint::PlaylistCreate => "playlist/create",
             // Endpoint::PlaylistDelete => "playlist/delete",
-            // Endpoint::PlaylistAddTracks => "playlist/addTracks",
+            Endpoint::PlaylistAddTracks => "playlist/addTracks",
             // Endpoint::PlaylistDeleteTracks => "playlist/deleteTracks",
             // Endpoint::PlaylistUpdatePosition => "playlist/updateTracksPosition",
             Endpoint::Search => "catalog/search",
+            Endpoint::AlbumSearch => "album/search",
+            Endpoint::ArtistSearch => "artist/search",
+            Endpoint::TrackSearch => "track/search",
+            Endpoint::PlaylistSearch => "playlist/search",
             Endpoint::Track => "track/get",
             Endpoint::TrackURL => "track/getFileUrl",
             Endpoint::UserPlaylist => "playlist/getUserPlaylists",
@@ -160,6 +195,7 @@ impl Display for Endpoint {
             Endpoint::AlbumFeatured => "album/getFeatured",
             Endpoint::AlbumOfTheWeek => "discover/albumOfTheWeek",
             Endpoint::PlaylistFeatured => "playlist/getFeatured",
+            Endpoint::GenreList => "genre/list",
         };
 
         f.write_str(endpoint)
@@ -175,9 +211,7 @@ macro_rules! get {
                     message: error.to_string(),
                 }),
             },
-            Err(error) => Err(Error::Api {
-                message: error.to_string(),
-            }),
+            Err(error) => Err(error),
         }
     };
 }
@@ -191,38 +225,56 @@ macro_rules! post {
                     message: error.to_string(),
                 }),
             },
-            Err(error) => Err(Error::Api {
-                message: error.to_string(),
-            }),
+            Err(error) => Err(error),
         }
     };
 }
 
 impl Client {
+    pub async fn genres(&self) -> Result<Vec<qobuz_player_models::Genre>> {
+        let endpoint = self.connection.endpoint(Endpoint::GenreList);
+        let params = vec![("offset", "0"), ("limit", "100")];
+
+        let response: GenreListResponse = get!(self, &endpoint, Some(&params))?;
+
+        Ok(response.genres.items.into_iter().map(parse_genre).collect())
+    }
+
+    /// Featured album lists, limited to a single genre when `genre_id` is set.
     pub async fn featured_albums(
         &self,
+        genre_id: Option<u32>,
     ) -> Result<Vec<(String, Vec<qobuz_player_models::AlbumSimple>)>> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::AlbumFeatured);
+        let endpoint = self.connection.endpoint(Endpoint::AlbumFeatured);
+        let genre_id = genre_id.map(|id| id.to_string());
 
         let make_call = |type_string| {
-            let params = vec![("type", type_string), ("offset", "0"), ("limit", "20")];
+            let mut params = vec![("type", type_string), ("offset", "0"), ("limit", "20")];
+            if let Some(genre_id) = &genre_id {
+                params.push(("genre_ids", genre_id.as_str()));
+            }
             let endpoint = endpoint.clone();
             async move { get!(self, &endpoint, Some(&params)) }
         };
 
-        let album_of_the_week: AlbumOfTheWeekQuery = get!(
-            self,
-            &format!("{}{}", self.base_url, Endpoint::AlbumOfTheWeek),
-            None
-        )?;
+        let mut albums = vec![];
 
-        let album_of_the_week = album_of_the_week
-            .items
-            .into_iter()
-            .map(|a| parse_album_simple(a, &self.max_audio_quality))
-            .collect();
+        // The album of the week is picked across all genres.
+        if genre_id.is_none() {
+            let album_of_the_week: AlbumOfTheWeekQuery = get!(
+                self,
+                &self.connection.endpoint(Endpoint::AlbumOfTheWeek),
+                None
+            )?;
 
-        let mut albums = vec![("Album of the week".to_string(), album_of_the_week)];
+            let album_of_the_week = album_of_the_week
+                .items
+                .into_iter()
+                .map(|a| parse_album_simple(a, &self.max_audio_quality))
+                .collect();
+
+            albums.push(("Album of the week".to_string(), album_of_the_week));
+        }
 
         let (a, b, c, d) = try_join!(
             make_call("press-awards"),
@@ -243,14 +295,20 @@ impl Client {
         Ok(albums)
     }
 
+    /// Featured playlists, limited to a single genre when `genre_id` is set.
     pub async fn featured_playlists(
         &self,
+        genre_id: Option<u32>,
     ) -> Result<Vec<(String, Vec<qobuz_player_models::Playlist>)>> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistFeatured);
+        let endpoint = self.connection.endpoint(Endpoint::PlaylistFeatured);
 
         let type_string = "editor-picks";
+        let genre_id = genre_id.map(|id| id.to_string());
 
-        let params = vec![("type", type_string), ("offset", "0"), ("limit", "20")];
+        let mut params = vec![("type", type_string), ("offset", "0"), ("limit", "20")];
+        if let Some(genre_id) = &genre_id {
+            params.push(("genre_ids", genre_id.as_str()));
+        }
 
         let response =
             get!(self, &endpoint, Some(&params)).map(|x| vec![("Editor picks".to_string(), x)])?;
@@ -263,74 +321,130 @@ impl Client {
     }
 
     pub async fn user_playlists(&self) -> Result<Vec<qobuz_player_models::Playlist>> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::UserPlaylist);
-        let params = vec![("limit", "500"), ("extra", "tracks"), ("offset", "0")];
+        let first_page = self.user_playlists_page(0).await?;
+        let total = first_page.playlists.total.max(0) as u32;
 
-        let response: UserPlaylistsResult = get!(self, &endpoint, Some(&params))?;
+        let remaining_pages = try_join_all(
+            (PAGE_SIZE..total)
+                .step_by(PAGE_SIZE as usize)
+                .map(|offset| self.user_playlists_page(offset)),
+        )
+        .await?;
 
-        Ok(response
-            .playlists
-            .items
-            .into_iter()
+        Ok(std::iter::once(first_page)
+            .chain(remaining_pages)
+            .flat_map(|page| page.playlists.items)
             .map(|playlist| parse_playlist(playlist, self.user_id, &self.max_audio_quality))
             .collect())
     }
 
-    pub async fn playlist(&self, playlist_id: u32) -> Result<qobuz_player_models::Playlist> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::Playlist);
-        let id_string = playlist_id.to_string();
+    async fn user_playlists_page(&self, offset: u32) -> Result<UserPlaylistsResult> {
+        let endpoint = self.connection.endpoint(Endpoint::UserPlaylist);
+        let limit_string = PAGE_SIZE.to_string();
+        let offset_string = offset.to_string();
         let params = vec![
-            ("limit", "500"),
+            ("limit", limit_string.as_str()),
             ("extra", "tracks"),
-            ("playlist_id", id_string.as_str()),
-            ("offset", "0"),
+            ("offset", offset_string.as_str()),
         ];
-        let response = get!(self, &endpoint, Some(&params))?;
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Fetch a playlist with all of its tracks. Qobuz returns at most 500 tracks per call,
+    /// so the remaining pages are requested concurrently once the track count is known.
+    pub async fn playlist(&self, playlist_id: u32) -> Result<qobuz_player_models::Playlist> {
+        let mut playlist = self.playlist_page(playlist_id, 0, PAGE_SIZE).await?;
+        let total = playlist.tracks_count.max(0) as u32;
+
+        let remaining_pages = try_join_all(
+            (PAGE_SIZE..total)
+                .step_by(PAGE_SIZE as usize)
+                .map(|offset| self.playlist_page(playlist_id, offset, PAGE_SIZE)),
+        )
+        .await?;
+
+        if !remaining_pages.is_empty() {
+            let missing_tracks = || Error::DeserializeJSON {
+                message: format!("playlist {playlist_id} page without tracks"),
+            };
+
+            let tracks = playlist.tracks.as_mut().ok_or_else(missing_tracks)?;
+            for page in remaining_pages {
+                tracks
+                    .items
+                    .extend(page.tracks.ok_or_else(missing_tracks)?.items);
+            }
+            tracks.offset = 0;
+            tracks.limit = tracks.items.len() as i64;
+        }
 
         Ok(parse_playlist(
-            response,
+            playlist,
             self.user_id,
             &self.max_audio_quality,
         ))
     }
 
-    // pub async fn create_playlist(
-    //     &self,
-    //     name: String,
-    //     is_public: bool,
-    //     description: Option<String>,
-    //     is_collaborative: Option<bool>,
-    // ) -> Result<Playlist> {
-    //     let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistCreate);
+    /// A single page of a playlist's tracks.
+    pub async fn playlist_tracks(
+        &self,
+        playlist_id: u32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<qobuz_player_models::Track>> {
+        let playlist = self.playlist_page(playlist_id, offset, limit).await?;
+
+        Ok(playlist.tracks.map_or(Default::default(), |tracks| {
+            tracks
+                .items
+                .into_iter()
+                .map(|track| parse_track(track, &self.max_audio_quality))
+                .collect()
+        }))
+    }
 
-    //     let mut form_data = HashMap::new();
-    //     form_data.insert("name", name.as_str());
+    async fn playlist_page(
+        &self,
+        playlist_id: u32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<playlist::Playlist> {
+        let endpoint = self.connection.endpoint(Endpoint::Playlist);
+        let id_string = playlist_id.to_string();
+        let limit_string = limit.min(PAGE_SIZE).to_string();
+        let offset_string = offset.to_string();
+        let params = vec![
+            ("limit", limit_string.as_str()),
+            ("extra", "tracks"),
+            ("playlist_id", id_string.as_str()),
+            ("offset", offset_string.as_str()),
+        ];
 
-    //     let is_collaborative = if !is_public || is_collaborative.is_none() {
-    //         "false".to_string()
-    //     } else if let Some(is_collaborative) = is_collaborative {
-    //         is_collaborative.to_string()
-    //     } else {
-    //         "false".to_string()
-    //     };
+        get!(self, &endpoint, Some(&params))
+    }
 
-    //     form_data.insert("is_collaborative", is_collaborative.as_str());
+    /// Create an empty private playlist owned by the user.
+    pub async fn create_playlist(&self, name: &str) -> Result<qobuz_player_models::Playlist> {
+        let endpoint = self.connection.endpoint(Endpoint::PlaylistCreate);
 
-    //     let is_public = is_public.to_string();
-    //     form_data.insert("is_public", is_public.as_str());
+        let mut form_data = HashMap::new();
+        form_data.insert("name", name);
+        form_data.insert("description", "");
+        form_data.insert("is_public", "false");
+        form_data.insert("is_collaborative", "false");
 
-    //     let description = if let Some(description) = description {
-    //         description
-    //     } else {
-    //         "".to_string()
-    //     };
-    //     form_data.insert("description", description.as_str());
+        let playlist: playlist::Playlist = post!(self, &endpoint, form_data)?;
 
-    //     post!(self, &endpoint, form_data)
-    // }
+        Ok(parse_playlist(
+            playlist,
+            self.user_id,
+            &self.max_audio_quality,
+        ))
+    }
 
     // pub async fn delete_playlist(&self, playlist_id: String) -> Result<SuccessfulResponse> {
-    //     let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistDelete);
+    //     let endpoint = self.connection.endpoint(Endpoint::PlaylistDelete);
 
     //     let mut form_data = HashMap::new();
     //     form_data.insert("playlist_id", playlist_id.as_str());
@@ -338,29 +452,41 @@ impl Client {
     //     post!(self, &endpoint, form_data)
     // }
 
-    // pub async fn playlist_add_track(
-    //     &self,
-    //     playlist_id: &str,
-    //     track_ids: Vec<&str>,
-    // ) -> Result<Playlist> {
-    //     let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistAddTracks);
+    /// Append tracks to a playlist owned by the user, in the given order.
+    pub async fn playlist_add_tracks(
+        &self,
+        playlist_id: u32,
+        track_ids: &[u32],
+    ) -> Result<qobuz_player_models::Playlist> {
+        let endpoint = self.connection.endpoint(Endpoint::PlaylistAddTracks);
+
+        let playlist_id = playlist_id.to_string();
+        let track_ids = track_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
 
-    //     let track_ids = track_ids.join(",");
+        let mut form_data = HashMap::new();
+        form_data.insert("playlist_id", playlist_id.as_str());
+        form_data.insert("track_ids", track_ids.as_str());
+        form_data.insert("no_duplicate", "false");
 
-    //     let mut form_data = HashMap::new();
-    //     form_data.insert("playlist_id", playlist_id);
-    //     form_data.insert("track_ids", track_ids.as_str());
-    //     form_data.insert("no_duplicate", "true");
+        let playlist: playlist::Playlist = post!(self, &endpoint, form_data)?;
 
-    //     post!(self, &endpoint, form_data)
-    // }
+        Ok(parse_playlist(
+            playlist,
+            self.user_id,
+            &self.max_audio_quality,
+        ))
+    }
 
     // pub async fn playlist_delete_track(
     //     &self,
     //     playlist_id: String,
     //     playlist_track_ids: Vec<String>,
     // ) -> Result<Playlist> {
-    //     let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistDeleteTracks);
+    //     let endpoint = self.connection.endpoint(Endpoint::PlaylistDeleteTracks);
 
     //     let playlist_track_ids = playlist_track_ids.join(",");
 
@@ -377,7 +503,7 @@ impl Client {
     //     playlist_id: &str,
     //     track_id: &str,
     // ) -> Result<Playlist> {
-    //     let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistUpdatePosition);
+    //     let endpoint = self.connection.endpoint(Endpoint::PlaylistUpdatePosition);
 
     //     let index = index.to_string();
 
@@ -393,8 +519,7 @@ impl Client {
         track_url(
             track_id,
             &self.active_secret,
-            &self.base_url,
-            &self.http_client,
+            &self.connection,
             &self.app_id,
             &self.user_token,
             &self.max_audio_quality,
@@ -405,7 +530,7 @@ impl Client {
     pub async fn favorites(&self, limit: i32) -> Result<qobuz_player_models::Favorites> {
         let mut favorite_playlists = self.user_playlists().await?;
 
-        let endpoint = format!("{}{}", self.base_url, Endpoint::Favorites);
+        let endpoint = self.connection.endpoint(Endpoint::Favorites);
 
         let limit = limit.to_string();
         let params = vec![("limit", limit.as_str())];
@@ -437,7 +562,7 @@ impl Client {
     }
 
     pub async fn add_favorite_album(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoriteAdd);
+        let endpoint = self.connection.endpoint(Endpoint::FavoriteAdd);
         let mut form_data = HashMap::new();
         form_data.insert("album_ids", id);
 
@@ -445,7 +570,7 @@ impl Client {
     }
 
     pub async fn remove_favorite_album(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoriteRemove);
+        let endpoint = self.connection.endpoint(Endpoint::FavoriteRemove);
         let mut form_data = HashMap::new();
         form_data.insert("album_ids", id);
 
@@ -453,7 +578,7 @@ impl Client {
     }
 
     pub async fn add_favorite_artist(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoriteAdd);
+        let endpoint = self.connection.endpoint(Endpoint::FavoriteAdd);
         let mut form_data = HashMap::new();
         form_data.insert("artist_ids", id);
 
@@ -461,7 +586,7 @@ impl Client {
     }
 
     pub async fn remove_favorite_artist(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoriteRemove);
+        let endpoint = self.connection.endpoint(Endpoint::FavoriteRemove);
         let mut form_data = HashMap::new();
         form_data.insert("artist_ids", id);
 
@@ -469,7 +594,7 @@ impl Client {
     }
 
     pub async fn add_favorite_playlist(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoritePlaylistAdd);
+        let endpoint = self.connection.endpoint(Endpoint::FavoritePlaylistAdd);
         let mut form_data = HashMap::new();
         form_data.insert("playlist_id", id);
 
@@ -477,7 +602,7 @@ impl Client {
     }
 
     pub async fn remove_favorite_playlist(&self, id: &str) -> Result<SuccessfulResponse> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::FavoritePlaylistRemove);
+        let endpoint = self.connection.endpoint(Endpoint::FavoritePlaylistRemove);
         let mut form_data = HashMap::new();
         form_data.insert("playlist_id", id);
 
@@ -489,7 +614,7 @@ impl Client {
         query: &str,
         limit: i32,
     ) -> Result<qobuz_player_models::SearchResults> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::Search);
+        let endpoint = self.connection.endpoint(Endpoint::Search);
         let limit = limit.to_string();
         let params = vec![("query", query), ("limit", &limit)];
 
@@ -502,8 +627,177 @@ impl Client {
         ))
     }
 
+    pub async fn search_albums(
+        &self,
+        query: &str,
+        filter: SearchFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<SearchPage<qobuz_player_models::Album>> {
+        let page = self
+            .filtered_search_page(
+                Endpoint::AlbumSearch,
+                query,
+                offset,
+                limit,
+                |response: AlbumSearchResults| {
+                    (response.query, response.albums.items, response.albums.total)
+                },
+                |album| filter.matches(album.hires_streamable, album.parental_warning),
+            )
+            .await?;
+
+        Ok(SearchPage {
+            query: page.query,
+            next_offset: page.next_offset,
+            items: page
+                .items
+                .into_iter()
+                .map(|album| parse_album(album, &self.max_audio_quality))
+                .collect(),
+        })
+    }
+
+    /// Artists have no quality or explicit flags, so no filter applies to them.
+    pub async fn search_artists(
+        &self,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<SearchPage<qobuz_player_models::Artist>> {
+        let response: ArtistSearchResults = self
+            .search_page(Endpoint::ArtistSearch, query, offset, limit)
+            .await?;
+        let artists = response.artists;
+
+        Ok(SearchPage {
+            query: response.query,
+            next_offset: next_offset(offset, artists.items.len(), artists.total),
+            items: artists.items.into_iter().map(parse_artist).collect(),
+        })
+    }
+
+    pub async fn search_tracks(
+        &self,
+        query: &str,
+        filter: SearchFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<SearchPage<qobuz_player_models::Track>> {
+        let page = self
+            .filtered_search_page(
+                Endpoint::TrackSearch,
+                query,
+                offset,
+                limit,
+                |response: TrackSearchResults| {
+                    (response.query, response.tracks.items, response.tracks.total)
+                },
+                |track| filter.matches(track.hires_streamable, track.parental_warning),
+            )
+            .await?;
+
+        Ok(SearchPage {
+            query: page.query,
+            next_offset: page.next_offset,
+            items: page
+                .items
+                .into_iter()
+                .map(|track| parse_track(track, &self.max_audio_quality))
+                .collect(),
+        })
+    }
+
+    /// Playlists have no quality or explicit flags, so no filter applies to them.
+    pub async fn search_playlists(
+        &self,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<SearchPage<qobuz_player_models::Playlist>> {
+        let response: PlaylistSearchResults = self
+            .search_page(Endpoint::PlaylistSearch, query, offset, limit)
+            .await?;
+        let playlists = response.playlists;
+
+        Ok(SearchPage {
+            query: response.query,
+            next_offset: next_offset(offset, playlists.items.len(), playlists.total),
+            items: playlists
+                .items
+                .into_iter()
+                .map(|playlist| parse_playlist(playlist, self.user_id, &self.max_audio_quality))
+                .collect(),
+        })
+    }
+
+    async fn search_page<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: Endpoint,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<T> {
+        let endpoint = self.connection.endpoint(endpoint);
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+        let params = vec![("query", query), ("offset", &offset), ("limit", &limit)];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Fetch result pages until `limit` results pass `keep`, as Qobuz can't filter searches.
+    ///
+    /// The next offset points right after the last result looked at, so the following page
+    /// continues where this one stopped. At most [`MAX_FILTERED_SEARCH_PAGES`] are fetched.
+    async fn filtered_search_page<R: serde::de::DeserializeOwned, T>(
+        &self,
+        endpoint: Endpoint,
+        query: &str,
+        offset: u32,
+        limit: u32,
+        results: impl Fn(R) -> (String, Vec<T>, i64),
+        keep: impl Fn(&T) -> bool,
+    ) -> Result<SearchPage<T>> {
+        let mut page = SearchPage {
+            query: query.to_string(),
+            items: vec![],
+            next_offset: Some(offset),
+        };
+
+        for _ in 0..MAX_FILTERED_SEARCH_PAGES {
+            let Some(page_offset) = page.next_offset else {
+                break;
+            };
+            if page.items.len() >= limit as usize {
+                break;
+            }
+
+            let response: R = self
+                .search_page(endpoint, query, page_offset, limit)
+                .await?;
+            let (response_query, items, total) = results(response);
+            page.query = response_query;
+
+            let mut looked_at = 0;
+            for item in items {
+                looked_at += 1;
+                if keep(&item) {
+                    page.items.push(item);
+                    if page.items.len() >= limit as usize {
+                        break;
+                    }
+                }
+            }
+
+            page.next_offset = next_offset(page_offset, looked_at, total);
+        }
+
+        Ok(page)
+    }
+
     pub async fn album(&self, album_id: &str) -> Result<qobuz_player_models::Album> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::Album);
+        let endpoint = self.connection.endpoint(Endpoint::Album);
         let params = vec![
             ("album_id", album_id),
             ("extra", "track_ids"),
@@ -517,7 +811,7 @@ impl Client {
     }
 
     pub async fn track(&self, track_id: u32) -> Result<qobuz_player_models::Track> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::Track);
+        let endpoint = self.connection.endpoint(Endpoint::Track);
         let track_id_string = track_id.to_string();
         let params = vec![("track_id", track_id_string.as_str())];
 
@@ -529,7 +823,7 @@ impl Client {
         &self,
         album_id: &str,
     ) -> Result<Vec<qobuz_player_models::AlbumSimple>> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::AlbumSuggest);
+        let endpoint = self.connection.endpoint(Endpoint::AlbumSuggest);
         let params = vec![("album_id", album_id)];
 
         let response: AlbumSuggestionResponse = get!(self, &endpoint, Some(&params))?;
@@ -545,7 +839,7 @@ impl Client {
     pub async fn artist(&self, artist_id: u32) -> Result<qobuz_player_models::ArtistPage> {
         let app_id = &self.app_id;
 
-        let endpoint = format!("{}{}", self.base_url, Endpoint::ArtistPage);
+        let endpoint = self.connection.endpoint(Endpoint::ArtistPage);
 
         let artistid_string = artist_id.to_string();
 
@@ -567,7 +861,7 @@ impl Client {
     ) -> Result<Vec<qobuz_player_models::Artist>> {
         let limit = limit.unwrap_or(10).to_string();
 
-        let endpoint = format!("{}{}", self.base_url, Endpoint::SimilarArtists);
+        let endpoint = self.connection.endpoint(Endpoint::SimilarArtists);
         let artistid_string = artist_id.to_string();
 
         let params = vec![
@@ -589,28 +883,60 @@ impl Client {
     pub async fn artist_releases(
         &self,
         artist_id: u32,
-        limit: Option<i32>,
-    ) -> Result<Vec<qobuz_player_models::AlbumSimple>> {
-        let endpoint = format!("{}{}", self.base_url, Endpoint::ArtistReleases);
-        let limit = limit.unwrap_or(100).to_string();
+        release_type: ReleaseType,
+        sort: ReleaseSort,
+        offset: u32,
+        limit: u32,
+    ) -> Result<ArtistReleases> {
+        let endpoint = self.connection.endpoint(Endpoint::ArtistReleases);
 
         let artistid_string = artist_id.to_string();
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+        let sort = sort.to_string();
 
         let params = vec![
             ("artist_id", artistid_string.as_str()),
             ("limit", &limit),
-            ("release_type", "album"),
-            ("sort", "release_date"),
-            ("offset", "0"),
+            ("release_type", release_type.as_str()),
+            ("sort", &sort),
+            ("offset", &offset),
             ("track_size", "1"),
         ];
 
-        let response: Result<ReleaseQuery> = get!(self, &endpoint, Some(&params));
-        let response = response?.items;
+        let response: ReleaseQuery = get!(self, &endpoint, Some(&params))?;
 
-        Ok(response
+        Ok(ArtistReleases {
+            release_type,
+            has_more: response.has_more,
+            albums: response
+                .items
+                .into_iter()
+                .map(|s| parse_album_simple(s, &self.max_audio_quality))
+                .collect(),
+        })
+    }
+
+    /// First page of every release type, leaving out types without releases.
+    pub async fn artist_discography(
+        &self,
+        artist_id: u32,
+        sort: ReleaseSort,
+        limit: u32,
+    ) -> Result<Vec<ArtistReleases>> {
+        let releases = |release_type| self.artist_releases(artist_id, release_type, sort, 0, limit);
+
+        let (albums, live, compilations, ep_singles, other) = try_join!(
+            releases(ReleaseType::Album),
+            releases(ReleaseType::Live),
+            releases(ReleaseType::Compilation),
+            releases(ReleaseType::EpSingle),
+            releases(ReleaseType::Other),
+        )?;
+
+        Ok([albums, live, compilations, ep_singles, other]
             .into_iter()
-            .map(|s| parse_album_simple(s, &self.max_audio_quality))
+            .filter(|releases| !releases.albums.is_empty())
             .collect())
     }
 
@@ -622,7 +948,7 @@ impl Client {
         make_get_call(
             endpoint,
             params,
-            &self.http_client,
+            &self.connection,
             &self.app_id,
             Some(&self.user_token),
         )
@@ -631,25 +957,14 @@ impl Client {
 
     async fn make_post_call(&self, endpoint: &str, params: HashMap<&str, &str>) -> Result<String> {
         let headers = client_headers(&self.app_id, Some(&self.user_token));
-
-        tracing::debug!("calling {} endpoint, with params {params:?}", endpoint);
-        let response = self
-            .http_client
-            .request(Method::POST, endpoint)
-            .headers(headers)
-            .form(&params)
-            .send()
-            .await?;
-
-        handle_response(response).await
+        self.connection.post(endpoint, &params, headers).await
     }
 }
 
 // Check the retrieved secrets to see which one works.
 async fn find_active_secret(
     secrets: HashMap<String, String>,
-    base_url: &str,
-    client: &reqwest::Client,
+    connection: &Connection,
     app_id: &str,
     user_token: &str,
 ) -> Result<String> {
@@ -659,8 +974,7 @@ async fn find_active_secret(
         let response = track_url(
             64868955,
             &secret,
-            base_url,
-            client,
+            connection,
             app_id,
             user_token,
             &AudioQuality::Mp3,
@@ -681,13 +995,12 @@ async fn find_active_secret(
 async fn track_url(
     track_id: u32,
     secret: &str,
-    base_url: &str,
-    client: &reqwest::Client,
+    connection: &Connection,
     app_id: &str,
     user_token: &str,
     max_audio_quality: &AudioQuality,
 ) -> Result<TrackURL> {
-    let endpoint = format!("{}{}", base_url, Endpoint::TrackURL);
+    let endpoint = connection.endpoint(Endpoint::TrackURL);
     let now = format!("{}", time::OffsetDateTime::now_utc().unix_timestamp());
 
     let sig = format!(
@@ -708,49 +1021,34 @@ async fn track_url(
         ("intent", "stream"),
     ];
 
-    match make_get_call(&endpoint, Some(&params), client, app_id, Some(user_token)).await {
+    match make_get_call(
+        &endpoint,
+        Some(&params),
+        connection,
+        app_id,
+        Some(user_token),
+    )
+    .await
+    {
         Ok(response) => match serde_json::from_str(response.as_str()) {
             Ok(item) => Ok(item),
             Err(error) => Err(Error::DeserializeJSON {
                 message: error.to_string(),
             }),
         },
-        Err(error) => Err(Error::Api {
-            message: error.to_string(),
-        }),
-    }
-}
-
-async fn handle_response(response: Response) -> Result<String> {
-    if response.status() == StatusCode::OK {
-        let res = response.text().await.unwrap_or_default();
-        Ok(res)
-    } else {
-        Err(Error::Api {
-            message: response.status().to_string(),
-        })
+        Err(error) => Err(error),
     }
 }
 
 async fn make_get_call(
     endpoint: &str,
     params: Option<&[(&str, &str)]>,
-    client: &reqwest::Client,
+    connection: &Connection,
     app_id: &str,
     user_token: Option<&str>,
 ) -> Result<String> {
     let headers = client_headers(app_id, user_token);
-
-    tracing::debug!("calling {} endpoint, with params {params:?}", endpoint);
-    let request = client.request(Method::GET, endpoint).headers(headers);
-
-    if let Some(p) = params {
-        let response = request.query(&p).send().await?;
-        handle_response(response).await
-    } else {
-        let response = request.send().await?;
-        handle_response(response).await
-    }
+    connection.get(endpoint, params, headers).await
 }
 
 fn client_headers(app_id: &str, user_token: Option<&str>) -> HeaderMap {
@@ -792,10 +1090,9 @@ async fn login(
     username: &str,
     password: &str,
     app_id: &str,
-    base_url: &str,
-    client: &reqwest::Client,
+    connection: &Connection,
 ) -> Result<LoginResult> {
-    let endpoint = format!("{}{}", base_url, Endpoint::Login);
+    let endpoint = connection.endpoint(Endpoint::Login);
 
     tracing::debug!(
         "logging in with email ({}) and password **HIDDEN** for app_id {}",
@@ -809,7 +1106,7 @@ async fn login(
         ("app_id", app_id),
     ];
 
-    match make_get_call(&endpoint, Some(&params), client, app_id, None).await {
+    match make_get_call(&endpoint, Some(&params), connection, app_id, None).await {
         Ok(response) => {
             let json: Value = serde_json::from_str(response.as_str())
                 .or(Err(Error::DeserializeJSON { message: response }))?;
@@ -845,12 +1142,9 @@ struct Secrets {
 
 // ported from https://github.com/vitiko98/qobuz-dl/blob/master/qobuz_dl/bundle.py
 // Retrieve the app_id and generate the secrets needed to authenticate
-async fn get_secrets(client: &reqwest::Client) -> Result<Secrets> {
+async fn get_secrets(connection: &Connection) -> Result<Secrets> {
     tracing::debug!("fetching login page");
-    let play_url = "https://play.qobuz.com";
-    let login_page = client.get(format!("{play_url}/login")).send().await?;
-
-    let contents = login_page.text().await.or(Err(Error::Login))?;
+    let contents = connection.get_page("/login").await?;
 
     let bundle_regex = regex::Regex::new(
         r#"<script src="(/resources/\d+\.\d+\.\d+-[a-z0-9]\d{3}/bundle\.js)"></script>"#,
@@ -870,17 +1164,15 @@ async fn get_secrets(client: &reqwest::Client) -> Result<Secrets> {
 
     let app_id = if let Some(captures) = bundle_regex.captures(contents.as_str()) {
         let bundle_path = captures.get(1).map_or("", |m| m.as_str());
-        let bundle_url = format!("{play_url}{bundle_path}");
-        if let Ok(bundle_page) = client.get(bundle_url).send().await {
-            if let Ok(bundle_contents) = bundle_page.text().await {
-                if let Some(captures) = app_id_regex.captures(bundle_contents.as_str()) {
-                    let found_app_id = captures
-                        .name("app_id")
-                        .map_or("".to_string(), |m| m.as_str().to_string());
+        if let Ok(bundle_contents) = connection.get_page(bundle_path).await {
+            if let Some(captures) = app_id_regex.captures(bundle_contents.as_str()) {
+                let found_app_id = captures
+                    .name("app_id")
+                    .map_or("".to_string(), |m| m.as_str().to_string());
 
-                    let seed_data = seed_regex.captures_iter(bundle_contents.as_str());
+                let seed_data = seed_regex.captures_iter(bundle_contents.as_str());
 
-                    seed_data.for_each(|s| {
+                seed_data.for_each(|s| {
                             let seed = s.name("seed").map_or("", |m| m.as_str()).to_string();
                             let mut timezone =
                                 s.name("timezone").map_or("", |m| m.as_str()).to_string();
@@ -911,10 +1203,7 @@ async fn get_secrets(client: &reqwest::Client) -> Result<Secrets> {
                                     secrets.insert(timezone, secret_utf8);
                                 });
                         });
-                    found_app_id
-                } else {
-                    return Err(Error::AppID);
-                }
+                found_app_id
             } else {
                 return Err(Error::AppID);
             }
@@ -990,6 +1279,12 @@ pub fn parse_featured_playlists(
         .collect()
 }
 
+/// Offset of the page after one of `count` items starting at `offset`, if there is one.
+fn next_offset(offset: u32, count: usize, total: i64) -> Option<u32> {
+    let next = offset + count as u32;
+    (count > 0 && i64::from(next) < total).then_some(next)
+}
+
 fn parse_search_results(
     search_results: SearchAllResults,
     user_id: i64,
@@ -1059,6 +1354,7 @@ fn parse_album(
     max_audio_quality: &AudioQuality,
 ) -> qobuz_player_models::Album {
     let year = extract_year(&value.release_date_original);
+    let genre = value.genre.map(|genre| genre.name);
 
     let tracks = value.tracks.map_or(Default::default(), |tracks| {
         tracks
@@ -1068,6 +1364,7 @@ fn parse_album(
                 id: t.id,
                 title: t.title,
                 number: t.track_number,
+                disc_number: t.media_number as u32,
                 explicit: t.parental_warning,
                 hires_available: t.hires_streamable,
                 available: t.streamable,
@@ -1079,6 +1376,15 @@ fn parse_album(
                 artist_id: Some(value.artist.id),
                 album_title: Some(value.title.clone()),
                 album_id: Some(value.id.clone()),
+                credits: parse_credits(t.performers.as_deref()),
+                composer: t.composer.map(|composer| composer.name),
+                work: t.work,
+                version: t.version,
+                copyright: t.copyright,
+                isrc: t.isrc,
+                release_date: t.release_date_original,
+                genre: genre.clone(),
+                audio_specs: parse_audio_specs(Some(t.maximum_bit_depth), t.maximum_sampling_rate),
             })
             .collect()
     });
@@ -1100,6 +1406,48 @@ fn parse_album(
         image_thumbnail: value.image.small,
         duration_seconds: value.duration.map_or(0, |duration| duration as u32),
         description: sanitize_html(value.description),
+        composer: value.composer.map(|composer| composer.name),
+        label: value.label.map(|label| label.name),
+        copyright: value.copyright,
+        upc: Some(value.upc).filter(|upc| !upc.is_empty()),
+        version: value.version,
+        release_date: Some(value.release_date_original).filter(|date| !date.is_empty()),
+        audio_specs: parse_audio_specs(value.maximum_bit_depth, value.maximum_sampling_rate),
+    }
+}
+
+/// Split Qobuz' performers string, `"Name, Role, Role - Name, Role"`, into credits.
+fn parse_credits(performers: Option<&str>) -> Vec<qobuz_player_models::Credit> {
+    performers
+        .unwrap_or_default()
+        .split(" - ")
+        .filter_map(|performer| {
+            let mut parts = performer.split(',').map(str::trim);
+            let name = parts.next().filter(|name| !name.is_empty())?;
+
+            Some(qobuz_player_models::Credit {
+                name: name.to_string(),
+                roles: parts
+                    .filter(|role| !role.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+fn parse_audio_specs(
+    bit_depth: Option<i64>,
+    sampling_rate: Option<f64>,
+) -> Option<qobuz_player_models::AudioSpecs> {
+    match (bit_depth, sampling_rate) {
+        (Some(bit_depth), Some(sampling_rate)) if bit_depth > 0 && sampling_rate > 0.0 => {
+            Some(qobuz_player_models::AudioSpecs {
+                bit_depth: bit_depth as u32,
+                sampling_rate,
+            })
+        }
+        _ => None,
     }
 }
 
@@ -1153,6 +1501,7 @@ fn parse_artist_page(value: artist_page::ArtistPage) -> qobuz_player_models::Art
                 qobuz_player_models::Track {
                     id: t.id,
                     number: t.physical_support.track_number,
+                    disc_number: t.physical_support.media_number,
                     title: t.title,
                     explicit: t.parental_warning,
                     hires_available: t.rights.hires_streamable,
@@ -1165,6 +1514,8 @@ fn parse_artist_page(value: artist_page::ArtistPage) -> qobuz_player_models::Art
                     artist_id: Some(value.id),
                     album_title: Some(t.album.title),
                     album_id: Some(t.album.id),
+                    isrc: Some(t.isrc),
+                    ..Default::default()
                 }
             })
             .collect(),
@@ -1172,6 +1523,13 @@ fn parse_artist_page(value: artist_page::ArtistPage) -> qobuz_player_models::Art
     }
 }
 
+fn parse_genre(value: genre::Genre) -> qobuz_player_models::Genre {
+    qobuz_player_models::Genre {
+        id: value.id,
+        name: value.name,
+    }
+}
+
 fn parse_artist(value: artist::Artist) -> qobuz_player_models::Artist {
     qobuz_player_models::Artist {
         id: value.id,
@@ -1232,6 +1590,7 @@ fn parse_track(
     qobuz_player_models::Track {
         id: value.id,
         number: value.track_number,
+        disc_number: value.media_number as u32,
         title: value.title,
         duration_seconds: value.duration,
         explicit: value.parental_warning,
@@ -1244,6 +1603,18 @@ fn parse_track(
         artist_id: artist.as_ref().map(move |a| a.id),
         album_title: value.album.as_ref().map(|a| a.title.clone()),
         album_id: value.album.as_ref().map(|a| a.id.clone()),
+        credits: parse_credits(value.performers.as_deref()),
+        composer: value.composer.map(|composer| composer.name),
+        work: value.work,
+        version: value.version,
+        copyright: value.copyright,
+        isrc: value.isrc,
+        release_date: value.release_date_original,
+        genre: value
+            .album
+            .and_then(|album| album.genre)
+            .map(|genre| genre.name),
+        audio_specs: parse_audio_specs(Some(value.maximum_bit_depth), value.maximum_sampling_rate),
     }
 }
 
@@ -1259,3 +1630,46 @@ fn hifi_available(track_has_hires_available: bool, max_audio_quality: &AudioQual
         AudioQuality::HIFI192 => true,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_performers_into_credits() {
+        let credits = parse_credits(Some(
+            "Fixture Artist, MainArtist, Composer - Jane Doe, Producer - , Engineer - John Roe",
+        ));
+
+        let credits = credits
+            .iter()
+            .map(|credit| (credit.name.as_str(), credit.roles.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            credits,
+            [
+                (
+                    "Fixture Artist",
+                    vec!["MainArtist".to_string(), "Composer".to_string()]
+                ),
+                ("Jane Doe", vec!["Producer".to_string()]),
+                ("John Roe", vec![]),
+            ]
+        );
+
+        assert!(parse_credits(None).is_empty());
+    }
+
+    #[test]
+    fn release_sorts_use_the_api_names_in_urls() {
+        for sort in [
+            ReleaseSort::ReleaseDate,
+            ReleaseSort::ReleaseDateByPriority,
+            ReleaseSort::Relevant,
+        ] {
+            let parsed: ReleaseSort =
+                serde_json::from_value(serde_json::Value::String(sort.to_string())).unwrap();
+            assert_eq!(parsed, sort);
+        }
+    }
+}
@@ -1,7 +1,14 @@
+use reqwest::StatusCode;
 use snafu::prelude::*;
 
+use crate::qobuz_models::ApiErrorResponse;
+
 pub mod client;
 pub mod qobuz_models;
+/// Fixture server for the tests of this and the other crates.
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transport;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -15,23 +22,137 @@ pub enum Error {
     Login,
     #[snafu(display("Failed to create client"))]
     Create,
-    #[snafu(display("{message}"))]
-    Api { message: String },
+    #[snafu(display("Authentication failed: {message}"))]
+    Authentication { message: String },
+    #[snafu(display("Not found: {message}"))]
+    NotFound { message: String },
+    #[snafu(display("Rate limited: {message}"))]
+    RateLimited { message: String },
+    #[snafu(display("Not available in your region: {message}"))]
+    RegionRestricted { message: String },
+    #[snafu(display("Unable to connect to Qobuz api: {message}"))]
+    Network { message: String },
+    #[snafu(display("{message} ({code})"))]
+    Api { code: u16, message: String },
     #[snafu(display("Failed to deserialize json: {message}"))]
     DeserializeJSON { message: String },
 }
 
+impl Error {
+    /// Build an error from a non-successful Qobuz reply, using the json error payload when present.
+    pub(crate) fn from_response(status: StatusCode, body: &str) -> Self {
+        let payload: Option<ApiErrorResponse> = serde_json::from_str(body).ok();
+
+        let code = payload
+            .as_ref()
+            .and_then(|payload| payload.code)
+            .unwrap_or(status.as_u16());
+
+        let message = payload
+            .and_then(|payload| payload.message)
+            .filter(|message| !message.trim().is_empty())
+            .unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string()
+            });
+
+        Self::from_code(code, message)
+    }
+
+    fn from_code(code: u16, message: String) -> Self {
+        match code {
+            // Unavailable For Legal Reasons, used for content blocked in the account's country
+            451 => Error::RegionRestricted { message },
+            // Qobuz mostly answers region restrictions with a 400 or 404 and says so in the message.
+            400 | 403 | 404 if is_region_restriction(&message) => {
+                Error::RegionRestricted { message }
+            }
+            401 | 403 => Error::Authentication { message },
+            404 => Error::NotFound { message },
+            429 => Error::RateLimited { message },
+            _ => Error::Api { code, message },
+        }
+    }
+}
+
+fn is_region_restriction(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["country", "region", "territor"]
+        .iter()
+        .any(|word| message.contains(word))
+}
+
 impl From<reqwest::Error> for Error {
     fn from(error: reqwest::Error) -> Self {
-        let status = error.status();
-
-        match status {
-            Some(status) => Error::Api {
-                message: status.to_string(),
-            },
-            None => Error::Api {
-                message: "Unable to connect to Qobuz api".to_string(),
+        match error.status() {
+            Some(status) => Error::from_code(
+                status.as_u16(),
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+            ),
+            None => Error::Network {
+                message: error.to_string(),
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_qobuz_error_payload() {
+        let body = r#"{"status":"error","code":404,"message":"No result matching given argument"}"#;
+        let error = Error::from_response(StatusCode::NOT_FOUND, body);
+
+        assert!(
+            matches!(error, Error::NotFound { message } if message == "No result matching given argument")
+        );
+    }
+
+    #[test]
+    fn classifies_region_restrictions_by_code_and_message() {
+        let body = r#"{"status":"error","code":451,"message":"Unavailable For Legal Reasons"}"#;
+        let error = Error::from_response(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, body);
+        assert!(matches!(error, Error::RegionRestricted { .. }));
+
+        let body = r#"{"status":"error","code":400,"message":"This album is not available in your country"}"#;
+        let error = Error::from_response(StatusCode::BAD_REQUEST, body);
+        assert!(
+            matches!(error, Error::RegionRestricted { message } if message == "This album is not available in your country")
+        );
+
+        let body =
+            r#"{"status":"error","code":404,"message":"Track not available in your region"}"#;
+        let error = Error::from_response(StatusCode::NOT_FOUND, body);
+        assert!(matches!(error, Error::RegionRestricted { .. }));
+
+        let body = r#"{"status":"error","code":400,"message":"Invalid time zone"}"#;
+        let error = Error::from_response(StatusCode::BAD_REQUEST, body);
+        assert!(matches!(error, Error::Api { code: 400, .. }));
+    }
+
+    #[test]
+    fn keeps_the_rate_limit_message() {
+        let body = r#"{"status":"error","code":429,"message":"Too many requests, retry in 60s"}"#;
+        let error = Error::from_response(StatusCode::TOO_MANY_REQUESTS, body);
+
+        assert!(
+            matches!(error, Error::RateLimited { message } if message == "Too many requests, retry in 60s")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_status_without_payload() {
+        let error = Error::from_response(StatusCode::UNAUTHORIZED, "<html></html>");
+        assert!(matches!(error, Error::Authentication { message } if message == "Unauthorized"));
+
+        let error = Error::from_response(StatusCode::BAD_GATEWAY, "");
+        assert!(matches!(error, Error::Api { code: 502, .. }));
+    }
+}
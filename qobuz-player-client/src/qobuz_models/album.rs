@@ -1,6 +1,7 @@
 use crate::qobuz_models::{
     Composer, Image,
     artist::{Artist, OtherArtists},
+    genre::Genre,
     track::Tracks,
 };
 use serde::{Deserialize, Serialize};
@@ -17,12 +18,14 @@ pub struct Album {
     pub displayable: bool,
     pub downloadable: bool,
     pub duration: Option<i64>,
+    pub genre: Option<Genre>,
     pub genres_list: Option<Vec<String>>,
     pub hires: bool,
     pub hires_streamable: bool,
     pub id: String,
     pub image: Image,
     pub is_official: Option<bool>,
+    pub label: Option<Label>,
     pub maximum_bit_depth: Option<i64>,
     pub maximum_channel_count: Option<i64>,
     pub maximum_sampling_rate: Option<f64>,
@@ -60,6 +63,12 @@ pub struct Album {
     pub version: Option<String>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Label {
+    pub id: i64,
+    pub name: String,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AlbumSearchResults {
     pub query: String,
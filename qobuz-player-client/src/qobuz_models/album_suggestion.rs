@@ -39,7 +39,7 @@ pub struct AlbumOfTheWeekQuery {
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ReleaseQuery {
-    has_more: bool,
+    pub has_more: bool,
     pub items: Vec<AlbumSuggestion>,
 }
 
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenreListResponse {
+    pub genres: Genres,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Genres {
+    pub limit: i64,
+    pub offset: i64,
+    pub total: i64,
+    pub items: Vec<Genre>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Genre {
+    pub id: u32,
+    pub name: String,
+    pub slug: Option<String>,
+    pub color: Option<String>,
+    pub path: Option<Vec<u32>>,
+}
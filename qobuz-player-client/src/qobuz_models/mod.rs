@@ -7,6 +7,7 @@ pub mod artist;
 pub mod artist_page;
 pub mod favorites;
 pub mod featured;
+pub mod genre;
 pub mod playlist;
 pub mod search_results;
 pub mod track;
@@ -38,10 +39,19 @@ pub struct TrackURL {
     pub bit_depth: i32,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorResponse {
+    pub status: Option<String>,
+    pub code: Option<u16>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UrlType {
     Album { id: String },
-    Playlist { id: i64 },
-    Track { id: i32 },
+    Artist { id: u32 },
+    Playlist { id: u32 },
+    Track { id: u32 },
 }
 
 #[derive(Snafu, Debug)]
@@ -58,42 +68,111 @@ pub enum UrlTypeError {
 
 pub type ParseUrlResult<T, E = UrlTypeError> = Result<T, E>;
 
+/// Parse a Qobuz share link.
+///
+/// Supports `play.qobuz.com` and `open.qobuz.com` links as well as the store pages on
+/// `www.qobuz.com/<locale>/...`, where the id is the last path segment.
 pub fn parse_url(string_url: &str) -> ParseUrlResult<UrlType> {
-    let url = url::Url::parse(string_url).map_err(|_| UrlTypeError::InvalidUrl)?;
+    let url = url::Url::parse(string_url.trim()).map_err(|_| UrlTypeError::InvalidUrl)?;
 
     let host = url.host_str().ok_or(UrlTypeError::InvalidUrl)?;
-    let mut path = url.path_segments().ok_or(UrlTypeError::InvalidUrl)?;
+    let path = url
+        .path_segments()
+        .ok_or(UrlTypeError::InvalidUrl)?
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
 
-    if host != "play.qobuz.com" && host != "open.qobuz.com" {
-        return Err(UrlTypeError::WrongDomain);
-    }
+    let (kind, id) = match host {
+        "play.qobuz.com" | "open.qobuz.com" => match path.as_slice() {
+            [kind, id, ..] => (*kind, *id),
+            _ => return Err(UrlTypeError::InvalidPath),
+        },
+        "www.qobuz.com" | "qobuz.com" => match path.as_slice() {
+            [_locale, kind, .., id] => (*kind, *id),
+            _ => return Err(UrlTypeError::InvalidPath),
+        },
+        _ => return Err(UrlTypeError::WrongDomain),
+    };
 
-    match path.next() {
-        Some("album") => {
+    match kind {
+        "album" => {
             tracing::debug!("this is an album");
-            let id = path.next().ok_or(UrlTypeError::InvalidPath)?.to_string();
-            Ok(UrlType::Album { id })
+            Ok(UrlType::Album { id: id.to_string() })
+        }
+        "artist" | "interpreter" => {
+            tracing::debug!("this is an artist");
+            Ok(UrlType::Artist { id: parse_id(id)? })
         }
-        Some("playlist") => {
+        "playlist" | "playlists" => {
             tracing::debug!("this is a playlist");
-            let id_str = path.next().ok_or(UrlTypeError::InvalidPath)?;
-            let id = id_str
-                .parse::<i64>()
-                .map_err(|_| UrlTypeError::InvalidPath)?;
-            Ok(UrlType::Playlist { id })
+            Ok(UrlType::Playlist { id: parse_id(id)? })
         }
-        Some("track") => {
+        "track" => {
             tracing::debug!("this is a track");
-            let id_str = path.next().ok_or(UrlTypeError::InvalidPath)?;
-            let id = id_str
-                .parse::<i32>()
-                .map_err(|_| UrlTypeError::InvalidPath)?;
-            Ok(UrlType::Track { id })
-        }
-        None => {
-            tracing::debug!("no path, cannot use path");
-            Err(UrlTypeError::InvalidPath)
+            Ok(UrlType::Track { id: parse_id(id)? })
         }
         _ => Err(UrlTypeError::Unknown),
     }
 }
+
+fn parse_id(id: &str) -> ParseUrlResult<u32> {
+    id.parse().map_err(|_| UrlTypeError::InvalidPath)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_player_links() {
+        assert_eq!(
+            parse_url("https://play.qobuz.com/album/jbkkqbhx0oc8a").unwrap(),
+            UrlType::Album {
+                id: "jbkkqbhx0oc8a".to_string()
+            }
+        );
+        assert_eq!(
+            parse_url("https://open.qobuz.com/track/12345678").unwrap(),
+            UrlType::Track { id: 12345678 }
+        );
+        assert_eq!(
+            parse_url("https://open.qobuz.com/playlist/4321/").unwrap(),
+            UrlType::Playlist { id: 4321 }
+        );
+        assert_eq!(
+            parse_url("https://play.qobuz.com/artist/36819").unwrap(),
+            UrlType::Artist { id: 36819 }
+        );
+    }
+
+    #[test]
+    fn parses_store_links() {
+        assert_eq!(
+            parse_url("https://www.qobuz.com/us-en/album/blue-train-john-coltrane/0060254735180")
+                .unwrap(),
+            UrlType::Album {
+                id: "0060254735180".to_string()
+            }
+        );
+        assert_eq!(
+            parse_url("https://www.qobuz.com/fr-fr/interpreter/john-coltrane/36819").unwrap(),
+            UrlType::Artist { id: 36819 }
+        );
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        assert!(matches!(
+            parse_url("https://example.com/album/123"),
+            Err(UrlTypeError::WrongDomain)
+        ));
+        assert!(matches!(
+            parse_url("https://play.qobuz.com/track/abc"),
+            Err(UrlTypeError::InvalidPath)
+        ));
+        assert!(matches!(
+            parse_url("not a link"),
+            Err(UrlTypeError::InvalidUrl)
+        ));
+    }
+}
@@ -2,6 +2,18 @@ use serde::{Deserialize, Serialize};
 
 use crate::qobuz_models::{album::Albums, artist::Artist, playlist::Playlists, track::Track};
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSearchResults {
+    pub query: String,
+    pub tracks: crate::qobuz_models::track::Tracks,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistSearchResults {
+    pub query: String,
+    pub playlists: Playlists,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchAllResults {
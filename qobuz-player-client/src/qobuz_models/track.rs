@@ -13,6 +13,7 @@ pub struct Tracks {
 pub struct Track {
     pub album: Option<Album>,
     pub audio_info: AudioInfo,
+    pub composer: Option<Performer>,
     pub copyright: Option<String>,
     pub displayable: bool,
     pub downloadable: bool,
@@ -41,6 +42,7 @@ pub struct Track {
     pub title: String,
     pub track_number: u32,
     pub version: Option<String>,
+    pub work: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,367 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    client::{AudioQuality, Client},
+    transport::{Connection, Reply, Request, Transport, TransportFuture},
+};
+use reqwest::StatusCode;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const LOGIN_PAGE: &str = include_str!("../fixtures/login.html");
+const BUNDLE: &str = include_str!("../fixtures/bundle.js");
+const USER_LOGIN: &str = include_str!("../fixtures/user_login.json");
+const TRACK_URL: &str = include_str!("../fixtures/track_get_file_url.json");
+const ALBUM: &str = include_str!("../fixtures/album_get.json");
+const GENRES: &str = include_str!("../fixtures/genre_list.json");
+const PLAYLIST_CREATE: &str = include_str!("../fixtures/playlist_create.json");
+const PLAYLIST_ADD_TRACKS: &str = include_str!("../fixtures/playlist_add_tracks.json");
+
+pub const PLAYLIST_TRACKS: u32 = 1203;
+pub const ALBUM_SEARCH_RESULTS: u32 = 6;
+
+#[derive(Debug)]
+struct Fixture {
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+/// Local http server replaying recorded Qobuz responses.
+///
+/// Serves the web player login page and bundle, the api under `/api.json/0.2/`
+/// and a short silent wav file under `/audio/track.wav`. `playlist/get` answers with
+/// pages of a generated playlist of [`PLAYLIST_TRACKS`] tracks, and `album/search` with
+/// pages of [`ALBUM_SEARCH_RESULTS`] generated albums.
+pub struct FixtureServer {
+    url: String,
+    fixtures: Arc<HashMap<String, Fixture>>,
+}
+
+impl FixtureServer {
+    pub async fn start() -> Self {
+        Self::with_track_seconds(1).await
+    }
+
+    /// A server whose tracks play for `seconds`, whatever length the track metadata says.
+    pub async fn with_track_seconds(seconds: u32) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind fixture server");
+        let url = format!("http://{}", listener.local_addr().expect("infailable"));
+
+        let mut fixtures = HashMap::new();
+        let mut text = |path: &str, content_type: &'static str, body: &str| {
+            fixtures.insert(
+                path.to_string(),
+                Fixture {
+                    content_type,
+                    body: body.replace("{server}", &url).into_bytes(),
+                },
+            );
+        };
+
+        text("/login", "text/html", LOGIN_PAGE);
+        text("/resources/7.1.2-b123/bundle.js", "text/javascript", BUNDLE);
+        text("/api.json/0.2/user/login", "application/json", USER_LOGIN);
+        text(
+            "/api.json/0.2/track/getFileUrl",
+            "application/json",
+            TRACK_URL,
+        );
+        text("/api.json/0.2/album/get", "application/json", ALBUM);
+        text("/api.json/0.2/genre/list", "application/json", GENRES);
+        text(
+            "/api.json/0.2/playlist/create",
+            "application/json",
+            PLAYLIST_CREATE,
+        );
+        text(
+            "/api.json/0.2/playlist/addTracks",
+            "application/json",
+            PLAYLIST_ADD_TRACKS,
+        );
+
+        fixtures.insert(
+            "/audio/track.wav".to_string(),
+            Fixture {
+                content_type: "audio/wav",
+                body: silent_wav(seconds),
+            },
+        );
+
+        let fixtures = Arc::new(fixtures);
+
+        let served = fixtures.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, served.clone()));
+            }
+        });
+
+        Self { url, fixtures }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// A connection to the server over http.
+    pub fn connection(&self) -> Connection {
+        Connection::new(&self.api_url(), &self.url)
+    }
+
+    /// A connection answered in process from the same fixtures, without going through http.
+    pub fn replay_connection(&self) -> Connection {
+        let transport = ReplayTransport {
+            url: self.url.clone(),
+            fixtures: self.fixtures.clone(),
+        };
+
+        Connection::with_transport(&self.api_url(), &self.url, Arc::new(transport))
+    }
+
+    fn api_url(&self) -> String {
+        format!("{}/api.json/0.2/", self.url)
+    }
+}
+
+/// Log in to the fixture server.
+pub async fn logged_in_client(server: &FixtureServer) -> Client {
+    logged_in(server.connection()).await
+}
+
+pub async fn logged_in(connection: Connection) -> Client {
+    crate::client::new("user@example.com", "password", AudioQuality::CD, connection)
+        .await
+        .expect("login against fixture server")
+}
+
+#[derive(Debug)]
+struct ReplayTransport {
+    url: String,
+    fixtures: Arc<HashMap<String, Fixture>>,
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: Request) -> TransportFuture<'_> {
+        let path = request
+            .url
+            .strip_prefix(&self.url)
+            .unwrap_or(&request.url)
+            .to_string();
+        let query = request
+            .query
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let (status, _, body) = respond(&self.fixtures, &path, &query);
+        let status = status
+            .split_whitespace()
+            .next()
+            .and_then(|code| code.parse().ok())
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .expect("fixture status");
+
+        Box::pin(async move {
+            Ok(Reply {
+                status,
+                body: String::from_utf8(body).expect("text fixture"),
+            })
+        })
+    }
+}
+
+async fn serve(mut stream: TcpStream, fixtures: Arc<HashMap<String, Fixture>>) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let target = request.split_whitespace().nth(1).unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let (status, content_type, body) = respond(&fixtures, path, query);
+
+    let head = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+        body.len()
+    );
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&body).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Status line, content type and body for a request.
+fn respond(
+    fixtures: &HashMap<String, Fixture>,
+    path: &str,
+    query: &str,
+) -> (&'static str, &'static str, Vec<u8>) {
+    match fixtures.get(path) {
+        Some(fixture) => ("200 OK", fixture.content_type, fixture.body.clone()),
+        None if path == "/api.json/0.2/playlist/get" => {
+            ("200 OK", "application/json", playlist_page(query))
+        }
+        None if path == "/api.json/0.2/album/search" => {
+            ("200 OK", "application/json", album_search_page(query))
+        }
+        None => (
+            "404 Not Found",
+            "application/json",
+            r#"{"status":"error","code":404,"message":"No fixture for this path"}"#
+                .as_bytes()
+                .to_vec(),
+        ),
+    }
+}
+
+/// The numeric query parameter `name`, or `default` without one.
+fn parameter(query: &str, name: &str, default: u32) -> u32 {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// A page of album search results. Album `n` is in hi-res unless `n % 3 == 2`
+/// and explicit when `n` is even, so only albums 1 and 3 are hi-res and clean.
+fn album_search_page(query: &str) -> Vec<u8> {
+    let offset = parameter(query, "offset", 0);
+    let limit = parameter(query, "limit", 50);
+
+    let items = (offset..(offset + limit).min(ALBUM_SEARCH_RESULTS))
+        .map(|index| {
+            let number = index + 1;
+            let hires = number % 3 != 2;
+
+            serde_json::json!({
+                "artist": { "id": 42, "name": "Fixture Artist" },
+                "displayable": true,
+                "downloadable": false,
+                "hires": hires,
+                "hires_streamable": hires,
+                "id": format!("album{number}"),
+                "label": { "id": 3, "name": "Fixture Records" },
+                "maximum_bit_depth": 24,
+                "maximum_sampling_rate": 96,
+                "image": {
+                    "small": "https://static.qobuz.com/images/covers/small.jpg",
+                    "thumbnail": "https://static.qobuz.com/images/covers/thumbnail.jpg",
+                    "large": "https://static.qobuz.com/images/covers/large.jpg"
+                },
+                "media_count": 1,
+                "parental_warning": number % 2 == 0,
+                "previewable": true,
+                "purchasable": true,
+                "qobuz_id": number,
+                "release_date_download": "2024-03-01",
+                "release_date_original": "2024-03-01",
+                "release_date_stream": "2024-03-01",
+                "sampleable": true,
+                "streamable": true,
+                "title": format!("Album {number}"),
+                "tracks_count": 2,
+                "upc": "0000000000001"
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "query": "fixture",
+        "albums": {
+            "limit": limit,
+            "offset": offset,
+            "total": ALBUM_SEARCH_RESULTS,
+            "items": items
+        }
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// A page of the generated playlist, honouring the `offset` and `limit` parameters.
+fn playlist_page(query: &str) -> Vec<u8> {
+    let offset = parameter(query, "offset", 0);
+    let limit = parameter(query, "limit", 50);
+
+    let items = (offset..(offset + limit).min(PLAYLIST_TRACKS))
+        .map(|index| {
+            serde_json::json!({
+                "id": index + 1,
+                "title": format!("Track {}", index + 1),
+                "track_number": index + 1,
+                "media_number": 1,
+                "duration": 1,
+                "hires": false,
+                "hires_streamable": false,
+                "streamable": true,
+                "displayable": true,
+                "downloadable": false,
+                "previewable": true,
+                "purchasable": true,
+                "sampleable": true,
+                "parental_warning": false,
+                "maximum_bit_depth": 16,
+                "maximum_channel_count": 2,
+                "audio_info": {}
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "owner": { "id": 7, "name": "Fixture User" },
+        "users_count": 0,
+        "is_collaborative": false,
+        "description": "",
+        "created_at": 0,
+        "duration": PLAYLIST_TRACKS,
+        "updated_at": 0,
+        "tracks_count": PLAYLIST_TRACKS,
+        "name": "Fixture Playlist",
+        "is_public": true,
+        "id": 77,
+        "tracks": {
+            "offset": offset,
+            "limit": limit,
+            "total": PLAYLIST_TRACKS,
+            "items": items
+        }
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// `seconds` of 8 kHz 16 bit mono silence.
+fn silent_wav(seconds: u32) -> Vec<u8> {
+    let sample_rate: u32 = 8000;
+    let data_len = sample_rate * 2 * seconds;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+    wav
+}
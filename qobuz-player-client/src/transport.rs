@@ -0,0 +1,199 @@
+use std::{collections::HashMap, fmt::Display, future::Future, pin::Pin, sync::Arc};
+
+use reqwest::{Method, StatusCode, header::HeaderMap};
+
+use crate::{Error, Result};
+
+pub const DEFAULT_API_URL: &str = "https://www.qobuz.com/api.json/0.2/";
+pub const DEFAULT_PLAY_URL: &str = "https://play.qobuz.com";
+
+/// A request to the Qobuz api or web player.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub query: Vec<(String, String)>,
+    pub form: Vec<(String, String)>,
+    pub headers: HeaderMap,
+}
+
+/// Status and body of a reply, whatever the status.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<Reply>> + Send + 'a>>;
+
+/// Carries requests to Qobuz and back.
+///
+/// [`HttpTransport`] goes over the network. Tests implement it to answer from recorded
+/// responses, which runs the client, and everything built on it, without Qobuz.
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    fn send(&self, request: Request) -> TransportFuture<'_>;
+}
+
+/// Sends requests over http, keeping cookies between them.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    http_client: reqwest::Client,
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        let http_client = reqwest::Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("infailable");
+
+        Self { http_client }
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut builder = self
+                .http_client
+                .request(request.method, request.url)
+                .headers(request.headers);
+
+            if !request.query.is_empty() {
+                builder = builder.query(&request.query);
+            }
+            if !request.form.is_empty() {
+                builder = builder.form(&request.form);
+            }
+
+            let response = builder.send().await?;
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            Ok(Reply { status, body })
+        })
+    }
+}
+
+/// The Qobuz api and web player base urls, and the transport requests to them go through.
+///
+/// Both base urls can be pointed at a local fixture server to run the client without Qobuz.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    transport: Arc<dyn Transport>,
+    api_url: String,
+    play_url: String,
+}
+
+impl Connection {
+    /// Connect over http.
+    pub fn new(api_url: &str, play_url: &str) -> Self {
+        Self::with_transport(api_url, play_url, Arc::new(HttpTransport::default()))
+    }
+
+    pub fn with_transport(api_url: &str, play_url: &str, transport: Arc<dyn Transport>) -> Self {
+        let api_url = match api_url.ends_with('/') {
+            true => api_url.to_string(),
+            false => format!("{api_url}/"),
+        };
+
+        Self {
+            transport,
+            api_url,
+            play_url: play_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    pub fn play_url(&self) -> &str {
+        &self.play_url
+    }
+
+    pub(crate) fn endpoint(&self, endpoint: impl Display) -> String {
+        format!("{}{}", self.api_url, endpoint)
+    }
+
+    pub(crate) async fn get(
+        &self,
+        endpoint: &str,
+        params: Option<&[(&str, &str)]>,
+        headers: HeaderMap,
+    ) -> Result<String> {
+        tracing::debug!("calling {} endpoint, with params {params:?}", endpoint);
+
+        let reply = self
+            .transport
+            .send(Request {
+                method: Method::GET,
+                url: endpoint.to_string(),
+                query: owned_pairs(params.unwrap_or_default().iter().copied()),
+                form: Vec::new(),
+                headers,
+            })
+            .await?;
+
+        handle_reply(reply)
+    }
+
+    pub(crate) async fn post(
+        &self,
+        endpoint: &str,
+        form: &HashMap<&str, &str>,
+        headers: HeaderMap,
+    ) -> Result<String> {
+        tracing::debug!("calling {} endpoint, with params {form:?}", endpoint);
+
+        let reply = self
+            .transport
+            .send(Request {
+                method: Method::POST,
+                url: endpoint.to_string(),
+                query: Vec::new(),
+                form: owned_pairs(form.iter().map(|(key, value)| (*key, *value))),
+                headers,
+            })
+            .await?;
+
+        handle_reply(reply)
+    }
+
+    /// Fetch a page from the web player, `path` is relative to the play url.
+    pub(crate) async fn get_page(&self, path: &str) -> Result<String> {
+        let reply = self
+            .transport
+            .send(Request {
+                method: Method::GET,
+                url: format!("{}{path}", self.play_url),
+                query: Vec::new(),
+                form: Vec::new(),
+                headers: HeaderMap::new(),
+            })
+            .await?;
+
+        handle_reply(reply)
+    }
+}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Self::new(DEFAULT_API_URL, DEFAULT_PLAY_URL)
+    }
+}
+
+fn owned_pairs<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> Vec<(String, String)> {
+    pairs
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn handle_reply(reply: Reply) -> Result<String> {
+    if reply.status == StatusCode::OK {
+        Ok(reply.body)
+    } else {
+        tracing::debug!("qobuz returned {}: {}", reply.status, reply.body);
+        Err(Error::from_response(reply.status, &reply.body))
+    }
+}
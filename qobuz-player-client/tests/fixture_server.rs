@@ -0,0 +1,193 @@
+use qobuz_player_client::{Error, client::AudioQuality, transport::Connection};
+use qobuz_player_models::{Album, SearchFilter, SearchPage};
+
+use qobuz_player_client::testing::{FixtureServer, PLAYLIST_TRACKS, logged_in, logged_in_client};
+
+#[tokio::test]
+async fn logs_in_and_fetches_album() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let album = client.album("fixturealbum").await.expect("album");
+
+    assert_eq!(album.title, "Fixture Album");
+    assert_eq!(album.artist.name, "Fixture Artist");
+    assert_eq!(album.release_year, 2024);
+    assert_eq!(album.tracks.len(), 2);
+    assert!(album.tracks[1].explicit);
+    assert_eq!(album.tracks[0].genre.as_deref(), Some("Rock"));
+
+    assert_eq!(album.label.as_deref(), Some("Fixture Records"));
+    assert_eq!(
+        album.audio_specs.map(|specs| specs.to_string()).as_deref(),
+        Some("24-bit / 96 kHz")
+    );
+    assert_eq!(album.upc.as_deref(), Some("0000000000001"));
+
+    let credits = &album.tracks[0].credits;
+    assert_eq!(credits.len(), 2);
+    assert_eq!(credits[0].name, "Fixture Artist");
+    assert_eq!(credits[0].roles, ["MainArtist", "Composer"]);
+    assert_eq!(credits[1].name, "Jane Doe");
+    assert_eq!(credits[1].roles, ["Producer"]);
+}
+
+#[tokio::test]
+async fn lists_genres() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let genres = client.genres().await.expect("genres");
+
+    let names = genres
+        .iter()
+        .map(|genre| genre.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Pop/Rock", "Jazz"]);
+    assert_eq!(genres[1].id, 80);
+}
+
+#[tokio::test]
+async fn serves_audio_from_track_url() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let track_url = client.track_url(1001).await.expect("track url");
+    assert!(track_url.url.starts_with(server.url()));
+
+    let audio = reqwest::get(&track_url.url)
+        .await
+        .expect("audio response")
+        .bytes()
+        .await
+        .expect("audio body");
+
+    assert_eq!(&audio[..4], b"RIFF");
+}
+
+#[tokio::test]
+async fn unknown_endpoint_maps_to_not_found() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let error = client.artist(1).await.expect_err("no fixture for artist");
+    assert!(matches!(error, Error::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn fetches_every_playlist_page() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let playlist = client.playlist(77).await.expect("playlist");
+
+    assert_eq!(playlist.tracks_count, PLAYLIST_TRACKS);
+    assert_eq!(playlist.tracks.len(), PLAYLIST_TRACKS as usize);
+    assert!(
+        playlist
+            .tracks
+            .iter()
+            .enumerate()
+            .all(|(index, track)| track.id == index as u32 + 1)
+    );
+
+    let page = client.playlist_tracks(77, 1200, 100).await.expect("page");
+    assert_eq!(page.len(), 3);
+}
+
+#[tokio::test]
+async fn filters_and_pages_album_search() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let titles = |page: &SearchPage<Album>| {
+        page.items
+            .iter()
+            .map(|album| album.title.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let page = client
+        .search_albums("fixture", SearchFilter::default(), 0, 2)
+        .await
+        .expect("search");
+    assert_eq!(titles(&page), ["Album 1", "Album 2"]);
+    assert_eq!(page.next_offset, Some(2));
+
+    let filter = SearchFilter {
+        hires_only: true,
+        hide_explicit: true,
+    };
+    let page = client
+        .search_albums("fixture", filter, 0, 2)
+        .await
+        .expect("search");
+    assert_eq!(titles(&page), ["Album 1", "Album 3"]);
+    assert_eq!(page.next_offset, Some(3));
+
+    let page = client
+        .search_albums("fixture", filter, 3, 2)
+        .await
+        .expect("search");
+    assert!(page.items.is_empty());
+    assert_eq!(page.next_offset, None);
+}
+
+#[tokio::test]
+async fn creates_playlist_and_adds_tracks() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in_client(&server).await;
+
+    let playlist = client
+        .create_playlist("Saved Queue")
+        .await
+        .expect("create playlist");
+    assert_eq!(playlist.id, 88);
+    assert_eq!(playlist.title, "Saved Queue");
+    assert!(playlist.is_owned);
+
+    let playlist = client
+        .playlist_add_tracks(playlist.id, &[1, 2])
+        .await
+        .expect("add tracks");
+    assert_eq!(playlist.tracks_count, 2);
+}
+
+#[tokio::test]
+async fn runs_against_a_replaying_transport() {
+    let server = FixtureServer::start().await;
+
+    let client = logged_in(server.replay_connection()).await;
+
+    let album = client.album("fixturealbum").await.expect("album");
+    assert_eq!(album.title, "Fixture Album");
+
+    let error = client.artist(1).await.expect_err("no fixture for artist");
+    assert!(matches!(error, Error::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn web_player_errors_fail_the_login() {
+    let server = FixtureServer::start().await;
+    let connection = Connection::new(
+        &format!("{}/api.json/0.2/", server.url()),
+        &format!("{}/missing", server.url()),
+    );
+
+    let error = qobuz_player_client::client::new(
+        "user@example.com",
+        "password",
+        AudioQuality::CD,
+        connection,
+    )
+    .await
+    .expect_err("no login page");
+    assert!(matches!(error, Error::NotFound { .. }));
+}
@@ -0,0 +1,2 @@
+ALTER TABLE "configuration" DROP COLUMN "play_url";
+ALTER TABLE "configuration" DROP COLUMN "api_url";
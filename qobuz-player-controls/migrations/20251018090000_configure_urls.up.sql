@@ -0,0 +1,2 @@
+ALTER TABLE "configuration" ADD COLUMN "api_url" TEXT;
+ALTER TABLE "configuration" ADD COLUMN "play_url" TEXT;
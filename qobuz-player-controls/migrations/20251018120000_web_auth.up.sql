@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS "web_auth" (
+    "secret_hash" text,
+    "session_key" text not null
+);
+
+CREATE TABLE IF NOT EXISTS "api_tokens" (
+    "id" integer primary key autoincrement not null,
+    "name" text not null,
+    "token_hash" text unique not null,
+    "created_at" text not null
+);
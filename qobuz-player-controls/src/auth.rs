@@ -0,0 +1,143 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng};
+use pbkdf2::Pbkdf2;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hash a web secret for storage, as a PBKDF2-SHA256 PHC string.
+pub fn hash_secret(secret: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Pbkdf2
+        .hash_password(secret.as_bytes(), &salt)
+        .expect("infailable")
+        .to_string()
+}
+
+/// Check `secret` against a hash created by [`hash_secret`].
+pub fn verify_secret(secret: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|hash| Pbkdf2.verify_password(secret.as_bytes(), &hash).is_ok())
+}
+
+/// New random token, handed out once and only stored as [`hash_token`].
+pub fn generate_token() -> String {
+    random_hex()
+}
+
+/// Tokens are long and random, so a plain digest is enough to store them.
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Random key stored to derive the session key from, see [`session_key`].
+pub fn generate_session_key() -> String {
+    random_hex()
+}
+
+/// Key signing session cookies, derived from the stored key and the secret in use. Sessions
+/// signed for another secret, such as one given on the command line earlier, are not valid.
+pub fn session_key(stored_key: &str, secret: &str) -> String {
+    sign(stored_key, secret)
+}
+
+/// Signed session value, `<expires>.<nonce>.<signature>`, valid for `lifetime`.
+pub fn new_session(key: &str, lifetime: Duration) -> String {
+    let expires = (SystemTime::now() + lifetime)
+        .duration_since(UNIX_EPOCH)
+        .expect("infailable")
+        .as_secs();
+    let payload = format!("{expires}.{}", random_hex());
+    let signature = sign(key, &payload);
+
+    format!("{payload}.{signature}")
+}
+
+/// Check the signature and expiry of a value created by [`new_session`].
+pub fn verify_session(key: &str, session: &str) -> bool {
+    let Some((payload, signature)) = session.rsplit_once('.') else {
+        return false;
+    };
+
+    if !bool::from(sign(key, payload).as_bytes().ct_eq(signature.as_bytes())) {
+        return false;
+    }
+
+    let Some(expires) = payload
+        .split_once('.')
+        .and_then(|(expires, _)| expires.parse::<u64>().ok())
+    else {
+        return false;
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("infailable")
+        .as_secs();
+
+    now < expires
+}
+
+fn sign(key: &str, payload: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("infailable");
+    mac.update(payload.as_bytes());
+    to_hex(&mac.finalize().into_bytes())
+}
+
+fn random_hex() -> String {
+    to_hex(&rand::random::<[u8; 32]>())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_hashed_secret() {
+        let hash = hash_secret("hunter2");
+
+        assert!(verify_secret("hunter2", &hash));
+        assert!(!verify_secret("hunter3", &hash));
+        assert!(!verify_secret("hunter2", "hunter2"));
+    }
+
+    #[test]
+    fn rejects_tampered_and_expired_sessions() {
+        let key = generate_session_key();
+        let session = new_session(&key, Duration::from_secs(60));
+
+        assert!(verify_session(&key, &session));
+        assert!(!verify_session(&generate_session_key(), &session));
+
+        let (_, rest) = session.split_once('.').unwrap();
+        assert!(!verify_session(&key, &format!("9999999999.{rest}")));
+
+        assert!(!verify_session(&key, &new_session(&key, Duration::ZERO)));
+    }
+
+    #[test]
+    fn session_key_changes_with_the_secret() {
+        let stored_key = generate_session_key();
+        let session = new_session(
+            &session_key(&stored_key, "hunter2"),
+            Duration::from_secs(60),
+        );
+
+        assert!(verify_session(
+            &session_key(&stored_key, "hunter2"),
+            &session
+        ));
+        assert!(!verify_session(
+            &session_key(&stored_key, "hunter3"),
+            &session
+        ));
+    }
+}
@@ -1,9 +1,14 @@
 use moka::future::Cache;
-use qobuz_player_client::{client::AudioQuality, qobuz_models::TrackURL};
+use qobuz_player_client::{
+    client::{AudioQuality, ReleaseSort},
+    qobuz_models::TrackURL,
+    transport::Connection,
+};
 use qobuz_player_models::{
-    Album, AlbumSimple, Artist, ArtistPage, Favorites, Playlist, SearchResults, Track,
+    Album, AlbumSimple, Artist, ArtistPage, ArtistReleases, Favorites, Genre, Playlist,
+    ReleaseType, SearchFilter, SearchPage, SearchResults, Suggestion, Track,
 };
-use std::sync::OnceLock;
+use std::{future::Future, pin::Pin, sync::OnceLock};
 use time::Duration;
 use tokio::sync::Mutex;
 
@@ -18,21 +23,78 @@ pub struct Client {
     username: String,
     password: String,
     max_audio_quality: AudioQuality,
+    connection: Connection,
     client_initiated: Mutex<bool>,
     favorites_cache: SimpleCache<Favorites>,
-    featured_albums_cache: SimpleCache<Vec<(String, Vec<AlbumSimple>)>>,
-    featured_playlists_cache: SimpleCache<Vec<(String, Vec<Playlist>)>>,
+    genres_cache: SimpleCache<Vec<Genre>>,
+    featured_albums_cache: Cache<Option<u32>, Vec<(String, Vec<AlbumSimple>)>>,
+    featured_playlists_cache: Cache<Option<u32>, Vec<(String, Vec<Playlist>)>>,
     album_cache: Cache<String, Album>,
     artist_cache: Cache<u32, ArtistPage>,
-    artist_albums_cache: Cache<u32, Vec<AlbumSimple>>,
+    artist_discography_cache: Cache<(u32, ReleaseSort), Vec<ArtistReleases>>,
+    artist_releases_cache: Cache<(u32, ReleaseType, ReleaseSort, u32), ArtistReleases>,
     playlist_cache: Cache<u32, Playlist>,
     similar_artists_cache: Cache<u32, Vec<Artist>>,
     suggested_albums_cache: Cache<String, Vec<AlbumSimple>>,
-    search_cache: Cache<String, SearchResults>,
+    album_search_cache: Cache<(String, SearchFilter, u32), SearchPage<Album>>,
+    artist_search_cache: Cache<(String, u32), SearchPage<Artist>>,
+    track_search_cache: Cache<(String, SearchFilter, u32), SearchPage<Track>>,
+    playlist_search_cache: Cache<(String, u32), SearchPage<Playlist>>,
+    suggestions_cache: Cache<String, Vec<Suggestion>>,
+}
+
+pub(crate) type ClientFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// The calls the player makes, implemented by [`Client`] and by a fake in tests.
+pub(crate) trait PlayerClient: Send + Sync {
+    fn track_url(&self, track_id: u32) -> ClientFuture<'_, TrackURL>;
+    fn track(&self, id: u32) -> ClientFuture<'_, Track>;
+    fn album<'a>(&'a self, id: &'a str) -> ClientFuture<'a, Album>;
+    fn artist_page(&self, id: u32) -> ClientFuture<'_, ArtistPage>;
+    fn playlist(&self, id: u32) -> ClientFuture<'_, Playlist>;
 }
 
+impl PlayerClient for Client {
+    fn track_url(&self, track_id: u32) -> ClientFuture<'_, TrackURL> {
+        Box::pin(Client::track_url(self, track_id))
+    }
+
+    fn track(&self, id: u32) -> ClientFuture<'_, Track> {
+        Box::pin(Client::track(self, id))
+    }
+
+    fn album<'a>(&'a self, id: &'a str) -> ClientFuture<'a, Album> {
+        Box::pin(Client::album(self, id))
+    }
+
+    fn artist_page(&self, id: u32) -> ClientFuture<'_, ArtistPage> {
+        Box::pin(Client::artist_page(self, id))
+    }
+
+    fn playlist(&self, id: u32) -> ClientFuture<'_, Playlist> {
+        Box::pin(Client::playlist(self, id))
+    }
+}
+
+const SEARCH_PAGE_SIZE: u32 = 20;
+const SUGGESTIONS_PER_TYPE: i32 = 3;
+const AUTOCOMPLETE_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+
 impl Client {
-    pub fn new(username: String, password: String, max_audio_quality: AudioQuality) -> Self {
+    pub fn new(
+        username: String,
+        password: String,
+        max_audio_quality: AudioQuality,
+        connection: Connection,
+    ) -> Self {
+        let featured_albums_cache = moka::future::CacheBuilder::new(100)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
+        let featured_playlists_cache = moka::future::CacheBuilder::new(100)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
         let album_cache = moka::future::CacheBuilder::new(1000)
             .time_to_live(std::time::Duration::from_secs(60 * 60 * 24 * 7))
             .build();
@@ -41,7 +103,11 @@ impl Client {
             .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
             .build();
 
-        let artist_albums_cache = moka::future::CacheBuilder::new(1000)
+        let artist_discography_cache = moka::future::CacheBuilder::new(1000)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
+        let artist_releases_cache = moka::future::CacheBuilder::new(1000)
             .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
             .build();
 
@@ -57,26 +123,49 @@ impl Client {
             .time_to_live(std::time::Duration::from_secs(60 * 60 * 24 * 7))
             .build();
 
-        let search_cache = moka::future::CacheBuilder::new(1000)
+        let album_search_cache = moka::future::CacheBuilder::new(1000)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
+        let artist_search_cache = moka::future::CacheBuilder::new(1000)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
+        let track_search_cache = moka::future::CacheBuilder::new(1000)
+            .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
+            .build();
+
+        let playlist_search_cache = moka::future::CacheBuilder::new(1000)
             .time_to_live(std::time::Duration::from_secs(60 * 60 * 24))
             .build();
 
+        let suggestions_cache = moka::future::CacheBuilder::new(1000)
+            .time_to_live(std::time::Duration::from_secs(60 * 5))
+            .build();
+
         Self {
             qobuz_client: Default::default(),
             username,
             password,
             max_audio_quality,
+            connection,
             client_initiated: Mutex::new(false),
             favorites_cache: SimpleCache::new(Duration::weeks(1)),
-            featured_albums_cache: SimpleCache::new(Duration::days(1)),
-            featured_playlists_cache: SimpleCache::new(Duration::days(1)),
+            genres_cache: SimpleCache::new(Duration::weeks(1)),
+            featured_albums_cache,
+            featured_playlists_cache,
             album_cache,
             artist_cache,
-            artist_albums_cache,
+            artist_discography_cache,
+            artist_releases_cache,
             playlist_cache,
             similar_artists_cache,
             suggested_albums_cache,
-            search_cache,
+            album_search_cache,
+            artist_search_cache,
+            track_search_cache,
+            playlist_search_cache,
+            suggestions_cache,
         }
     }
 
@@ -85,6 +174,7 @@ impl Client {
             &self.username,
             &self.password,
             self.max_audio_quality.clone(),
+            self.connection.clone(),
         )
         .await?;
 
@@ -132,18 +222,107 @@ impl Client {
         Ok(album)
     }
 
-    pub async fn search(&self, query: String) -> Result<SearchResults> {
-        if let Some(cache) = self.search_cache.get(&query).await {
+    pub async fn search_albums(
+        &self,
+        query: String,
+        filter: SearchFilter,
+        offset: u32,
+    ) -> Result<SearchPage<Album>> {
+        let key = (query, filter, offset);
+        if let Some(cache) = self.album_search_cache.get(&key).await {
+            return Ok(cache);
+        }
+
+        let client = self.get_client().await?;
+        let results = client
+            .search_albums(&key.0, filter, offset, SEARCH_PAGE_SIZE)
+            .await?;
+
+        self.album_search_cache.insert(key, results.clone()).await;
+        Ok(results)
+    }
+
+    pub async fn search_artists(&self, query: String, offset: u32) -> Result<SearchPage<Artist>> {
+        let key = (query, offset);
+        if let Some(cache) = self.artist_search_cache.get(&key).await {
+            return Ok(cache);
+        }
+
+        let client = self.get_client().await?;
+        let results = client
+            .search_artists(&key.0, offset, SEARCH_PAGE_SIZE)
+            .await?;
+
+        self.artist_search_cache.insert(key, results.clone()).await;
+        Ok(results)
+    }
+
+    pub async fn search_tracks(
+        &self,
+        query: String,
+        filter: SearchFilter,
+        offset: u32,
+    ) -> Result<SearchPage<Track>> {
+        let key = (query, filter, offset);
+        if let Some(cache) = self.track_search_cache.get(&key).await {
+            return Ok(cache);
+        }
+
+        let client = self.get_client().await?;
+        let results = client
+            .search_tracks(&key.0, filter, offset, SEARCH_PAGE_SIZE)
+            .await?;
+
+        self.track_search_cache.insert(key, results.clone()).await;
+        Ok(results)
+    }
+
+    pub async fn search_playlists(
+        &self,
+        query: String,
+        offset: u32,
+    ) -> Result<SearchPage<Playlist>> {
+        let key = (query, offset);
+        if let Some(cache) = self.playlist_search_cache.get(&key).await {
             return Ok(cache);
         }
 
         let client = self.get_client().await?;
-        let results = client.search_all(&query, 20).await?;
+        let results = client
+            .search_playlists(&key.0, offset, SEARCH_PAGE_SIZE)
+            .await?;
 
-        self.search_cache.insert(query, results.clone()).await;
+        self.playlist_search_cache
+            .insert(key, results.clone())
+            .await;
         Ok(results)
     }
 
+    /// Suggestions for a partially typed query. The request is debounced, a caller replacing
+    /// it with one for newer input drops the future and nothing is fetched.
+    pub async fn autocomplete(&self, query: String) -> Result<Vec<Suggestion>> {
+        let query = query.trim().to_lowercase();
+
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        if let Some(cache) = self.suggestions_cache.get(&query).await {
+            return Ok(cache);
+        }
+
+        tokio::time::sleep(AUTOCOMPLETE_DEBOUNCE).await;
+
+        let client = self.get_client().await?;
+        let results = client.search_all(&query, SUGGESTIONS_PER_TYPE).await?;
+        let suggestions = suggestions(results);
+
+        self.suggestions_cache
+            .insert(query, suggestions.clone())
+            .await;
+        Ok(suggestions)
+    }
+
     pub async fn artist_page(&self, id: u32) -> Result<ArtistPage> {
         if let Some(cache) = self.artist_cache.get(&id).await {
             return Ok(cache);
@@ -185,28 +364,51 @@ impl Client {
         Ok(suggested_albums)
     }
 
-    pub async fn featured_albums(&self) -> Result<Vec<(String, Vec<AlbumSimple>)>> {
-        if let Some(cache) = self.featured_albums_cache.get().await {
+    pub async fn genres(&self) -> Result<Vec<Genre>> {
+        if let Some(cache) = self.genres_cache.get().await {
+            return Ok(cache);
+        }
+
+        let client = self.get_client().await?;
+        let genres = client.genres().await?;
+
+        self.genres_cache.set(genres.clone()).await;
+
+        Ok(genres)
+    }
+
+    pub async fn featured_albums(
+        &self,
+        genre_id: Option<u32>,
+    ) -> Result<Vec<(String, Vec<AlbumSimple>)>> {
+        if let Some(cache) = self.featured_albums_cache.get(&genre_id).await {
             return Ok(cache);
         }
 
         let client = self.get_client().await?;
-        let featured = client.featured_albums().await?;
+        let featured = client.featured_albums(genre_id).await?;
 
-        self.featured_albums_cache.set(featured.clone()).await;
+        self.featured_albums_cache
+            .insert(genre_id, featured.clone())
+            .await;
 
         Ok(featured)
     }
 
-    pub async fn featured_playlists(&self) -> Result<Vec<(String, Vec<Playlist>)>> {
-        if let Some(cache) = self.featured_playlists_cache.get().await {
+    pub async fn featured_playlists(
+        &self,
+        genre_id: Option<u32>,
+    ) -> Result<Vec<(String, Vec<Playlist>)>> {
+        if let Some(cache) = self.featured_playlists_cache.get(&genre_id).await {
             return Ok(cache);
         }
 
         let client = self.get_client().await?;
-        let featured = client.featured_playlists().await?;
+        let featured = client.featured_playlists(genre_id).await?;
 
-        self.featured_playlists_cache.set(featured.clone()).await;
+        self.featured_playlists_cache
+            .insert(genre_id, featured.clone())
+            .await;
 
         Ok(featured)
     }
@@ -223,17 +425,64 @@ impl Client {
         Ok(playlist)
     }
 
-    pub async fn artist_albums(&self, id: u32) -> Result<Vec<AlbumSimple>> {
-        if let Some(cache) = self.artist_albums_cache.get(&id).await {
+    /// A page of a playlist's tracks, without fetching the whole playlist when it is not cached.
+    pub async fn playlist_tracks(&self, id: u32, offset: u32, limit: u32) -> Result<Vec<Track>> {
+        if let Some(cache) = self.playlist_cache.get(&id).await {
+            return Ok(cache
+                .tracks
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect());
+        }
+
+        let client = self.get_client().await?;
+        Ok(client.playlist_tracks(id, offset, limit).await?)
+    }
+
+    /// Releases of an artist grouped by release type.
+    pub async fn artist_discography(
+        &self,
+        id: u32,
+        sort: ReleaseSort,
+    ) -> Result<Vec<ArtistReleases>> {
+        let key = (id, sort);
+        if let Some(cache) = self.artist_discography_cache.get(&key).await {
             return Ok(cache);
         }
 
         let client = self.get_client().await?;
-        let albums = client.artist_releases(id, None).await?;
+        let discography = client.artist_discography(id, sort, 50).await?;
 
-        self.artist_albums_cache.insert(id, albums.clone()).await;
+        self.artist_discography_cache
+            .insert(key, discography.clone())
+            .await;
 
-        Ok(albums)
+        Ok(discography)
+    }
+
+    pub async fn artist_releases(
+        &self,
+        id: u32,
+        release_type: ReleaseType,
+        sort: ReleaseSort,
+        offset: u32,
+    ) -> Result<ArtistReleases> {
+        let key = (id, release_type, sort, offset);
+        if let Some(cache) = self.artist_releases_cache.get(&key).await {
+            return Ok(cache);
+        }
+
+        let client = self.get_client().await?;
+        let releases = client
+            .artist_releases(id, release_type, sort, offset, 50)
+            .await?;
+
+        self.artist_releases_cache
+            .insert(key, releases.clone())
+            .await;
+
+        Ok(releases)
     }
 
     pub async fn add_favorite_album(&self, id: &str) -> Result<()> {
@@ -278,6 +527,19 @@ impl Client {
         Ok(())
     }
 
+    /// Save tracks as a new private playlist, returning its id.
+    pub async fn create_playlist(&self, name: &str, track_ids: &[u32]) -> Result<u32> {
+        let client = self.get_client().await?;
+        let playlist = client.create_playlist(name).await?;
+
+        if !track_ids.is_empty() {
+            client.playlist_add_tracks(playlist.id, track_ids).await?;
+        }
+
+        self.favorites_cache.clear().await;
+        Ok(playlist.id)
+    }
+
     pub async fn favorites(&self) -> Result<Favorites> {
         if let Some(cache) = self.favorites_cache.get().await {
             return Ok(cache);
@@ -291,3 +553,39 @@ impl Client {
         Ok(favorites)
     }
 }
+
+fn suggestions(results: SearchResults) -> Vec<Suggestion> {
+    let artists = results
+        .artists
+        .into_iter()
+        .map(|artist| Suggestion::Artist {
+            id: artist.id,
+            name: artist.name,
+        });
+
+    let albums = results.albums.into_iter().map(|album| Suggestion::Album {
+        id: album.id,
+        title: album.title,
+        artist: album.artist.name,
+    });
+
+    let tracks = results.tracks.into_iter().map(|track| Suggestion::Track {
+        id: track.id,
+        title: track.title,
+        artist: track.artist_name,
+    });
+
+    let playlists = results
+        .playlists
+        .into_iter()
+        .map(|playlist| Suggestion::Playlist {
+            id: playlist.id,
+            title: playlist.title,
+        });
+
+    artists
+        .chain(albums)
+        .chain(tracks)
+        .chain(playlists)
+        .collect()
+}
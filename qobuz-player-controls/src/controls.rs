@@ -1,12 +1,22 @@
 use std::time::Duration;
 
+use qobuz_player_client::qobuz_models::{UrlType, parse_url};
+
+use crate::{Result, error::Error, sync::PlaybackClock, tracklist::Tracklist};
+
 #[derive(Debug)]
 pub enum ControlCommand {
     Album { id: String, index: u32 },
+    Work { album_id: String, index: u32 },
     Playlist { id: u32, index: u32, shuffle: bool },
     ArtistTopTracks { artist_id: u32, index: u32 },
     Track { id: u32 },
     SkipToPosition { new_position: u32, force: bool },
+    AddToQueue { track_id: u32 },
+    PlayNext { track_id: u32 },
+    RemoveFromQueue { entry: u64 },
+    MoveInQueue { entry: u64, to: u32 },
+    ClearUpcoming,
     Next,
     Previous,
     PlayPause,
@@ -15,7 +25,10 @@ pub enum ControlCommand {
     JumpForward,
     JumpBackward,
     Seek { time: Duration },
+    SeekRelative { offset: i64 },
     SetVolume { volume: f32 },
+    FollowTracklist { tracklist: Tracklist },
+    Align { clock: PlaybackClock },
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +70,16 @@ impl Controls {
             .expect("infailable");
     }
 
+    /// Play only the work the track at `index` of the album belongs to.
+    pub fn play_work(&self, album_id: &str, index: u32) {
+        self.tx
+            .send(ControlCommand::Work {
+                album_id: album_id.to_string(),
+                index,
+            })
+            .expect("infailable");
+    }
+
     pub fn play_playlist(&self, id: u32, index: u32, shuffle: bool) {
         self.tx
             .send(ControlCommand::Playlist { id, index, shuffle })
@@ -75,6 +98,35 @@ impl Controls {
             .expect("infailable");
     }
 
+    /// Play the album, artist, playlist or track a Qobuz share link points to.
+    pub fn play_url(&self, url: &str) -> Result<()> {
+        match parse_url(url)? {
+            UrlType::Album { id } => self.play_album(&id, 0),
+            UrlType::Artist { id } => self.play_top_tracks(id, 0),
+            UrlType::Playlist { id } => self.play_playlist(id, 0, false),
+            UrlType::Track { id } => self.play_track(id),
+        }
+
+        Ok(())
+    }
+
+    /// Queue the track a Qobuz share link points to, right after the playing track when `next`.
+    /// Returns the id of the track.
+    pub fn queue_url(&self, url: &str, next: bool) -> Result<u32> {
+        let UrlType::Track { id } = parse_url(url)? else {
+            return Err(Error::InvalidUrl {
+                message: "only track links can be queued".into(),
+            });
+        };
+
+        match next {
+            true => self.play_next(id),
+            false => self.add_to_queue(id),
+        }
+
+        Ok(id)
+    }
+
     pub fn skip_to_position(&self, index: u32, force: bool) {
         self.tx
             .send(ControlCommand::SkipToPosition {
@@ -84,6 +136,42 @@ impl Controls {
             .expect("infailable");
     }
 
+    /// Append a track to the queue, or play it when the queue is empty.
+    pub fn add_to_queue(&self, track_id: u32) {
+        self.tx
+            .send(ControlCommand::AddToQueue { track_id })
+            .expect("infailable");
+    }
+
+    /// Insert a track right after the one playing.
+    pub fn play_next(&self, track_id: u32) {
+        self.tx
+            .send(ControlCommand::PlayNext { track_id })
+            .expect("infailable");
+    }
+
+    /// Remove the queue entry `entry`, see [`Tracklist::entries`]. Removing the playing track
+    /// skips to the next one.
+    pub fn remove_from_queue(&self, entry: u64) {
+        self.tx
+            .send(ControlCommand::RemoveFromQueue { entry })
+            .expect("infailable");
+    }
+
+    /// Move the queue entry `entry` so it ends up at position `to`.
+    pub fn move_in_queue(&self, entry: u64, to: u32) {
+        self.tx
+            .send(ControlCommand::MoveInQueue { entry, to })
+            .expect("infailable");
+    }
+
+    /// Remove every track after the one playing.
+    pub fn clear_upcoming(&self) {
+        self.tx
+            .send(ControlCommand::ClearUpcoming)
+            .expect("infailable");
+    }
+
     pub fn set_volume(&self, volume: f32) {
         self.tx
             .send(ControlCommand::SetVolume { volume })
@@ -96,6 +184,13 @@ impl Controls {
             .expect("infailable");
     }
 
+    /// Seek `offset` milliseconds from the current position, negative to go back.
+    pub fn seek_relative(&self, offset: i64) {
+        self.tx
+            .send(ControlCommand::SeekRelative { offset })
+            .expect("infailable");
+    }
+
     pub fn jump_forward(&self) {
         self.tx
             .send(ControlCommand::JumpForward)
@@ -107,4 +202,18 @@ impl Controls {
             .send(ControlCommand::JumpBackward)
             .expect("infailable");
     }
+
+    /// Replace the queue with a sync group leader's.
+    pub fn follow_tracklist(&self, tracklist: Tracklist) {
+        self.tx
+            .send(ControlCommand::FollowTracklist { tracklist })
+            .expect("infailable");
+    }
+
+    /// Move playback towards a sync group leader's clock.
+    pub fn align(&self, clock: PlaybackClock) {
+        self.tx
+            .send(ControlCommand::Align { clock })
+            .expect("infailable");
+    }
 }
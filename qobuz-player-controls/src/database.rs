@@ -1,4 +1,4 @@
-use crate::{AudioQuality, Error, Result, Tracklist};
+use crate::{AudioQuality, Error, Result, Tracklist, auth};
 use serde_json::to_string;
 use sqlx::types::Json;
 use sqlx::{Pool, Sqlite, SqlitePool, sqlite::SqliteConnectOptions};
@@ -39,11 +39,24 @@ impl Database {
         Database::init(pool).await
     }
 
-    async fn init(pool: sqlx::Pool<sqlx::Sqlite>) -> Result<Self> {
+    /// A fresh database that lives as long as this value.
+    #[cfg(feature = "testing")]
+    pub async fn in_memory() -> Result<Self> {
+        // A single connection, every new connection would get its own empty in-memory database.
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+
+        Database::init(pool).await
+    }
+
+    pub(crate) async fn init(pool: sqlx::Pool<sqlx::Sqlite>) -> Result<Self> {
         sqlx::migrate!("./migrations").run(&pool).await?;
 
         create_credentials_row(&pool).await?;
         create_configuration(&pool).await?;
+        create_web_auth(&pool).await?;
 
         Ok(Self { pool })
     }
@@ -165,6 +178,36 @@ impl Database {
         Ok(())
     }
 
+    pub async fn set_api_url(&self, api_url: Option<String>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE configuration
+            SET api_url=?1
+            WHERE ROWID = 1
+            "#,
+            api_url
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_play_url(&self, play_url: Option<String>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE configuration
+            SET play_url=?1
+            WHERE ROWID = 1
+            "#,
+            play_url
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn get_credentials(&self) -> Result<DatabaseCredentials> {
         Ok(sqlx::query_as!(
             DatabaseCredentials,
@@ -189,6 +232,90 @@ impl Database {
         .await?)
     }
 
+    /// Store a hash of the web ui secret, or disable web auth with `None`.
+    ///
+    /// The session key is rotated as well, so existing sessions are logged out.
+    pub async fn set_web_secret(&self, secret: Option<String>) -> Result<()> {
+        let secret_hash = secret.map(|secret| auth::hash_secret(&secret));
+        let session_key = auth::generate_session_key();
+
+        sqlx::query!(
+            r#"
+            UPDATE web_auth
+            SET secret_hash=?1, session_key=?2
+            WHERE ROWID = 1
+            "#,
+            secret_hash,
+            session_key
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_web_auth(&self) -> Result<DatabaseWebAuth> {
+        Ok(sqlx::query_as!(
+            DatabaseWebAuth,
+            r#"
+            SELECT * FROM web_auth
+            WHERE ROWID = 1;
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Create an api token named `name`. The token is only returned here, the database keeps a hash.
+    pub async fn create_api_token(&self, name: String) -> Result<String> {
+        let token = auth::generate_token();
+        let token_hash = auth::hash_token(&token);
+        let created_at = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .expect("infailable");
+
+        sqlx::query!(
+            "INSERT INTO api_tokens (name, token_hash, created_at) VALUES (?1, ?2, ?3)",
+            name,
+            token_hash,
+            created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    pub async fn get_api_tokens(&self) -> Result<Vec<ApiToken>> {
+        Ok(sqlx::query_as!(
+            ApiToken,
+            "SELECT id, name, created_at FROM api_tokens ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn revoke_api_token(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM api_tokens WHERE id = ?1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn is_api_token_valid(&self, token: &str) -> Result<bool> {
+        let token_hash = auth::hash_token(token);
+
+        let row = sqlx::query!(
+            "SELECT id FROM api_tokens WHERE token_hash = ?1",
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
     pub async fn add_rfid_reference(
         &self,
         rfid_id: String,
@@ -336,6 +463,20 @@ pub struct DatabaseCredentials {
 
 pub struct DatabaseConfiguration {
     pub max_audio_quality: i64,
+    pub api_url: Option<String>,
+    pub play_url: Option<String>,
+}
+
+pub struct DatabaseWebAuth {
+    pub secret_hash: Option<String>,
+    pub session_key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
 }
 
 #[derive(Debug, sqlx::FromRow, serde::Deserialize)]
@@ -375,6 +516,21 @@ async fn create_configuration(pool: &Pool<Sqlite>) -> Result<()> {
     Ok(())
 }
 
+async fn create_web_auth(pool: &Pool<Sqlite>) -> Result<()> {
+    let rowid = 1;
+    let session_key = auth::generate_session_key();
+    sqlx::query!(
+        r#"
+            INSERT OR IGNORE INTO web_auth (ROWID, session_key) VALUES (?1, ?2);
+            "#,
+        rowid,
+        session_key
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,4 +574,20 @@ mod tests {
         assert_eq!(remaining, vec![new_path_str]);
         assert_eq!(deleted, vec![old_path]);
     }
+
+    #[sqlx::test]
+    async fn api_tokens(pool: sqlx::Pool<sqlx::Sqlite>) {
+        let db = Database::init(pool).await.unwrap();
+
+        let token = db.create_api_token("script".to_string()).await.unwrap();
+        assert!(db.is_api_token_valid(&token).await.unwrap());
+        assert!(!db.is_api_token_valid("not a token").await.unwrap());
+
+        let tokens = db.get_api_tokens().await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].name, "script");
+
+        db.revoke_api_token(tokens[0].id).await.unwrap();
+        assert!(!db.is_api_token_valid(&token).await.unwrap());
+    }
 }
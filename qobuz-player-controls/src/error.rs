@@ -13,6 +13,40 @@ pub enum Error {
     Client {
         message: String,
     },
+    #[snafu(display("Qobuz rejected the credentials. Check username and password. ({message})"))]
+    Unauthorized {
+        message: String,
+    },
+    #[snafu(display("Not found on Qobuz: {message}"))]
+    NotFound {
+        message: String,
+    },
+    #[snafu(display("Too many requests to Qobuz. Try again in a moment. ({message})"))]
+    RateLimited {
+        message: String,
+    },
+    #[snafu(display("Not available in your region: {message}"))]
+    RegionRestricted {
+        message: String,
+    },
+    #[snafu(display("Unable to reach Qobuz. Check your network connection."))]
+    Network,
+    #[snafu(display("Unable to open link: {message}"))]
+    InvalidUrl {
+        message: String,
+    },
+    #[snafu(display("Remote control: {message}"))]
+    RemoteControl {
+        message: String,
+    },
+    #[snafu(display("Audio output: {message}"))]
+    Output {
+        message: String,
+    },
+    #[snafu(display("Sync group: {message}"))]
+    SyncGroup {
+        message: String,
+    },
     #[snafu(display("Unable to broadcast notification"))]
     Notification,
     StreamError {
@@ -48,9 +82,19 @@ pub enum Error {
     },
     #[snafu(display("Rfid prompt input error"))]
     RfidInputPanic,
-    #[snafu(display("Port already in use: {port}"))]
-    PortInUse {
-        port: u16,
+    #[snafu(display("Unable to listen on {address}: {message}"))]
+    Bind {
+        address: String,
+        message: String,
+    },
+    #[snafu(display("Unable to set up tls: {message}"))]
+    Tls {
+        message: String,
+    },
+    #[snafu(display("Zone {zone} is unavailable: {message}"))]
+    Zone {
+        zone: String,
+        message: String,
     },
 }
 
@@ -116,7 +160,26 @@ impl From<reqwest::Error> for Error {
 
 impl From<qobuz_player_client::Error> for Error {
     fn from(value: qobuz_player_client::Error) -> Self {
-        Error::Client {
+        match value {
+            qobuz_player_client::Error::Authentication { message } => {
+                Error::Unauthorized { message }
+            }
+            qobuz_player_client::Error::NotFound { message } => Error::NotFound { message },
+            qobuz_player_client::Error::RateLimited { message } => Error::RateLimited { message },
+            qobuz_player_client::Error::RegionRestricted { message } => {
+                Error::RegionRestricted { message }
+            }
+            qobuz_player_client::Error::Network { .. } => Error::Network,
+            value => Error::Client {
+                message: value.to_string(),
+            },
+        }
+    }
+}
+
+impl From<qobuz_player_client::qobuz_models::UrlTypeError> for Error {
+    fn from(value: qobuz_player_client::qobuz_models::UrlTypeError) -> Self {
+        Self::InvalidUrl {
             message: value.to_string(),
         }
     }
@@ -1,28 +1,41 @@
 use std::time::Duration;
 
-pub use qobuz_player_client::client::AudioQuality;
+pub use qobuz_player_client::{
+    client::{AudioQuality, ReleaseSort},
+    transport::Connection,
+};
 use tokio::sync::watch;
 
-use crate::{error::Error, tracklist::Tracklist};
+use crate::{error::Error, sync::PlaybackClock, tracklist::Tracklist};
+pub mod auth;
 pub mod client;
 pub mod controls;
 pub mod database;
 pub mod error;
 pub mod notification;
+pub mod output;
 pub mod player;
+#[cfg(unix)]
+pub mod remote;
 pub(crate) mod simple_cache;
 pub mod sink;
+pub mod sync;
+#[cfg(test)]
+pub(crate) mod testing;
 pub mod timer;
 pub mod tracklist;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 pub type PositionReceiver = watch::Receiver<Duration>;
+pub type SeekedReceiver = watch::Receiver<Duration>;
 pub type VolumeReceiver = watch::Receiver<f32>;
 pub type StatusReceiver = watch::Receiver<Status>;
 pub type TracklistReceiver = watch::Receiver<Tracklist>;
+pub type ClockReceiver = watch::Receiver<PlaybackClock>;
 
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     Playing,
     Buffering,
@@ -1,6 +1,7 @@
 use tokio::sync::broadcast::{self, Receiver, Sender};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "level", content = "message", rename_all = "lowercase")]
 pub enum Notification {
     Error(String),
     Warning(String),
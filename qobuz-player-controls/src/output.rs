@@ -0,0 +1,647 @@
+use std::{
+    fs::File,
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use rodio::mixer::Mixer;
+
+use crate::{Result, error::Error};
+
+/// Sample rate of every output except the audio device, which follows the track instead.
+pub const SAMPLE_RATE: u32 = 48_000;
+pub const CHANNELS: u16 = 2;
+/// Samples are handed to backends in chunks of this length.
+const CHUNK_DURATION: Duration = Duration::from_millis(20);
+const FLAC_BLOCK_SIZE: usize = 4096;
+const WAV_BYTES_PER_SECOND: u32 = SAMPLE_RATE * CHANNELS as u32 * 2;
+/// Largest wav data chunk: the riff size, 36 bytes more, has to fit in 32 bits.
+const WAV_MAX_DATA_LENGTH: u32 = (u32::MAX - 36) / 4 * 4;
+
+/// Where the player sends audio.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Output {
+    /// The default audio device, through cpal.
+    #[default]
+    Device,
+    /// Throws the audio away at playback speed.
+    Null,
+    /// A wav or flac file, picked by extension.
+    File(PathBuf),
+    /// Raw pcm on stdout.
+    Stdout,
+    /// Raw pcm to a named pipe, e.g. a Snapcast fifo.
+    Pipe(PathBuf),
+}
+
+/// Parses `device`, `null`, `stdout`, `file:<path>` or `pipe:<path>`.
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "device" => return Ok(Self::Device),
+            "null" => return Ok(Self::Null),
+            "stdout" => return Ok(Self::Stdout),
+            _ => {}
+        }
+
+        match value.split_once(':') {
+            Some(("file", path)) => {
+                let path = PathBuf::from(path);
+                match FileFormat::from_path(&path) {
+                    Some(_) => Ok(Self::File(path)),
+                    None => Err(format!("{} is not a .wav or .flac file", path.display())),
+                }
+            }
+            Some(("pipe", path)) => Ok(Self::Pipe(PathBuf::from(path))),
+            _ => Err(format!(
+                "expected device, null, stdout, file:<path> or pipe:<path>, got {value}"
+            )),
+        }
+    }
+}
+
+impl Output {
+    /// Open the output, at `sample_rate` when it is the audio device.
+    pub(crate) fn open(&self, sample_rate: u32) -> Result<OutputStream> {
+        let backend: Box<dyn Backend> = match self {
+            // The device plays from rodio's own mixer, driven by the device's callback.
+            Output::Device => {
+                let mut stream = rodio::OutputStreamBuilder::from_default_device()?
+                    .with_sample_rate(sample_rate)
+                    .open_stream()?;
+                stream.log_on_drop(false);
+
+                return Ok(OutputStream::Device(stream));
+            }
+            Output::Null => Box::new(NullBackend),
+            Output::File(path) => file_backend(path).map_err(|err| Error::Output {
+                message: format!("unable to write {}: {err}", path.display()),
+            })?,
+            Output::Stdout => Box::new(PcmBackend::new(None)),
+            Output::Pipe(path) => Box::new(PcmBackend::new(Some(path.clone()))),
+        };
+
+        Ok(OutputStream::Backend(BackendStream::spawn(backend)))
+    }
+}
+
+fn file_backend(path: &Path) -> io::Result<Box<dyn Backend>> {
+    let file = File::create(path)?;
+
+    Ok(match FileFormat::from_path(path) {
+        Some(FileFormat::Flac) => Box::new(FlacBackend::new(file)?),
+        _ => Box::new(WavBackend::new(file)?),
+    })
+}
+
+enum FileFormat {
+    Wav,
+    Flac,
+}
+
+impl FileFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "wav" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+}
+
+/// An opened output, playing whatever is added to its mixer.
+pub(crate) enum OutputStream {
+    Device(rodio::OutputStream),
+    Backend(BackendStream),
+}
+
+impl OutputStream {
+    pub(crate) fn mixer(&self) -> &Mixer {
+        match self {
+            OutputStream::Device(stream) => stream.mixer(),
+            OutputStream::Backend(stream) => &stream.mixer,
+        }
+    }
+
+    /// Whether a track at `sample_rate` plays without reopening the output. Backends
+    /// resample everything to [`SAMPLE_RATE`].
+    pub(crate) fn plays_at(&self, sample_rate: u32) -> bool {
+        match self {
+            OutputStream::Device(stream) => stream.config().sample_rate() == sample_rate,
+            OutputStream::Backend(_) => true,
+        }
+    }
+
+    /// Files would be overwritten and pipes reopened, so only the device is closed between
+    /// tracks.
+    pub(crate) fn closes_on_clear(&self) -> bool {
+        matches!(self, OutputStream::Device(_))
+    }
+}
+
+/// Receives the mixed audio of outputs other than the audio device: interleaved stereo
+/// samples at [`SAMPLE_RATE`], in real time.
+pub trait Backend: Send + 'static {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>;
+
+    /// Called once when the output closes.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A backend fed from a mixer by its own thread, paced to playback speed.
+pub(crate) struct BackendStream {
+    mixer: Mixer,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackendStream {
+    fn spawn(mut backend: Box<dyn Backend>) -> Self {
+        let (mixer, mut source) = rodio::mixer::mixer(CHANNELS, SAMPLE_RATE);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let chunk_length = (SAMPLE_RATE as f64 * CHUNK_DURATION.as_secs_f64()) as usize
+                    * CHANNELS as usize;
+                let mut chunk = vec![0.0; chunk_length];
+                let mut frames_written = 0u64;
+                let mut failing = false;
+                let start = Instant::now();
+
+                while !stop.load(Ordering::Relaxed) {
+                    // The mixer has nothing to play between tracks, which is silence.
+                    for sample in chunk.iter_mut() {
+                        *sample = source.next().unwrap_or(0.0);
+                    }
+
+                    match backend.write(&chunk) {
+                        Ok(()) => failing = false,
+                        Err(err) => {
+                            if !failing {
+                                tracing::warn!("Unable to write audio: {err}");
+                            }
+                            failing = true;
+                        }
+                    }
+
+                    frames_written += (chunk_length / CHANNELS as usize) as u64;
+                    let due =
+                        start + Duration::from_secs_f64(frames_written as f64 / SAMPLE_RATE as f64);
+                    std::thread::sleep(due.saturating_duration_since(Instant::now()));
+                }
+
+                if let Err(err) = backend.finish() {
+                    tracing::warn!("Unable to finish audio output: {err}");
+                }
+            }
+        });
+
+        Self {
+            mixer,
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for BackendStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            _ = thread.join();
+        }
+    }
+}
+
+pub struct NullBackend;
+
+impl Backend for NullBackend {
+    fn write(&mut self, _samples: &[f32]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Signed 16 bit little endian pcm, to stdout or a named pipe.
+pub struct PcmBackend {
+    path: Option<PathBuf>,
+    writer: Option<Box<dyn Write + Send>>,
+    buffer: Vec<u8>,
+}
+
+impl PcmBackend {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            writer: None,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Backend for PcmBackend {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            // Opening a pipe waits for its reader, and is retried when the reader goes away.
+            None => self.writer.insert(match &self.path {
+                Some(path) => Box::new(std::fs::OpenOptions::new().write(true).open(path)?),
+                None => Box::new(io::stdout()),
+            }),
+        };
+
+        self.buffer.clear();
+        for sample in samples {
+            self.buffer
+                .extend_from_slice(&to_i16(*sample).to_le_bytes());
+        }
+
+        let result = writer.write_all(&self.buffer);
+        if result.is_err() {
+            self.writer = None;
+        }
+        result
+    }
+}
+
+/// 16 bit wav file. The header is patched every second of audio and when the file is
+/// closed, so the file stays playable when the player is killed.
+pub struct WavBackend {
+    file: File,
+    data_length: u32,
+    patched_length: u32,
+    buffer: Vec<u8>,
+}
+
+impl WavBackend {
+    pub fn new(mut file: File) -> io::Result<Self> {
+        let block_align = CHANNELS * 2;
+
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&36u32.to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&CHANNELS.to_le_bytes());
+        header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        header.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        file.write_all(&header)?;
+
+        Ok(Self {
+            file,
+            data_length: 0,
+            patched_length: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn patch_header(&mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file
+            .write_all(&(36 + self.data_length).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&self.data_length.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.patched_length = self.data_length;
+
+        Ok(())
+    }
+}
+
+impl Backend for WavBackend {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        self.buffer.clear();
+        for sample in samples {
+            self.buffer
+                .extend_from_slice(&to_i16(*sample).to_le_bytes());
+        }
+
+        let data_length = u32::try_from(self.buffer.len())
+            .ok()
+            .and_then(|length| self.data_length.checked_add(length))
+            .filter(|length| *length <= WAV_MAX_DATA_LENGTH)
+            .ok_or_else(|| io::Error::other("the wav file reached its 4 GiB limit"))?;
+
+        self.file.write_all(&self.buffer)?;
+        self.data_length = data_length;
+
+        if self.data_length - self.patched_length >= WAV_BYTES_PER_SECOND {
+            self.patch_header()?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.patch_header()
+    }
+}
+
+impl Drop for WavBackend {
+    fn drop(&mut self) {
+        if self.patched_length != self.data_length {
+            _ = self.patch_header();
+        }
+    }
+}
+
+/// 16 bit flac file with uncompressed (verbatim) frames. The sample count in the stream
+/// info is updated after every frame.
+pub struct FlacBackend {
+    file: File,
+    pending: Vec<i16>,
+    frame_number: u64,
+    total_frames: u64,
+}
+
+impl FlacBackend {
+    pub fn new(mut file: File) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(42);
+        header.extend_from_slice(b"fLaC");
+        // Last metadata block, stream info, 34 bytes long.
+        header.extend_from_slice(&[0x80, 0x00, 0x00, 34]);
+        header.extend_from_slice(&(FLAC_BLOCK_SIZE as u16).to_be_bytes());
+        header.extend_from_slice(&(FLAC_BLOCK_SIZE as u16).to_be_bytes());
+        // Unknown minimum and maximum frame size.
+        header.extend_from_slice(&[0; 6]);
+        header.extend_from_slice(&flac_stream_format(0).to_be_bytes());
+        // No md5 signature.
+        header.extend_from_slice(&[0; 16]);
+        file.write_all(&header)?;
+
+        Ok(Self {
+            file,
+            pending: Vec::with_capacity(FLAC_BLOCK_SIZE * CHANNELS as usize),
+            frame_number: 0,
+            total_frames: 0,
+        })
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let block_size = self.pending.len() / CHANNELS as usize;
+        if block_size == 0 {
+            return Ok(());
+        }
+
+        let mut frame = vec![0xff, 0xf8];
+        // Block size from the end of the header, sample rate from the stream info.
+        frame.push(0b0111_0000);
+        // Independent channels, 16 bits per sample.
+        frame.push((((CHANNELS - 1) as u8) << 4) | 0b1000);
+        push_utf8_number(&mut frame, self.frame_number);
+        frame.extend_from_slice(&((block_size - 1) as u16).to_be_bytes());
+        frame.push(crc8(&frame));
+
+        for channel in 0..CHANNELS as usize {
+            // Verbatim subframe without wasted bits.
+            frame.push(0b0000_0010);
+            for sample in self.pending.iter().skip(channel).step_by(CHANNELS as usize) {
+                frame.extend_from_slice(&sample.to_be_bytes());
+            }
+        }
+
+        frame.extend_from_slice(&crc16(&frame).to_be_bytes());
+        self.file.write_all(&frame)?;
+
+        self.pending.clear();
+        self.frame_number += 1;
+        self.total_frames += block_size as u64;
+
+        self.file.seek(SeekFrom::Start(18))?;
+        self.file
+            .write_all(&flac_stream_format(self.total_frames).to_be_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+
+        Ok(())
+    }
+}
+
+impl Backend for FlacBackend {
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        for sample in samples {
+            self.pending.push(to_i16(*sample));
+
+            if self.pending.len() == FLAC_BLOCK_SIZE * CHANNELS as usize {
+                self.write_frame()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_frame()
+    }
+}
+
+/// Sample rate, channels, bits per sample and total samples, as packed in the stream info.
+fn flac_stream_format(total_frames: u64) -> u64 {
+    ((SAMPLE_RATE as u64) << 44)
+        | (((CHANNELS - 1) as u64) << 41)
+        | (15 << 36)
+        | (total_frames & 0xf_ffff_ffff)
+}
+
+/// Frame numbers are coded like utf-8, extended to 36 bits.
+fn push_utf8_number(bytes: &mut Vec<u8>, number: u64) {
+    if number < 0x80 {
+        bytes.push(number as u8);
+        return;
+    }
+
+    let continuation_bytes = match number {
+        0..0x800 => 1,
+        0x800..0x10000 => 2,
+        0x10000..0x20_0000 => 3,
+        0x20_0000..0x400_0000 => 4,
+        0x400_0000..0x8000_0000 => 5,
+        _ => 6,
+    };
+
+    let marker = !(0xffu8 >> (continuation_bytes + 1));
+    bytes.push(marker | (number >> (6 * continuation_bytes)) as u8);
+    for index in (0..continuation_bytes).rev() {
+        bytes.push(0x80 | ((number >> (6 * index)) & 0x3f) as u8);
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = match crc & 0x80 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x07,
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |mut crc, byte| {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = match crc & 0x8000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x8005,
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rodio::Source;
+
+    use super::*;
+
+    fn sine(frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|frame| {
+                let sample = (frame as f32 / 48.0 * std::f32::consts::TAU).sin() * 0.5;
+                [sample, -sample]
+            })
+            .collect()
+    }
+
+    fn decode(path: &Path) -> (u32, u16, Vec<i16>) {
+        let file = File::open(path).unwrap();
+        let decoder = rodio::Decoder::try_from(file).unwrap();
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels();
+
+        (sample_rate, channels, decoder.map(to_i16).collect())
+    }
+
+    fn assert_samples_match(decoded: &[i16], written: &[f32]) {
+        assert_eq!(decoded.len(), written.len());
+        for (decoded, written) in decoded.iter().zip(written) {
+            assert!(decoded.abs_diff(to_i16(*written)) <= 1);
+        }
+    }
+
+    fn write_file(name: &str, backend: impl FnOnce(File) -> Box<dyn Backend>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "qobuz-player-output-test-{}-{name}",
+            std::process::id()
+        ));
+
+        let mut backend = backend(File::create(&path).unwrap());
+        let samples = sine(10_000);
+        backend.write(&samples[..6_000]).unwrap();
+        backend.write(&samples[6_000..]).unwrap();
+        backend.finish().unwrap();
+
+        path
+    }
+
+    #[test]
+    fn parses_outputs() {
+        assert_eq!("null".parse(), Ok(Output::Null));
+        assert_eq!(
+            "file:/tmp/out.flac".parse(),
+            Ok(Output::File(PathBuf::from("/tmp/out.flac")))
+        );
+        assert_eq!(
+            "pipe:/tmp/snapfifo".parse(),
+            Ok(Output::Pipe(PathBuf::from("/tmp/snapfifo")))
+        );
+        assert!("file:/tmp/out.mp3".parse::<Output>().is_err());
+        assert!("speaker".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn wav_file_decodes_to_written_samples() {
+        let path = write_file("sine.wav", |file| Box::new(WavBackend::new(file).unwrap()));
+
+        let (sample_rate, channels, samples) = decode(&path);
+        assert_eq!(sample_rate, SAMPLE_RATE);
+        assert_eq!(channels, CHANNELS);
+        assert_samples_match(&samples, &sine(10_000));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_header_is_patched_on_drop() {
+        let path = std::env::temp_dir().join(format!(
+            "qobuz-player-output-test-{}-dropped.wav",
+            std::process::id()
+        ));
+
+        let mut backend = WavBackend::new(File::create(&path).unwrap()).unwrap();
+        backend.write(&sine(1_000)).unwrap();
+        assert_eq!(backend.patched_length, 0);
+        drop(backend);
+
+        let (_, _, samples) = decode(&path);
+        assert_samples_match(&samples, &sine(1_000));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_file_stops_at_the_riff_limit() {
+        let path = std::env::temp_dir().join(format!(
+            "qobuz-player-output-test-{}-full.wav",
+            std::process::id()
+        ));
+
+        let mut backend = WavBackend::new(File::create(&path).unwrap()).unwrap();
+        backend.data_length = WAV_MAX_DATA_LENGTH - 4;
+        backend.patched_length = backend.data_length;
+
+        backend.write(&sine(1)).unwrap();
+        assert!(backend.write(&sine(1)).is_err());
+        assert_eq!(backend.data_length, WAV_MAX_DATA_LENGTH);
+
+        drop(backend);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn flac_file_decodes_to_written_samples() {
+        let path = write_file("sine.flac", |file| {
+            Box::new(FlacBackend::new(file).unwrap())
+        });
+
+        let (sample_rate, channels, samples) = decode(&path);
+        assert_eq!(sample_rate, SAMPLE_RATE);
+        assert_eq!(channels, CHANNELS);
+        assert_samples_match(&samples, &sine(10_000));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn frame_numbers_are_utf8_coded() {
+        let mut bytes = Vec::new();
+        push_utf8_number(&mut bytes, 0x7f);
+        push_utf8_number(&mut bytes, 0x80);
+        push_utf8_number(&mut bytes, 0x1_0000);
+        assert_eq!(bytes, [0x7f, 0xc2, 0x80, 0xf0, 0x90, 0x80, 0x80]);
+    }
+}
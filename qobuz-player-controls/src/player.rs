@@ -7,32 +7,44 @@ use tokio::{
 };
 
 use crate::{
-    PositionReceiver, Result, Status, StatusReceiver, TracklistReceiver, VolumeReceiver,
+    ClockReceiver, PositionReceiver, Result, SeekedReceiver, Status, StatusReceiver,
+    TracklistReceiver, VolumeReceiver,
     controls::{ControlCommand, Controls},
     database::Database,
-    notification::NotificationBroadcast,
-    timer::Timer,
+    notification::{Notification, NotificationBroadcast},
+    output::Output,
+    sync::{self, PlaybackClock},
+    timer::{Clock, SystemClock, Timer},
     tracklist::{SingleTracklist, TracklistType},
 };
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use crate::{
-    client::Client,
-    sink::Sink,
+    client::{Client, PlayerClient},
+    sink::{AudioSink, Sink},
     tracklist::{self, Tracklist},
 };
 
+const DEFAULT_JUMP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Difference between the output's position and the timer that is put down to output buffering.
+const TIMER_TOLERANCE: Duration = Duration::from_millis(50);
+
 pub struct Player {
     broadcast: Arc<NotificationBroadcast>,
     tracklist_tx: Sender<Tracklist>,
     tracklist_rx: Receiver<Tracklist>,
     target_status: Sender<Status>,
-    client: Arc<Client>,
-    sink: Sink,
+    client: Arc<dyn PlayerClient>,
+    sink: Box<dyn AudioSink>,
     volume: Sender<f32>,
     position_timer: Timer,
     position: Sender<Duration>,
+    seeked: Sender<Duration>,
+    clock: Sender<PlaybackClock>,
+    jump_interval: Duration,
     next_track_is_queried: bool,
+    queried_next_track_is_stale: bool,
     first_track_queried: bool,
     track_finished: Receiver<()>,
     done_buffering: Receiver<()>,
@@ -44,6 +56,7 @@ pub struct Player {
 
 impl Player {
     pub fn new(
+        output: Output,
         tracklist: Tracklist,
         client: Arc<Client>,
         volume: f32,
@@ -51,12 +64,45 @@ impl Player {
         audio_cache_dir: PathBuf,
         database: Arc<Database>,
     ) -> Result<Self> {
-        let sink = Sink::new(volume, broadcast.clone(), audio_cache_dir, database.clone())?;
+        let sink = Sink::new(
+            output,
+            volume,
+            broadcast.clone(),
+            audio_cache_dir,
+            database.clone(),
+        )?;
 
+        Ok(Self::with_parts(
+            tracklist,
+            client,
+            Box::new(sink),
+            Arc::new(SystemClock),
+            volume,
+            broadcast,
+            database,
+        ))
+    }
+
+    /// A player on any client, sink and clock, for the test harness.
+    pub(crate) fn with_parts(
+        tracklist: Tracklist,
+        client: Arc<dyn PlayerClient>,
+        sink: Box<dyn AudioSink>,
+        clock: Arc<dyn Clock>,
+        volume: f32,
+        broadcast: Arc<NotificationBroadcast>,
+        database: Arc<Database>,
+    ) -> Self {
         let track_finished = sink.track_finished();
         let done_buffering = sink.done_buffering();
 
+        let position_timer = Timer::with_clock(clock);
         let (position, _) = watch::channel(Default::default());
+        let (seeked, _) = watch::channel(Default::default());
+        let (clock, _) = watch::channel(PlaybackClock {
+            queue_position: tracklist.current_position(),
+            reference: position_timer.reference(),
+        });
         let (volume, _) = watch::channel(volume);
         let (target_status, _) = watch::channel(Default::default());
         let (tracklist_tx, tracklist_rx) = watch::channel(tracklist);
@@ -64,7 +110,7 @@ impl Player {
         let (controls_tx, controls_rx) = tokio::sync::mpsc::unbounded_channel();
         let controls = Controls::new(controls_tx);
 
-        Ok(Self {
+        Self {
             broadcast,
             tracklist_tx,
             tracklist_rx,
@@ -74,15 +120,24 @@ impl Player {
             client,
             sink,
             volume,
-            position_timer: Default::default(),
+            position_timer,
             position,
+            seeked,
+            clock,
+            jump_interval: DEFAULT_JUMP_INTERVAL,
             next_track_is_queried: false,
+            queried_next_track_is_stale: false,
             first_track_queried: false,
             track_finished,
             done_buffering,
             database,
             next_track_has_same_sample_rate: false,
-        })
+        }
+    }
+
+    /// How far jump forward and jump backward skip.
+    pub fn set_jump_interval(&mut self, interval: Duration) {
+        self.jump_interval = interval;
     }
 
     pub fn controls(&self) -> Controls {
@@ -105,6 +160,16 @@ impl Player {
         self.tracklist_tx.subscribe()
     }
 
+    /// Position in the track after every seek.
+    pub fn seeked(&self) -> SeekedReceiver {
+        self.seeked.subscribe()
+    }
+
+    /// Position together with the instant it was taken, for sync group followers.
+    pub fn clock(&self) -> ClockReceiver {
+        self.clock.subscribe()
+    }
+
     async fn play_pause(&mut self) -> Result<()> {
         let target_status = *self.target_status.borrow();
 
@@ -116,39 +181,33 @@ impl Player {
         Ok(())
     }
 
+    fn broadcast_position(&self) {
+        let reference = self.position_timer.reference();
+        self.position.send_replace(reference.position);
+        self.clock.send_replace(PlaybackClock {
+            queue_position: self.tracklist_rx.borrow().current_position(),
+            reference,
+        });
+    }
+
     fn start_timer(&mut self) {
         self.position_timer.start();
-        self.position
-            .send(self.position_timer.elapsed())
-            .expect("infailable");
+        self.broadcast_position();
     }
 
     fn pause_timer(&mut self) {
         self.position_timer.pause();
-        self.position
-            .send(self.position_timer.elapsed())
-            .expect("infailable");
+        self.broadcast_position();
     }
 
     fn stop_timer(&mut self) {
         self.position_timer.stop();
-        self.position
-            .send(self.position_timer.elapsed())
-            .expect("infailable");
-    }
-
-    fn reset_timer(&mut self) {
-        self.position_timer.reset();
-        self.position
-            .send(self.position_timer.elapsed())
-            .expect("infailable");
+        self.broadcast_position();
     }
 
     fn set_timer(&mut self, duration: Duration) {
         self.position_timer.set_time(duration);
-        self.position
-            .send(self.position_timer.elapsed())
-            .expect("infailable");
+        self.broadcast_position();
     }
 
     async fn play(&mut self) -> Result<()> {
@@ -204,12 +263,22 @@ impl Player {
         Ok(())
     }
 
+    /// Position in the playing track, as far as the output has played it.
+    fn playback_position(&self) -> Duration {
+        self.sink
+            .position()
+            .unwrap_or_else(|| self.position_timer.elapsed())
+    }
+
     fn seek(&mut self, duration: Duration) -> Result<()> {
         self.set_timer(duration);
-        self.sink.seek(duration)
+        self.sink.seek(duration)?;
+        self.seeked.send_replace(duration);
+        Ok(())
     }
 
-    fn jump_forward(&mut self) -> Result<()> {
+    /// Seek `offset` milliseconds from the current position, staying within the track.
+    fn seek_relative(&mut self, offset: i64) -> Result<()> {
         let duration = self
             .tracklist_rx
             .borrow()
@@ -217,41 +286,19 @@ impl Player {
             .map(|x| Duration::from_secs(x.duration_seconds as u64));
 
         if let Some(duration) = duration {
-            let ten_seconds = Duration::from_secs(10);
-            let next_position = self.position_timer.elapsed() + ten_seconds;
+            let position = self.playback_position().as_millis() as i64 + offset;
+            let position = Duration::from_millis(position.max(0) as u64).min(duration);
 
-            if next_position < duration {
-                self.seek(next_position)?;
-            } else {
-                self.seek(duration)?;
-            }
+            self.seek(position)?;
         }
 
         Ok(())
     }
 
-    fn jump_backward(&mut self) -> Result<()> {
-        let current_position = self.position_timer.elapsed();
-
-        if current_position.as_millis() < 10000 {
-            self.seek(Duration::default())?;
-        } else {
-            let ten_seconds = Duration::from_secs(10);
-            let seek_position = current_position - ten_seconds;
-
-            self.seek(seek_position)?;
-        }
-        Ok(())
-    }
-
     /// Skip to a specific track in the tracklist.
     async fn skip_to_position(&mut self, new_position: u32, force: bool) -> Result<()> {
-        self.stop_timer();
         let mut tracklist = self.tracklist_rx.borrow().clone();
         let current_position = tracklist.current_position();
-        self.set_target_status(Status::Buffering);
-
-        self.position.send(Default::default())?;
 
         if !force && new_position < current_position && current_position == 0 {
             self.seek(Duration::default())?;
@@ -269,22 +316,28 @@ impl Player {
             && new_position < current_position
             && total_tracks != current_position
             && new_position != 0
-            && self.position_timer.elapsed().as_millis() > 1000
+            && self.playback_position().as_millis() > 1000
         {
             self.seek(Duration::default())?;
             return Ok(());
         }
 
+        // Only a new track stops playback, restarting the playing one is a seek.
+        self.stop_timer();
+        self.set_target_status(Status::Buffering);
+
         if let Some(next_track) = tracklist.skip_to_track(new_position) {
-            self.sink.clear().await?;
+            self.sink.clear()?;
             self.next_track_is_queried = false;
+            self.queried_next_track_is_stale = false;
             self.query_track_url(next_track).await?;
             self.first_track_queried = true;
             self.start_timer();
         } else {
             tracklist.reset();
-            self.sink.clear().await?;
+            self.sink.clear()?;
             self.next_track_is_queried = false;
+            self.queried_next_track_is_stale = false;
             self.first_track_queried = false;
             self.set_target_status(Status::Paused);
             self.sink.pause();
@@ -313,10 +366,12 @@ impl Player {
         self.skip_to_position(next, false).await
     }
 
-    async fn new_queue(&mut self, tracklist: Tracklist) -> Result<()> {
+    async fn new_queue(&mut self, mut tracklist: Tracklist) -> Result<()> {
+        tracklist.number_entries_after(&self.tracklist_rx.borrow());
         self.stop_timer();
-        self.sink.clear().await?;
+        self.sink.clear()?;
         self.next_track_is_queried = false;
+        self.queried_next_track_is_stale = false;
         self.set_target_status(Status::Buffering);
 
         if let Some(first_track) = tracklist.current_track() {
@@ -333,14 +388,12 @@ impl Player {
         let mut track: Track = self.client.track(track_id).await?;
         track.status = TrackStatus::Playing;
 
-        let tracklist = Tracklist {
-            list_type: TracklistType::Track(SingleTracklist {
-                track_title: track.title.clone(),
-                album_id: track.album_id.clone(),
-                image: track.image.clone(),
-            }),
-            queue: vec![track],
-        };
+        let list_type = TracklistType::Track(SingleTracklist {
+            track_title: track.title.clone(),
+            album_id: track.album_id.clone(),
+            image: track.image.clone(),
+        });
+        let tracklist = Tracklist::with_queue(vec![track], list_type);
 
         self.new_queue(tracklist).await
     }
@@ -355,19 +408,51 @@ impl Player {
             .filter(|t| !t.available)
             .count() as u32;
 
-        let mut tracklist = Tracklist {
-            queue: album.tracks.into_iter().filter(|t| t.available).collect(),
-            list_type: TracklistType::Album(tracklist::AlbumTracklist {
+        let mut tracklist = Tracklist::with_queue(
+            album.tracks.into_iter().filter(|t| t.available).collect(),
+            TracklistType::Album(tracklist::AlbumTracklist {
                 title: album.title,
                 id: album.id,
                 image: Some(album.image),
             }),
-        };
+        );
 
         tracklist.skip_to_track(index - unstreambale_tracks_to_index);
         self.new_queue(tracklist).await
     }
 
+    async fn play_work(&mut self, album_id: &str, index: u32) -> Result<()> {
+        let album: Album = self.client.album(album_id).await?;
+
+        let Some(work) = qobuz_player_models::works(&album.tracks)
+            .into_iter()
+            .find(|work| work.tracks.contains(&(index as usize)))
+        else {
+            return Ok(());
+        };
+
+        let title = match &work.title {
+            Some(work_title) => format!("{} - {}", album.title, work_title),
+            None => album.title,
+        };
+
+        let mut tracklist = Tracklist::with_queue(
+            album.tracks[work.tracks]
+                .iter()
+                .filter(|t| t.available)
+                .cloned()
+                .collect(),
+            TracklistType::Album(tracklist::AlbumTracklist {
+                title,
+                id: album.id,
+                image: Some(album.image),
+            }),
+        );
+
+        tracklist.skip_to_track(0);
+        self.new_queue(tracklist).await
+    }
+
     async fn play_top_tracks(&mut self, artist_id: u32, index: u32) -> Result<()> {
         let artist = self.client.artist_page(artist_id).await?;
         let tracks = artist.top_tracks;
@@ -377,14 +462,14 @@ impl Player {
             .filter(|t| !t.available)
             .count() as u32;
 
-        let mut tracklist = Tracklist {
-            queue: tracks.into_iter().filter(|t| t.available).collect(),
-            list_type: TracklistType::TopTracks(tracklist::TopTracklist {
+        let mut tracklist = Tracklist::with_queue(
+            tracks.into_iter().filter(|t| t.available).collect(),
+            TracklistType::TopTracks(tracklist::TopTracklist {
                 artist_name: artist.name,
                 id: artist_id,
                 image: artist.image,
             }),
-        };
+        );
 
         tracklist.skip_to_track(index - unstreambale_tracks_to_index);
         self.new_queue(tracklist).await
@@ -410,27 +495,186 @@ impl Player {
             tracks.shuffle(&mut rand::rng());
         }
 
-        let mut tracklist = Tracklist {
-            queue: tracks,
-            list_type: TracklistType::Playlist(tracklist::PlaylistTracklist {
+        let mut tracklist = Tracklist::with_queue(
+            tracks,
+            TracklistType::Playlist(tracklist::PlaylistTracklist {
                 title: playlist.title,
                 id: playlist.id,
                 image: playlist.image,
             }),
-        };
+        );
 
         tracklist.skip_to_track(index - unstreambale_tracks_to_index);
         self.new_queue(tracklist).await
     }
 
+    /// Broadcast an edited queue. Audio already buffered for a next track that is no longer
+    /// next is dropped once the current track finishes.
+    async fn update_queue(&mut self, tracklist: Tracklist) -> Result<()> {
+        let next_track_id = self
+            .tracklist_rx
+            .borrow()
+            .next_track()
+            .map(|track| track.id);
+
+        if self.next_track_is_queried
+            && tracklist.next_track().map(|track| track.id) != next_track_id
+        {
+            self.queried_next_track_is_stale = true;
+        }
+
+        self.broadcast_tracklist(tracklist).await
+    }
+
+    async fn queue_track(&mut self, track_id: u32, next: bool) -> Result<()> {
+        if self.tracklist_rx.borrow().queue().is_empty() {
+            return self.play_track(track_id).await;
+        }
+
+        let track = self.client.track(track_id).await?;
+        if !track.available {
+            self.broadcast.send(Notification::Warning(format!(
+                "{} is not available for streaming",
+                track.title
+            )));
+            return Ok(());
+        }
+
+        let mut tracklist = self.tracklist_rx.borrow().clone();
+        match next {
+            true => tracklist.insert_next(track),
+            false => tracklist.push(track),
+        }
+
+        self.update_queue(tracklist).await
+    }
+
+    async fn remove_from_queue(&mut self, entry: u64) -> Result<()> {
+        let mut tracklist = self.tracklist_rx.borrow().clone();
+
+        // The entry is gone when the queue changed since the request was made.
+        let Some(position) = tracklist.entry_position(entry) else {
+            return Ok(());
+        };
+        let Some(removed) = tracklist.remove(position) else {
+            return Ok(());
+        };
+
+        if removed.status == TrackStatus::Playing {
+            self.broadcast_tracklist(tracklist).await?;
+            return self.skip_to_position(position, true).await;
+        }
+
+        self.update_queue(tracklist).await
+    }
+
+    async fn move_in_queue(&mut self, entry: u64, to: u32) -> Result<()> {
+        let mut tracklist = self.tracklist_rx.borrow().clone();
+
+        let Some(from) = tracklist.entry_position(entry) else {
+            return Ok(());
+        };
+
+        if tracklist.move_track(from, to) {
+            self.update_queue(tracklist).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn clear_upcoming(&mut self) -> Result<()> {
+        let mut tracklist = self.tracklist_rx.borrow().clone();
+        tracklist.clear_upcoming();
+        self.update_queue(tracklist).await
+    }
+
+    /// Take over a sync group leader's queue. A new current track is loaded paused and
+    /// started by the next [`Self::align`].
+    async fn follow_tracklist(&mut self, tracklist: Tracklist) -> Result<()> {
+        let same_track = {
+            let current = self.tracklist_rx.borrow();
+            current.current_position() == tracklist.current_position()
+                && current.current_track().map(|track| track.id)
+                    == tracklist.current_track().map(|track| track.id)
+        };
+
+        if same_track {
+            return self.update_queue(tracklist).await;
+        }
+
+        self.stop_timer();
+        self.sink.clear()?;
+        self.next_track_is_queried = false;
+        self.queried_next_track_is_stale = false;
+        self.first_track_queried = false;
+        self.set_target_status(Status::Paused);
+
+        self.broadcast_tracklist(tracklist).await
+    }
+
+    /// Follow a sync group leader's clock: start, pause and seek with it, and play slightly
+    /// faster or slower to remove small drift.
+    async fn align(&mut self, clock: PlaybackClock) -> Result<()> {
+        if clock.queue_position != self.tracklist_rx.borrow().current_position() {
+            return Ok(());
+        }
+
+        let reference = clock.reference;
+        let target = reference.position_now();
+        // Drift is measured on the samples played, the timer only follows them every tick.
+        let drift = sync::drift(self.playback_position(), target);
+        let out_of_sync = drift.unsigned_abs() > sync::MAX_DRIFT.as_micros() as u64;
+        let status = *self.target_status.borrow();
+
+        match (status, reference.running) {
+            (Status::Buffering, _) => {}
+            (Status::Paused, false) => {
+                if out_of_sync {
+                    self.seek(target)?;
+                }
+            }
+            (Status::Playing, false) => {
+                self.pause();
+                self.set_rate(1.0);
+                self.seek(target)?;
+            }
+            (Status::Paused, true) => {
+                // Playing loads the track, a seek before that has nothing to move.
+                self.play().await?;
+                self.seek(reference.position_now())?;
+            }
+            (Status::Playing, true) => {
+                if out_of_sync {
+                    self.set_rate(1.0);
+                    self.seek(target)?;
+                } else {
+                    self.set_rate(sync::correction_rate(drift));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_rate(&mut self, rate: f64) {
+        self.sink.set_speed(rate as f32);
+        self.position_timer.set_rate(rate);
+    }
+
     async fn tick(&mut self) -> Result<()> {
         if *self.target_status.borrow() != Status::Playing {
             return Ok(());
         }
 
-        let position = self.position_timer.elapsed();
+        let position = self.playback_position();
 
-        self.position.send(position)?;
+        // The timer only fills in between ticks, for the sync group clock. Move it when the
+        // output fell behind or jumped, not for every bit of output buffer jitter.
+        if position.abs_diff(self.position_timer.elapsed()) > TIMER_TOLERANCE {
+            self.set_timer(position);
+        } else {
+            self.position.send_replace(position);
+        }
 
         let duration = self
             .tracklist_rx
@@ -461,6 +705,9 @@ impl Player {
             ControlCommand::Album { id, index } => {
                 self.play_album(&id, index).await?;
             }
+            ControlCommand::Work { album_id, index } => {
+                self.play_work(&album_id, index).await?;
+            }
             ControlCommand::Playlist { id, index, shuffle } => {
                 self.play_playlist(id, index, shuffle).await?;
             }
@@ -491,25 +738,48 @@ impl Player {
             } => {
                 self.skip_to_position(new_position, force).await?;
             }
+            ControlCommand::AddToQueue { track_id } => {
+                self.queue_track(track_id, false).await?;
+            }
+            ControlCommand::PlayNext { track_id } => {
+                self.queue_track(track_id, true).await?;
+            }
+            ControlCommand::RemoveFromQueue { entry } => {
+                self.remove_from_queue(entry).await?;
+            }
+            ControlCommand::MoveInQueue { entry, to } => {
+                self.move_in_queue(entry, to).await?;
+            }
+            ControlCommand::ClearUpcoming => {
+                self.clear_upcoming().await?;
+            }
             ControlCommand::JumpForward => {
-                self.jump_forward()?;
+                self.seek_relative(self.jump_interval.as_millis() as i64)?;
             }
             ControlCommand::JumpBackward => {
-                self.jump_backward()?;
+                self.seek_relative(-(self.jump_interval.as_millis() as i64))?;
+            }
+            ControlCommand::SeekRelative { offset } => {
+                self.seek_relative(offset)?;
             }
             ControlCommand::Seek { time } => {
-                self.set_timer(time);
                 self.seek(time)?;
             }
             ControlCommand::SetVolume { volume } => {
                 self.set_volume(volume).await?;
             }
+            ControlCommand::FollowTracklist { tracklist } => {
+                self.follow_tracklist(tracklist).await?;
+            }
+            ControlCommand::Align { clock } => {
+                self.align(clock).await?;
+            }
         }
         Ok(())
     }
 
     async fn track_finished(&mut self) -> Result<()> {
-        self.reset_timer();
+        // Broadcast with the new queue below, so the clock is never sent for the wrong track.
         self.position_timer.reset();
 
         let mut tracklist = self.tracklist_rx.borrow().clone();
@@ -521,20 +791,26 @@ impl Player {
 
         match next_track {
             Some(next_track) => {
-                if !self.next_track_has_same_sample_rate {
-                    self.sink.clear().await?;
+                if !self.next_track_has_same_sample_rate || self.queried_next_track_is_stale {
+                    self.sink.clear()?;
                     self.query_track_url(next_track).await?;
                 }
             }
             None => {
+                if self.queried_next_track_is_stale {
+                    self.sink.clear()?;
+                    self.first_track_queried = false;
+                }
                 tracklist.reset();
                 self.set_target_status(Status::Paused);
                 self.sink.pause();
-                self.position_timer.stop();
+                self.stop_timer();
             }
         }
         self.next_track_is_queried = false;
+        self.queried_next_track_is_stale = false;
         self.broadcast_tracklist(tracklist).await?;
+        self.broadcast_position();
         Ok(())
     }
 
@@ -572,3 +848,265 @@ impl Player {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use qobuz_player_client::testing::FixtureServer;
+
+    use crate::{
+        Status,
+        testing::{ALBUM_ID, Harness, fixture_player, tracks, wait_for},
+    };
+
+    #[tokio::test]
+    async fn finished_tracks_advance_the_queue() {
+        let (mut player, harness) = Harness::new(tracks(3, 30)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 0);
+                harness.wait_for_status(Status::Playing).await;
+                harness.wait_for_track(0).await;
+
+                // Short tracks are close enough to their end to preload the next one right away.
+                harness.wait_for_queued(&[1, 2]).await;
+
+                harness.finish_track();
+                harness.wait_for_track(1).await;
+                harness.wait_for_queued(&[1, 2, 3]).await;
+
+                let tracklist = harness.tracklist();
+                assert_eq!(tracklist.current_track().map(|track| track.id), Some(2));
+                assert_eq!(tracklist.next_track().map(|track| track.id), Some(3));
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn previous_restarts_track_after_one_second() {
+        let (mut player, harness) = Harness::new(tracks(3, 300)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 2);
+                harness.wait_for_status(Status::Playing).await;
+                harness.wait_for_track(2).await;
+
+                harness.play_to(Duration::from_secs(2));
+                harness
+                    .wait_for_position(|position| *position >= Duration::from_secs(2))
+                    .await;
+
+                harness.controls.previous();
+                harness
+                    .wait_for_position(|position| *position < Duration::from_secs(1))
+                    .await;
+                assert_eq!(harness.tracklist().current_position(), 2);
+                assert_eq!(harness.queued(), vec![3]);
+
+                // Within a second of the restart, previous goes to the previous track.
+                harness.controls.previous();
+                harness.wait_for_track(1).await;
+                harness.wait_for_queued(&[2]).await;
+                harness.wait_for_status(Status::Playing).await;
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn restarting_the_track_keeps_playing() {
+        let (mut player, harness) = Harness::new(tracks(3, 300)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 2);
+                harness.wait_for_status(Status::Playing).await;
+                harness.wait_for_track(2).await;
+
+                harness.play_to(Duration::from_secs(5));
+                harness
+                    .wait_for_position(|position| *position >= Duration::from_secs(5))
+                    .await;
+
+                harness.controls.previous();
+                harness
+                    .wait_for_position(|position| *position < Duration::from_secs(1))
+                    .await;
+                assert_eq!(harness.status(), Status::Playing);
+
+                // The position keeps moving after the restart.
+                harness.clock.advance(Duration::from_secs(3));
+                harness.play_to(Duration::from_secs(3));
+                harness
+                    .wait_for_position(|position| *position >= Duration::from_secs(3))
+                    .await;
+                assert_eq!(harness.tracklist().current_position(), 2);
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn end_of_queue_resets_to_first_track() {
+        let (mut player, harness) = Harness::new(tracks(2, 30)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 1);
+                harness.wait_for_status(Status::Playing).await;
+                harness.wait_for_track(1).await;
+                harness.play_to(Duration::from_secs(10));
+
+                harness.finish_track();
+                harness.wait_for_status(Status::Paused).await;
+                harness.wait_for_track(0).await;
+
+                assert_eq!(harness.position(), Duration::ZERO);
+                assert_eq!(
+                    harness.tracklist().current_track().map(|track| track.id),
+                    Some(1)
+                );
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn tick_preloads_next_track_near_the_end() {
+        let (mut player, harness) = Harness::new(tracks(2, 300)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 0);
+                harness.wait_for_status(Status::Playing).await;
+                harness.wait_for_queued(&[1]).await;
+
+                harness.play_to(Duration::from_secs(200));
+                harness
+                    .wait_for_position(|position| *position >= Duration::from_secs(200))
+                    .await;
+                assert_eq!(harness.queued(), vec![1]);
+
+                harness.play_to(Duration::from_secs(241));
+                harness.wait_for_queued(&[1, 2]).await;
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn position_follows_the_output_not_the_wall_clock() {
+        let (mut player, harness) = Harness::new(tracks(1, 300)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 0);
+                harness.wait_for_status(Status::Playing).await;
+
+                // The output stalled: ten seconds passed, three were played.
+                harness.clock.advance(Duration::from_secs(10));
+                harness.play_to(Duration::from_secs(3));
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(3))
+                    .await;
+
+                harness.clock.advance(Duration::from_secs(1));
+                harness.play_to(Duration::from_secs(4));
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(4))
+                    .await;
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn jumps_are_relative_to_the_output_position() {
+        let (mut player, harness) = Harness::new(tracks(1, 300)).await;
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 0);
+                harness.wait_for_status(Status::Playing).await;
+
+                harness.clock.advance(Duration::from_secs(60));
+                harness.play_to(Duration::from_secs(20));
+
+                harness.controls.jump_forward();
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(30))
+                    .await;
+                assert_eq!(harness.sink_position(), Some(Duration::from_secs(30)));
+
+                harness.controls.jump_backward();
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(20))
+                    .await;
+                assert_eq!(harness.sink_position(), Some(Duration::from_secs(20)));
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn seeks_relative_within_the_track() {
+        let (mut player, harness) = Harness::new(tracks(1, 300)).await;
+        player.set_jump_interval(Duration::from_secs(30));
+        let seeked = player.seeked();
+
+        harness
+            .run(&mut player, async {
+                harness.controls.play_album(ALBUM_ID, 0);
+                harness.wait_for_status(Status::Playing).await;
+
+                harness.play_to(Duration::from_secs(20));
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(20))
+                    .await;
+
+                harness.controls.seek_relative(-25_000);
+                harness
+                    .wait_for_position(|position| *position == Duration::ZERO)
+                    .await;
+                assert_eq!(*seeked.borrow(), Duration::ZERO);
+
+                harness.controls.jump_forward();
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(30))
+                    .await;
+                assert_eq!(*seeked.borrow(), Duration::from_secs(30));
+
+                harness.controls.seek_relative(1_000_000);
+                harness
+                    .wait_for_position(|position| *position == Duration::from_secs(300))
+                    .await;
+                assert_eq!(harness.sink_position(), Some(Duration::from_secs(300)));
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn plays_an_album_from_the_fixture_server() {
+        let server = FixtureServer::start().await;
+        let audio_cache_dir =
+            std::env::temp_dir().join(format!("qobuz-player-fixture-cache-{}", std::process::id()));
+        let mut player = fixture_player(&server, audio_cache_dir.clone()).await;
+        let controls = player.controls();
+        let status = player.status();
+        let tracklist = player.tracklist();
+
+        tokio::select! {
+            result = player.player_loop() => panic!("player loop stopped: {result:?}"),
+            _ = async {
+                controls.play_album("fixturealbum", 0);
+                wait_for(&status, |status| *status == Status::Playing).await;
+
+                // The fixture tracks are a second of silence, the null output plays them in
+                // real time and moves on to the second track by itself.
+                wait_for(&tracklist, |tracklist| {
+                    tracklist.current_track().map(|track| track.id) == Some(1002)
+                })
+                .await;
+            } => {}
+        }
+
+        _ = std::fs::remove_dir_all(audio_cache_dir);
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    fs::{DirBuilder, Permissions},
+    os::unix::fs::{DirBuilderExt, PermissionsExt},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use qobuz_player_models::Track;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::{
+    PositionReceiver, Result, Status, StatusReceiver, TracklistReceiver, VolumeReceiver,
+    controls::Controls, error::Error,
+};
+
+/// Command sent by `qobuz-player ctl` to a running player, one json object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Play,
+    Pause,
+    Next,
+    Previous,
+    Seek { seconds: u64 },
+    Volume { volume: f32 },
+    Status,
+    Queue,
+    PlayAlbum { id: String },
+    PlayWork { album_id: String, index: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "kebab-case")]
+pub enum Response {
+    Ok,
+    Status(Box<PlayerStatus>),
+    Queue { tracks: Vec<Track> },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStatus {
+    pub status: Status,
+    pub position_seconds: u64,
+    pub volume: f32,
+    pub track: Option<Track>,
+}
+
+/// Default location of the socket, in the user's runtime directory when there is one and
+/// otherwise in a directory of the user's cache.
+pub fn socket_path() -> PathBuf {
+    let mut path = match dirs::runtime_dir() {
+        Some(runtime_dir) => runtime_dir,
+        None => dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("qobuz-player"),
+    };
+    path.push("qobuz-player.sock");
+    path
+}
+
+/// Listen on `path`, replacing a socket left behind by a player that did not shut down cleanly.
+///
+/// Only the user can connect: missing parent directories are created with mode 0700, binding
+/// fails when the directory of the socket is open to other users, and the socket gets mode 0600.
+pub async fn bind(path: &Path) -> Result<UnixListener> {
+    if let Some(directory) = path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(directory)
+            .map_err(|err| Error::RemoteControl {
+                message: err.to_string(),
+            })?;
+
+        check_private(directory)?;
+    }
+
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(Error::RemoteControl {
+                message: format!("another player is listening on {}", path.display()),
+            });
+        }
+
+        std::fs::remove_file(path).map_err(|err| Error::RemoteControl {
+            message: err.to_string(),
+        })?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(|err| Error::RemoteControl {
+        message: err.to_string(),
+    })?;
+
+    std::fs::set_permissions(path, Permissions::from_mode(0o600)).map_err(|err| {
+        Error::RemoteControl {
+            message: err.to_string(),
+        }
+    })?;
+
+    Ok(listener)
+}
+
+/// Fail unless only the owner has access to `directory`. Others could otherwise connect to the
+/// socket before its permissions are set, or replace it.
+fn check_private(directory: &Path) -> Result<()> {
+    let mode = std::fs::metadata(directory)
+        .map_err(|err| Error::RemoteControl {
+            message: err.to_string(),
+        })?
+        .permissions()
+        .mode();
+
+    if mode & 0o077 != 0 {
+        return Err(Error::RemoteControl {
+            message: format!(
+                "{} is accessible to other users (mode {:o}), it must be 700",
+                directory.display(),
+                mode & 0o777
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+pub async fn serve(
+    listener: UnixListener,
+    controls: Controls,
+    position_receiver: PositionReceiver,
+    tracklist_receiver: TracklistReceiver,
+    volume_receiver: VolumeReceiver,
+    status_receiver: StatusReceiver,
+) -> Result<()> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|err| Error::RemoteControl {
+                message: err.to_string(),
+            })?;
+
+        let connection = Connection {
+            controls: controls.clone(),
+            position_receiver: position_receiver.clone(),
+            tracklist_receiver: tracklist_receiver.clone(),
+            volume_receiver: volume_receiver.clone(),
+            status_receiver: status_receiver.clone(),
+        };
+
+        tokio::spawn(async move {
+            if let Err(err) = connection.handle(stream).await {
+                tracing::debug!("remote control connection closed: {err}");
+            }
+        });
+    }
+}
+
+/// Send a single request to the player listening on `path`.
+pub async fn send(path: &Path, request: &Request) -> Result<Response> {
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|err| Error::RemoteControl {
+            message: format!(
+                "no player is listening on {} ({err}). Is qobuz-player running?",
+                path.display()
+            ),
+        })?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|err| Error::RemoteControl {
+            message: err.to_string(),
+        })?;
+
+    let mut reply = String::new();
+    BufReader::new(reader)
+        .read_line(&mut reply)
+        .await
+        .map_err(|err| Error::RemoteControl {
+            message: err.to_string(),
+        })?;
+
+    Ok(serde_json::from_str(&reply)?)
+}
+
+struct Connection {
+    controls: Controls,
+    position_receiver: PositionReceiver,
+    tracklist_receiver: TracklistReceiver,
+    volume_receiver: VolumeReceiver,
+    status_receiver: StatusReceiver,
+}
+
+impl Connection {
+    async fn handle(&self, stream: UnixStream) -> std::io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.respond(request),
+                Err(err) => Response::Error {
+                    message: err.to_string(),
+                },
+            };
+
+            let mut reply = serde_json::to_string(&response).expect("infailable");
+            reply.push('\n');
+            writer.write_all(reply.as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    fn respond(&self, request: Request) -> Response {
+        match request {
+            Request::Play => self.controls.play(),
+            Request::Pause => self.controls.pause(),
+            Request::Next => self.controls.next(),
+            Request::Previous => self.controls.previous(),
+            Request::Seek { seconds } => self.controls.seek(Duration::from_secs(seconds)),
+            Request::Volume { volume } => self.controls.set_volume(volume.clamp(0.0, 1.0)),
+            Request::PlayAlbum { id } => self.controls.play_album(&id, 0),
+            Request::PlayWork { album_id, index } => self.controls.play_work(&album_id, index),
+            Request::Status => {
+                return Response::Status(Box::new(PlayerStatus {
+                    status: *self.status_receiver.borrow(),
+                    position_seconds: self.position_receiver.borrow().as_secs(),
+                    volume: *self.volume_receiver.borrow(),
+                    track: self.tracklist_receiver.borrow().current_track().cloned(),
+                }));
+            }
+            Request::Queue => {
+                return Response::Queue {
+                    tracks: self.tracklist_receiver.borrow().queue().clone(),
+                };
+            }
+        }
+
+        Response::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::{mpsc, watch};
+
+    use super::*;
+    use crate::{controls::ControlCommand, tracklist::Tracklist};
+
+    fn test_directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "qobuz-player-remote-test-{name}-{}",
+            std::process::id()
+        ))
+    }
+
+    #[tokio::test]
+    async fn forwards_commands_and_reports_status() {
+        let directory = test_directory("status");
+        let path = directory.join("qobuz-player.sock");
+        let listener = bind(&path).await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let mode = std::fs::metadata(&directory).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (_position_tx, position_receiver) = watch::channel(Duration::from_secs(83));
+        let (_tracklist_tx, tracklist_receiver) = watch::channel(Tracklist::new());
+        let (_volume_tx, volume_receiver) = watch::channel(0.5);
+        let (_status_tx, status_receiver) = watch::channel(Status::Playing);
+
+        tokio::spawn(serve(
+            listener,
+            Controls::new(tx),
+            position_receiver,
+            tracklist_receiver,
+            volume_receiver,
+            status_receiver,
+        ));
+
+        let response = send(&path, &Request::Next).await.unwrap();
+        assert_eq!(response, Response::Ok);
+        assert!(matches!(rx.recv().await, Some(ControlCommand::Next)));
+
+        let response = send(&path, &Request::Status).await.unwrap();
+        assert_eq!(
+            response,
+            Response::Status(Box::new(PlayerStatus {
+                status: Status::Playing,
+                position_seconds: 83,
+                volume: 0.5,
+                track: None,
+            }))
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_a_directory_open_to_other_users() {
+        let directory = test_directory("shared");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::set_permissions(&directory, Permissions::from_mode(0o755)).unwrap();
+        let path = directory.join("qobuz-player.sock");
+
+        assert!(matches!(
+            bind(&path).await,
+            Err(Error::RemoteControl { .. })
+        ));
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,11 +1,12 @@
 use std::io::Cursor;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use qobuz_player_client::qobuz_models::TrackURL;
 use qobuz_player_models::Track;
-use rodio::{decoder::DecoderBuilder, queue::queue};
+use rodio::{Source, decoder::DecoderBuilder, queue::queue, source::SeekError};
 use tokio::fs;
 use tokio::sync::watch::{self, Receiver, Sender};
 use tokio::task::JoinHandle;
@@ -13,11 +14,35 @@ use tokio::task::JoinHandle;
 use crate::Result;
 use crate::database::Database;
 use crate::notification::NotificationBroadcast;
+use crate::output::{Output, OutputStream};
+
+/// What the player needs from a sink, implemented by [`Sink`] and by a fake in tests.
+pub(crate) trait AudioSink {
+    fn track_finished(&self) -> Receiver<()>;
+    fn done_buffering(&self) -> Receiver<()>;
+    fn clear(&mut self) -> Result<()>;
+    fn play(&self);
+    fn pause(&self);
+    fn seek(&self, duration: Duration) -> Result<()>;
+    /// Position in the playing track, from the samples the output has taken. `None` when
+    /// no track is loaded.
+    fn position(&self) -> Option<Duration>;
+    /// Start loading the track, queued after what is already playing. Returns whether it
+    /// plays without reopening the output.
+    fn query_track_url(&mut self, track_url: TrackURL, track: &Track) -> Result<bool>;
+    fn set_volume(&mut self, volume: f32);
+    fn set_speed(&mut self, speed: f32);
+}
 
 pub struct Sink {
-    stream_handle: Option<rodio::OutputStream>,
+    output: Output,
+    stream_handle: Option<OutputStream>,
     sink: Option<rodio::Sink>,
     sender: Option<Arc<rodio::queue::SourcesQueueInput>>,
+    /// Microseconds into the playing track, written by its [`CountingSource`].
+    position: Arc<AtomicU64>,
+    /// Whether the first track of the sink has reached the queue yet.
+    loading: Arc<Mutex<Loading>>,
     current_download: Arc<Mutex<Option<JoinHandle<()>>>>,
     track_finished_tx: Sender<()>,
     done_buffering_tx: Sender<()>,
@@ -25,10 +50,12 @@ pub struct Sink {
     audio_cache_dir: PathBuf,
     database: Arc<Database>,
     volume: f32,
+    speed: f32,
 }
 
 impl Sink {
     pub fn new(
+        output: Output,
         volume: f32,
         broadcast: Arc<NotificationBroadcast>,
         audio_cache_dir: PathBuf,
@@ -38,9 +65,12 @@ impl Sink {
         let (done_buffering_tx, _) = watch::channel(());
 
         Ok(Self {
+            output,
             sink: Default::default(),
             stream_handle: Default::default(),
             sender: Default::default(),
+            position: Default::default(),
+            loading: Default::default(),
             current_download: Default::default(),
             track_finished_tx,
             done_buffering_tx,
@@ -48,50 +78,75 @@ impl Sink {
             audio_cache_dir,
             database,
             volume,
+            speed: 1.0,
         })
     }
+}
 
-    pub fn track_finished(&self) -> Receiver<()> {
+impl AudioSink for Sink {
+    fn track_finished(&self) -> Receiver<()> {
         self.track_finished_tx.subscribe()
     }
 
-    pub fn done_buffering(&self) -> Receiver<()> {
+    fn done_buffering(&self) -> Receiver<()> {
         self.done_buffering_tx.subscribe()
     }
 
-    pub async fn clear(&mut self) -> Result<()> {
+    fn clear(&mut self) -> Result<()> {
         if let Some(handle) = self.current_download.lock()?.take() {
             handle.abort();
         }
 
         self.sink = None;
         self.sender = None;
-        self.stream_handle = None;
+        if self
+            .stream_handle
+            .as_ref()
+            .is_some_and(|stream| stream.closes_on_clear())
+        {
+            self.stream_handle = None;
+        }
 
         Ok(())
     }
 
-    pub fn play(&self) {
+    fn play(&self) {
         if let Some(sink) = &self.sink {
             sink.play();
         }
     }
 
-    pub fn pause(&self) {
+    fn pause(&self) {
         if let Some(sink) = &self.sink {
             sink.pause();
         }
     }
 
-    pub fn seek(&self, duration: Duration) -> Result<()> {
-        if let Some(sink) = &self.sink {
-            sink.try_seek(duration)?;
+    fn seek(&self, duration: Duration) -> Result<()> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+
+        // The queue is empty until the track downloads, a seek now would be dropped.
+        if let Loading::Pending(seek) = &mut *self.loading.lock()? {
+            *seek = Some(duration);
+            self.position
+                .store(duration.as_micros() as u64, Ordering::Relaxed);
+            return Ok(());
         }
 
+        sink.try_seek(duration)?;
+
         Ok(())
     }
 
-    pub fn query_track_url(&mut self, track_url: TrackURL, track: &Track) -> Result<bool> {
+    fn position(&self) -> Option<Duration> {
+        self.sink
+            .as_ref()
+            .map(|_| Duration::from_micros(self.position.load(Ordering::Relaxed)))
+    }
+
+    fn query_track_url(&mut self, track_url: TrackURL, track: &Track) -> Result<bool> {
         if let Some(handle) = self.current_download.lock()?.take() {
             handle.abort();
         }
@@ -99,22 +154,28 @@ impl Sink {
         let sample_rate = (track_url.sampling_rate * 1000.0) as u32;
 
         if self.stream_handle.is_none() || self.sink.is_none() || self.sender.is_none() {
-            let mut stream_handle = rodio::OutputStreamBuilder::from_default_device()?
-                .with_sample_rate(sample_rate)
-                .open_stream()?;
-            stream_handle.log_on_drop(false);
+            let stream_handle = match self.stream_handle.take() {
+                Some(stream_handle) => stream_handle,
+                None => self.output.open(sample_rate)?,
+            };
 
             let (sender, receiver) = queue(true);
             let sink = rodio::Sink::connect_new(stream_handle.mixer());
             sink.append(receiver);
             sink.set_volume(self.volume);
+            sink.set_speed(self.speed);
             self.sink = Some(sink);
             self.sender = Some(sender);
+            // Sources of a dropped sink may still be pulled once or twice, keep them apart.
+            self.position = Default::default();
+            self.loading = Arc::new(Mutex::new(Loading::Pending(None)));
             self.stream_handle = Some(stream_handle);
         }
 
         let track_url_url = track_url.url;
         let sender = self.sender.as_ref().unwrap().clone();
+        let position = self.position.clone();
+        let loading = self.loading.clone();
         let track_finished_tx = self.track_finished_tx.clone();
         let done_buffering_tx = self.done_buffering_tx.clone();
         let broadcast = self.broadcast.clone();
@@ -140,7 +201,16 @@ impl Sink {
                 sanitize_name(album_title),
                 sanitize_name(album_id),
             );
-            let track_file = format!("{}_{}.mp3", track.number, sanitize_name(track_title));
+            // Tracks on the first disc keep the name they were cached under before disc numbers.
+            let track_file = match track.disc_number {
+                0 | 1 => format!("{}_{}.mp3", track.number, sanitize_name(track_title)),
+                disc => format!(
+                    "{}-{}_{}.mp3",
+                    disc,
+                    track.number,
+                    sanitize_name(track_title)
+                ),
+            };
             self.audio_cache_dir
                 .join(artist_dir)
                 .join(album_dir)
@@ -192,7 +262,19 @@ impl Sink {
                 return;
             };
 
-            let signal = sender.append_with_signal(source);
+            let mut source = CountingSource::new(source, position);
+            let signal = {
+                let Ok(mut loading) = loading.lock() else {
+                    return;
+                };
+                if let Loading::Pending(Some(seek)) = *loading
+                    && let Err(e) = source.try_seek(seek)
+                {
+                    broadcast.send_error(format!("Unable to seek: {e}"));
+                }
+                *loading = Loading::Done;
+                sender.append_with_signal(source)
+            };
 
             done_buffering_tx.send(()).expect("infailable");
 
@@ -205,10 +287,18 @@ impl Sink {
 
         *self.current_download.lock()? = Some(handle);
 
-        Ok(sample_rate == self.stream_handle.as_ref().unwrap().config().sample_rate())
+        Ok(self.stream_handle.as_ref().unwrap().plays_at(sample_rate))
+    }
+
+    /// Play slightly faster or slower, used to correct drift from a sync group leader.
+    fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+        if let Some(sink) = &self.sink {
+            sink.set_speed(speed);
+        }
     }
 
-    pub fn set_volume(&mut self, volume: f32) {
+    fn set_volume(&mut self, volume: f32) {
         self.volume = volume;
         if let Some(sink) = &self.sink {
             set_volume(sink, volume);
@@ -216,6 +306,82 @@ impl Sink {
     }
 }
 
+/// Seeks made before the first track of a sink is queued are kept here and applied to it.
+#[derive(Default)]
+enum Loading {
+    Pending(Option<Duration>),
+    #[default]
+    Done,
+}
+
+/// Passes a track through and publishes how far the output has played into it. Tracks play
+/// one after the other, so the one that wrote last is the one playing.
+struct CountingSource<S> {
+    inner: S,
+    samples: u64,
+    position: Arc<AtomicU64>,
+}
+
+impl<S: Source> CountingSource<S> {
+    fn new(inner: S, position: Arc<AtomicU64>) -> Self {
+        Self {
+            inner,
+            samples: 0,
+            position,
+        }
+    }
+
+    fn samples_per_second(&self) -> u64 {
+        self.inner.sample_rate() as u64 * self.inner.channels() as u64
+    }
+}
+
+impl<S: Source> Iterator for CountingSource<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+
+        self.samples += 1;
+        let micros = self.samples * 1_000_000 / self.samples_per_second().max(1);
+        self.position.store(micros, Ordering::Relaxed);
+
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source> Source for CountingSource<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, position: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(position)?;
+
+        self.samples = (position.as_secs_f64() * self.samples_per_second() as f64) as u64;
+        self.position
+            .store(position.as_micros() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
 fn set_volume(sink: &rodio::Sink, volume: f32) {
     let volume = volume.clamp(0.0, 1.0).powi(3);
     sink.set_volume(volume);
@@ -255,3 +421,27 @@ fn sanitize_name(input: &str) -> String {
     const MAX: usize = 100;
     out.chars().take(MAX).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use rodio::buffer::SamplesBuffer;
+
+    use super::*;
+
+    #[test]
+    fn counting_source_tracks_played_samples_and_seeks() {
+        let position = Arc::new(AtomicU64::new(0));
+        // One second of stereo at 1kHz.
+        let buffer = SamplesBuffer::new(2, 1_000, vec![0.0; 2_000]);
+        let mut source = CountingSource::new(buffer, position.clone());
+
+        source.by_ref().take(500).for_each(drop);
+        assert_eq!(position.load(Ordering::Relaxed), 250_000);
+
+        source.try_seek(Duration::from_millis(800)).unwrap();
+        assert_eq!(position.load(Ordering::Relaxed), 800_000);
+
+        source.by_ref().take(100).for_each(drop);
+        assert_eq!(position.load(Ordering::Relaxed), 850_000);
+    }
+}
@@ -0,0 +1,577 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, tcp::OwnedWriteHalf},
+    select,
+};
+
+use crate::{
+    ClockReceiver, Result, Status, StatusReceiver, TracklistReceiver, controls::Controls,
+    database::Database, error::Error, timer::ClockReference, tracklist::Tracklist,
+};
+
+/// How often the leader sends its clock and followers measure the clock offset.
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Time a follower has to introduce itself before the leader hangs up.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Number of round trips the clock offset is estimated from.
+const OFFSET_SAMPLES: usize = 16;
+
+/// Drift that is left alone.
+const TOLERANCE: Duration = Duration::from_millis(2);
+/// Drift is corrected by changing the playback rate so it is gone after this long.
+const CORRECTION_WINDOW: Duration = Duration::from_secs(4);
+/// Largest change of the playback rate, small enough not to hear the pitch change.
+const MAX_RATE_ADJUSTMENT: f64 = 0.005;
+/// Followers further off than this seek instead of changing the playback rate.
+pub(crate) const MAX_DRIFT: Duration = Duration::from_millis(100);
+
+/// Message between a sync group leader and a follower, one json object per line.
+/// Times are microseconds on the sender's clock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "message", rename_all = "kebab-case")]
+pub enum Message {
+    /// First message of a follower, with an api token of the leader when it has a web secret.
+    Hello {
+        token: Option<String>,
+    },
+    Ping {
+        sent: u64,
+    },
+    Pong {
+        sent: u64,
+        leader_time: u64,
+    },
+    Tracklist {
+        tracklist: Tracklist,
+    },
+    Clock {
+        queue_position: u32,
+        position: u64,
+        leader_time: u64,
+        running: bool,
+    },
+}
+
+/// Playback clock of a player while it plays the track at `queue_position`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackClock {
+    pub queue_position: u32,
+    pub reference: ClockReference,
+}
+
+/// Monotonic clock in microseconds, counted from when the connection was set up.
+#[derive(Debug, Clone, Copy)]
+struct LocalClock {
+    epoch: Instant,
+}
+
+impl LocalClock {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    fn instant(&self, time: i64) -> Instant {
+        self.epoch + Duration::from_micros(time.max(0) as u64)
+    }
+}
+
+/// Offset of the leader's clock from ours, estimated from ping round trips. The sample with
+/// the shortest round trip wins, as it had the least room for network delay.
+#[derive(Debug, Default)]
+pub(crate) struct OffsetEstimate {
+    samples: VecDeque<(u64, i64)>,
+}
+
+impl OffsetEstimate {
+    pub(crate) fn add(&mut self, sent: u64, leader_time: u64, received: u64) {
+        let round_trip = received.saturating_sub(sent);
+        let offset = leader_time as i64 - (sent + round_trip / 2) as i64;
+
+        if self.samples.len() == OFFSET_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((round_trip, offset));
+    }
+
+    pub(crate) fn offset(&self) -> Option<i64> {
+        self.samples
+            .iter()
+            .min_by_key(|(round_trip, _)| *round_trip)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+/// How far `position` is ahead of the leader's `target`, in microseconds.
+pub(crate) fn drift(position: Duration, target: Duration) -> i64 {
+    position.as_micros() as i64 - target.as_micros() as i64
+}
+
+/// Playback rate that removes `drift` over the correction window.
+pub(crate) fn correction_rate(drift: i64) -> f64 {
+    if drift.unsigned_abs() <= TOLERANCE.as_micros() as u64 {
+        return 1.0;
+    }
+
+    let rate = 1.0 - drift as f64 / CORRECTION_WINDOW.as_micros() as f64;
+    rate.clamp(1.0 - MAX_RATE_ADJUSTMENT, 1.0 + MAX_RATE_ADJUSTMENT)
+}
+
+fn io_error(err: std::io::Error) -> Error {
+    Error::SyncGroup {
+        message: err.to_string(),
+    }
+}
+
+async fn write(writer: &mut OwnedWriteHalf, message: &Message) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await.map_err(io_error)
+}
+
+/// Listen for followers on `address`, e.g. `0.0.0.0:9889`.
+pub async fn bind(address: &str) -> Result<TcpListener> {
+    TcpListener::bind(address)
+        .await
+        .map_err(|err| Error::SyncGroup {
+            message: format!("unable to listen on {address}: {err}"),
+        })
+}
+
+/// Receivers of the leading player, shared by every follower connection.
+#[derive(Clone)]
+pub struct Leader {
+    pub clock_receiver: ClockReceiver,
+    pub tracklist_receiver: TracklistReceiver,
+    pub status_receiver: StatusReceiver,
+}
+
+/// Lead a sync group: send the queue and the playback clock to every follower that connects.
+/// With `tokens`, followers must introduce themselves with an api token valid there.
+pub async fn lead(
+    listener: TcpListener,
+    leader: Leader,
+    tokens: Option<Arc<Database>>,
+) -> Result<()> {
+    loop {
+        let (stream, address) = listener.accept().await.map_err(io_error)?;
+        tracing::info!("sync group follower connected from {address}");
+
+        let leader = leader.clone();
+        let tokens = tokens.clone();
+        tokio::spawn(async move {
+            if let Err(err) = lead_follower(stream, leader, tokens).await {
+                tracing::debug!("sync group follower {address} disconnected: {err}");
+            }
+        });
+    }
+}
+
+async fn lead_follower(
+    stream: TcpStream,
+    leader: Leader,
+    tokens: Option<Arc<Database>>,
+) -> Result<()> {
+    let Leader {
+        mut clock_receiver,
+        mut tracklist_receiver,
+        mut status_receiver,
+    } = leader;
+
+    _ = stream.set_nodelay(true);
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let local_clock = LocalClock::new();
+    let mut interval = tokio::time::interval(CLOCK_INTERVAL);
+
+    let hello = tokio::time::timeout(HELLO_TIMEOUT, lines.next_line())
+        .await
+        .ok()
+        .and_then(|line| line.ok().flatten())
+        .and_then(|line| serde_json::from_str(&line).ok());
+    let Some(Message::Hello { token }) = hello else {
+        return Err(Error::SyncGroup {
+            message: "follower did not introduce itself".into(),
+        });
+    };
+
+    if let Some(tokens) = tokens {
+        let valid = match token {
+            Some(token) => tokens.is_api_token_valid(&token).await?,
+            None => false,
+        };
+
+        if !valid {
+            tracing::warn!("sync group follower rejected, it has no valid api token");
+            return Ok(());
+        }
+    }
+
+    // The clock is held back while buffering, until the track it belongs to has started.
+    let clock_message = |clock_receiver: &mut ClockReceiver,
+                         status_receiver: &mut StatusReceiver| {
+        if *status_receiver.borrow_and_update() == Status::Buffering {
+            return None;
+        }
+
+        let PlaybackClock {
+            queue_position,
+            reference,
+        } = *clock_receiver.borrow_and_update();
+        Some(Message::Clock {
+            queue_position,
+            position: reference.position_now().as_micros() as u64,
+            leader_time: local_clock.now(),
+            running: reference.running,
+        })
+    };
+
+    let tracklist = tracklist_receiver.borrow_and_update().clone();
+    write(&mut writer, &Message::Tracklist { tracklist }).await?;
+
+    loop {
+        let message = select! {
+            line = lines.next_line() => {
+                let Some(line) = line.map_err(io_error)? else {
+                    return Ok(());
+                };
+
+                match serde_json::from_str(&line)? {
+                    Message::Ping { sent } => Some(Message::Pong {
+                        sent,
+                        leader_time: local_clock.now(),
+                    }),
+                    _ => continue,
+                }
+            }
+            Ok(_) = tracklist_receiver.changed() => {
+                let tracklist = tracklist_receiver.borrow_and_update().clone();
+                Some(Message::Tracklist { tracklist })
+            }
+            Ok(_) = clock_receiver.changed() => {
+                clock_message(&mut clock_receiver, &mut status_receiver)
+            }
+            Ok(_) = status_receiver.changed() => {
+                clock_message(&mut clock_receiver, &mut status_receiver)
+            }
+            _ = interval.tick() => {
+                clock_message(&mut clock_receiver, &mut status_receiver)
+            }
+        };
+
+        if let Some(message) = message {
+            write(&mut writer, &message).await?;
+        }
+    }
+}
+
+/// Follow the sync group leader at `address`, e.g. `livingroom:9889`, reconnecting whenever
+/// the connection drops. `token` is an api token of the leader, needed when it has a web secret.
+pub async fn follow(address: &str, token: Option<String>, controls: Controls) {
+    loop {
+        if let Err(err) = follow_leader(address, token.clone(), &controls).await {
+            tracing::warn!("{err}");
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn follow_leader(address: &str, token: Option<String>, controls: &Controls) -> Result<()> {
+    let stream = TcpStream::connect(address)
+        .await
+        .map_err(|err| Error::SyncGroup {
+            message: format!("unable to reach leader {address}: {err}"),
+        })?;
+    _ = stream.set_nodelay(true);
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let local_clock = LocalClock::new();
+    let mut offset = OffsetEstimate::default();
+    let mut interval = tokio::time::interval(CLOCK_INTERVAL);
+
+    write(&mut writer, &Message::Hello { token }).await?;
+
+    loop {
+        select! {
+            _ = interval.tick() => {
+                write(&mut writer, &Message::Ping { sent: local_clock.now() }).await?;
+            }
+            line = lines.next_line() => {
+                let Some(line) = line.map_err(io_error)? else {
+                    return Err(Error::SyncGroup {
+                        message: format!("leader {address} closed the connection"),
+                    });
+                };
+
+                match serde_json::from_str(&line)? {
+                    Message::Pong { sent, leader_time } => {
+                        offset.add(sent, leader_time, local_clock.now());
+                    }
+                    Message::Tracklist { tracklist } => controls.follow_tracklist(tracklist),
+                    Message::Clock {
+                        queue_position,
+                        position,
+                        leader_time,
+                        running,
+                    } => {
+                        // Wait for the first round trip, the clock is useless without the offset.
+                        let Some(offset) = offset.offset() else {
+                            continue;
+                        };
+
+                        controls.align(PlaybackClock {
+                            queue_position,
+                            reference: ClockReference {
+                                position: Duration::from_micros(position),
+                                instant: local_clock.instant(leader_time as i64 - offset),
+                                running,
+                            },
+                        });
+                    }
+                    Message::Hello { .. } | Message::Ping { .. } => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qobuz_player_client::testing::FixtureServer;
+    use tokio::sync::{mpsc, watch};
+
+    use super::*;
+    use crate::{
+        controls::ControlCommand,
+        testing::{fixture_player, wait_for},
+    };
+
+    #[test]
+    fn offset_prefers_shortest_round_trip() {
+        let mut estimate = OffsetEstimate::default();
+        assert_eq!(estimate.offset(), None);
+
+        // Slow round trip, delayed on the way back.
+        estimate.add(1_000, 51_000, 9_000);
+        // Fast round trip: the leader is 50ms ahead.
+        estimate.add(10_000, 60_100, 10_200);
+
+        assert_eq!(estimate.offset(), Some(50_000));
+    }
+
+    #[test]
+    fn correction_rate_slows_down_when_ahead() {
+        assert_eq!(correction_rate(1_000), 1.0);
+        assert!(correction_rate(10_000) < 1.0);
+        assert!(correction_rate(-10_000) > 1.0);
+        assert_eq!(correction_rate(90_000), 1.0 - MAX_RATE_ADJUSTMENT);
+        assert_eq!(correction_rate(-90_000), 1.0 + MAX_RATE_ADJUSTMENT);
+    }
+
+    /// A leader at 30 seconds into the first track, with the sender of its status.
+    async fn leader_at_30_seconds(
+        status: Status,
+        tokens: Option<Arc<Database>>,
+    ) -> (String, watch::Sender<Status>) {
+        let listener = bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let (clock_tx, clock_receiver) = watch::channel(PlaybackClock {
+            queue_position: 0,
+            reference: ClockReference {
+                position: Duration::from_secs(30),
+                instant: Instant::now(),
+                running: true,
+            },
+        });
+        let (tracklist_tx, tracklist_receiver) = watch::channel(Tracklist::new());
+        let (status_tx, status_receiver) = watch::channel(status);
+        let leader = Leader {
+            clock_receiver,
+            tracklist_receiver,
+            status_receiver,
+        };
+
+        tokio::spawn(async move {
+            // Keep the senders of the leading player alive.
+            let _senders = (clock_tx, tracklist_tx);
+            lead(listener, leader, tokens).await
+        });
+
+        (address, status_tx)
+    }
+
+    fn follower(address: String, token: Option<String>) -> mpsc::UnboundedReceiver<ControlCommand> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move { follow(&address, token, Controls::new(tx)).await });
+        rx
+    }
+
+    async fn next_alignment(rx: &mut mpsc::UnboundedReceiver<ControlCommand>) -> PlaybackClock {
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+            {
+                Some(ControlCommand::Align { clock }) => break clock,
+                Some(_) => continue,
+                None => panic!("follower stopped"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn follower_aligns_to_leader_clock() {
+        let (address, _status_tx) = leader_at_30_seconds(Status::Playing, None).await;
+        let mut rx = follower(address, None);
+
+        let command = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap();
+        assert!(matches!(
+            command,
+            Some(ControlCommand::FollowTracklist { .. })
+        ));
+
+        let clock = next_alignment(&mut rx).await;
+        assert_eq!(clock.queue_position, 0);
+        assert!(clock.reference.running);
+        let position = clock.reference.position_now();
+        assert!(position >= Duration::from_secs(30));
+        assert!(position < Duration::from_secs(35));
+    }
+
+    #[tokio::test]
+    async fn leader_holds_the_clock_while_buffering() {
+        let (address, status_tx) = leader_at_30_seconds(Status::Buffering, None).await;
+        let mut rx = follower(address, None);
+
+        // Long enough for the first clock offset and a clock interval.
+        tokio::time::sleep(CLOCK_INTERVAL * 2).await;
+        while let Ok(command) = rx.try_recv() {
+            assert!(!matches!(command, ControlCommand::Align { .. }));
+        }
+
+        status_tx.send(Status::Playing).unwrap();
+        next_alignment(&mut rx).await;
+    }
+
+    #[sqlx::test]
+    async fn leader_rejects_followers_without_a_valid_token(pool: sqlx::Pool<sqlx::Sqlite>) {
+        let database = Arc::new(Database::init(pool).await.unwrap());
+        let token = database.create_api_token("kitchen".into()).await.unwrap();
+        let (address, _status_tx) = leader_at_30_seconds(Status::Playing, Some(database)).await;
+
+        for token in [None, Some("not a token".to_string())] {
+            let stream = TcpStream::connect(&address).await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            write(&mut writer, &Message::Hello { token }).await.unwrap();
+
+            let mut lines = BufReader::new(reader).lines();
+            let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(line, None);
+        }
+
+        let mut rx = follower(address, Some(token));
+        next_alignment(&mut rx).await;
+    }
+
+    #[tokio::test]
+    async fn players_converge_over_a_sync_link() {
+        let server = FixtureServer::with_track_seconds(60).await;
+        let cache_dir = |player: &str| {
+            std::env::temp_dir().join(format!(
+                "qobuz-player-sync-test-{player}-{}",
+                std::process::id()
+            ))
+        };
+        let mut leader = fixture_player(&server, cache_dir("leader")).await;
+        let mut follower = fixture_player(&server, cache_dir("follower")).await;
+
+        let listener = bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(lead(
+            listener,
+            Leader {
+                clock_receiver: leader.clock(),
+                tracklist_receiver: leader.tracklist(),
+                status_receiver: leader.status(),
+            },
+            None,
+        ));
+
+        let leader_controls = leader.controls();
+        let leader_status = leader.status();
+        let leader_clock = leader.clock();
+        let follower_controls = follower.controls();
+        let follower_status = follower.status();
+        let follower_clock = follower.clock();
+
+        // Queue position and how far apart the players are right now.
+        let drift = || {
+            let leader = *leader_clock.borrow();
+            let follower = *follower_clock.borrow();
+            let in_step = leader.queue_position == follower.queue_position;
+            let drift = leader
+                .reference
+                .position_now()
+                .abs_diff(follower.reference.position_now());
+            (in_step, drift)
+        };
+
+        select! {
+            result = leader.player_loop() => panic!("leader stopped: {result:?}"),
+            result = follower.player_loop() => panic!("follower stopped: {result:?}"),
+            _ = async {
+                leader_controls.play_album("fixturealbum", 0);
+                wait_for(&leader_status, |status| *status == Status::Playing).await;
+
+                // Join a while into the track, the follower has to catch up.
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                tokio::spawn(async move { follow(&address, None, follower_controls).await });
+                wait_for(&follower_status, |status| *status == Status::Playing).await;
+
+                tokio::time::timeout(Duration::from_secs(10), async {
+                    loop {
+                        let (in_step, drift) = drift();
+                        if in_step && drift <= MAX_DRIFT {
+                            break;
+                        }
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                })
+                .await
+                .expect("follower did not catch up with the leader");
+
+                // Rate corrections keep it there.
+                for _ in 0..30 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    let (in_step, drift) = drift();
+                    assert!(in_step && drift <= MAX_DRIFT, "follower drifted {drift:?}");
+                }
+            } => {}
+        }
+
+        for player in ["leader", "follower"] {
+            _ = std::fs::remove_dir_all(cache_dir(player));
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use qobuz_player_client::{client::AudioQuality, qobuz_models::TrackURL, testing::FixtureServer};
+use qobuz_player_models::{Album, Artist, ArtistPage, Playlist, Track};
+use sqlx::sqlite::SqlitePoolOptions;
+use tokio::sync::watch::{self, Receiver, Sender};
+
+use crate::{
+    PositionReceiver, Result, Status, StatusReceiver, TracklistReceiver,
+    client::{Client, ClientFuture, PlayerClient},
+    controls::Controls,
+    database::Database,
+    error::Error,
+    notification::NotificationBroadcast,
+    output::Output,
+    player::Player,
+    sink::AudioSink,
+    timer::Clock,
+    tracklist::Tracklist,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const ALBUM_ID: &str = "album";
+
+/// Tracks with ids counting up from 1, on the album [`ALBUM_ID`].
+pub(crate) fn tracks(count: u32, duration_seconds: u32) -> Vec<Track> {
+    (1..=count)
+        .map(|id| Track {
+            id,
+            title: format!("Track {id}"),
+            number: id,
+            available: true,
+            duration_seconds,
+            album_id: Some(ALBUM_ID.to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Serves the given tracks, as single tracks and as one album.
+pub(crate) struct FakeClient {
+    tracks: HashMap<u32, Track>,
+    album: Album,
+}
+
+impl FakeClient {
+    pub(crate) fn new(tracks: Vec<Track>) -> Self {
+        let album = Album {
+            id: ALBUM_ID.to_string(),
+            title: "Album".to_string(),
+            artist: Artist {
+                id: 1,
+                name: "Artist".to_string(),
+                image: None,
+            },
+            release_year: 2024,
+            hires_available: false,
+            explicit: false,
+            total_tracks: tracks.len() as u32,
+            tracks: tracks.clone(),
+            available: true,
+            image: String::default(),
+            image_thumbnail: String::default(),
+            duration_seconds: tracks.iter().map(|track| track.duration_seconds).sum(),
+            description: None,
+            composer: None,
+            label: None,
+            copyright: None,
+            upc: None,
+            version: None,
+            release_date: None,
+            audio_specs: None,
+        };
+
+        Self {
+            tracks: tracks.into_iter().map(|track| (track.id, track)).collect(),
+            album,
+        }
+    }
+
+    fn find(&self, id: u32) -> Result<Track> {
+        self.tracks.get(&id).cloned().ok_or(Error::NotFound {
+            message: format!("track {id}"),
+        })
+    }
+}
+
+impl PlayerClient for FakeClient {
+    fn track_url(&self, track_id: u32) -> ClientFuture<'_, TrackURL> {
+        let track_url = self.find(track_id).map(|track| TrackURL {
+            track_id: track_id as i32,
+            duration: track.duration_seconds as i32,
+            url: format!("fake://{track_id}"),
+            format_id: 6,
+            mime_type: "audio/flac".to_string(),
+            sampling_rate: 44.1,
+            bit_depth: 16,
+        });
+
+        Box::pin(async move { track_url })
+    }
+
+    fn track(&self, id: u32) -> ClientFuture<'_, Track> {
+        let track = self.find(id);
+        Box::pin(async move { track })
+    }
+
+    fn album<'a>(&'a self, id: &'a str) -> ClientFuture<'a, Album> {
+        let album = match id == self.album.id {
+            true => Ok(self.album.clone()),
+            false => Err(Error::NotFound {
+                message: format!("album {id}"),
+            }),
+        };
+
+        Box::pin(async move { album })
+    }
+
+    fn artist_page(&self, id: u32) -> ClientFuture<'_, ArtistPage> {
+        Box::pin(async move {
+            Err(Error::NotFound {
+                message: format!("artist {id}"),
+            })
+        })
+    }
+
+    fn playlist(&self, id: u32) -> ClientFuture<'_, Playlist> {
+        Box::pin(async move {
+            Err(Error::NotFound {
+                message: format!("playlist {id}"),
+            })
+        })
+    }
+}
+
+/// A sink that loads tracks instantly and only plays or finishes them when told to.
+pub(crate) struct FakeSink {
+    track_finished_tx: Sender<()>,
+    done_buffering_tx: Sender<()>,
+    /// Ids of the tracks loaded since the sink was last cleared, in order.
+    queued_tx: Sender<Vec<u32>>,
+    position_tx: Sender<Option<Duration>>,
+    sample_rate: Option<f64>,
+}
+
+impl FakeSink {
+    fn new() -> Self {
+        Self {
+            track_finished_tx: watch::channel(()).0,
+            done_buffering_tx: watch::channel(()).0,
+            queued_tx: watch::channel(Vec::new()).0,
+            position_tx: watch::channel(None).0,
+            sample_rate: None,
+        }
+    }
+}
+
+impl AudioSink for FakeSink {
+    fn track_finished(&self) -> Receiver<()> {
+        self.track_finished_tx.subscribe()
+    }
+
+    fn done_buffering(&self) -> Receiver<()> {
+        self.done_buffering_tx.subscribe()
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.sample_rate = None;
+        self.queued_tx.send_replace(Vec::new());
+        self.position_tx.send_replace(None);
+        Ok(())
+    }
+
+    fn play(&self) {}
+
+    fn pause(&self) {}
+
+    fn seek(&self, duration: Duration) -> Result<()> {
+        self.position_tx
+            .send_if_modified(|position| match position {
+                Some(position) => {
+                    *position = duration;
+                    true
+                }
+                None => false,
+            });
+        Ok(())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        *self.position_tx.borrow()
+    }
+
+    fn query_track_url(&mut self, track_url: TrackURL, _track: &Track) -> Result<bool> {
+        let sample_rate = *self.sample_rate.get_or_insert(track_url.sampling_rate);
+
+        self.queued_tx
+            .send_modify(|queued| queued.push(track_url.track_id as u32));
+        if self.position_tx.borrow().is_none() {
+            self.position_tx.send_replace(Some(Duration::ZERO));
+        }
+        self.done_buffering_tx.send_replace(());
+
+        Ok(sample_rate == track_url.sampling_rate)
+    }
+
+    fn set_volume(&mut self, _volume: f32) {}
+
+    fn set_speed(&mut self, _speed: f32) {}
+}
+
+/// A clock that only moves when advanced.
+#[derive(Debug)]
+pub(crate) struct FakeClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl FakeClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+}
+
+/// A [`Player`] on a fake client, sink and clock, driven through its controls and observed
+/// through its watch channels.
+pub(crate) struct Harness {
+    pub(crate) controls: Controls,
+    pub(crate) clock: Arc<FakeClock>,
+    tracklist: TracklistReceiver,
+    status: StatusReceiver,
+    position: PositionReceiver,
+    queued: Receiver<Vec<u32>>,
+    track_finished_tx: Sender<()>,
+    sink_position_tx: Sender<Option<Duration>>,
+}
+
+/// A fresh in-memory database.
+pub(crate) async fn database() -> Arc<Database> {
+    // A single connection, every new connection would get its own empty in-memory database.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    Arc::new(Database::init(pool).await.unwrap())
+}
+
+/// A [`Player`] on the null output, streaming from `server` and caching in `audio_cache_dir`.
+pub(crate) async fn fixture_player(server: &FixtureServer, audio_cache_dir: PathBuf) -> Player {
+    let client = Client::new(
+        "user@example.com".into(),
+        "password".into(),
+        AudioQuality::CD,
+        server.connection(),
+    );
+
+    Player::new(
+        Output::Null,
+        Tracklist::new(),
+        Arc::new(client),
+        1.0,
+        Arc::new(NotificationBroadcast::new()),
+        audio_cache_dir,
+        database().await,
+    )
+    .unwrap()
+}
+
+impl Harness {
+    pub(crate) async fn new(tracks: Vec<Track>) -> (Player, Self) {
+        let database = database().await;
+        let sink = FakeSink::new();
+        let clock = Arc::new(FakeClock::new());
+
+        let queued = sink.queued_tx.subscribe();
+        let track_finished_tx = sink.track_finished_tx.clone();
+        let sink_position_tx = sink.position_tx.clone();
+
+        let player = Player::with_parts(
+            Tracklist::new(),
+            Arc::new(FakeClient::new(tracks)),
+            Box::new(sink),
+            clock.clone(),
+            1.0,
+            Arc::new(NotificationBroadcast::new()),
+            database,
+        );
+
+        let harness = Self {
+            controls: player.controls(),
+            clock,
+            tracklist: player.tracklist(),
+            status: player.status(),
+            position: player.position(),
+            queued,
+            track_finished_tx,
+            sink_position_tx,
+        };
+
+        (player, harness)
+    }
+
+    /// Run `test` while the player loop runs next to it.
+    pub(crate) async fn run(&self, player: &mut Player, test: impl Future<Output = ()>) {
+        tokio::select! {
+            result = player.player_loop() => panic!("player loop stopped: {result:?}"),
+            _ = test => {}
+        }
+    }
+
+    /// Let the sink play up to `position` in the playing track, however much time passed.
+    pub(crate) fn play_to(&self, position: Duration) {
+        self.sink_position_tx.send_replace(Some(position));
+    }
+
+    /// Let the sink report the playing track as finished and move on to the next one.
+    pub(crate) fn finish_track(&self) {
+        self.sink_position_tx.send_replace(Some(Duration::ZERO));
+        self.track_finished_tx.send_replace(());
+    }
+
+    pub(crate) fn sink_position(&self) -> Option<Duration> {
+        *self.sink_position_tx.borrow()
+    }
+
+    pub(crate) fn tracklist(&self) -> Tracklist {
+        self.tracklist.borrow().clone()
+    }
+
+    pub(crate) fn position(&self) -> Duration {
+        *self.position.borrow()
+    }
+
+    pub(crate) fn status(&self) -> Status {
+        *self.status.borrow()
+    }
+
+    pub(crate) fn queued(&self) -> Vec<u32> {
+        self.queued.borrow().clone()
+    }
+
+    pub(crate) async fn wait_for_status(&self, status: Status) {
+        wait_for(&self.status, |current| *current == status).await;
+    }
+
+    /// Wait until the track at `position` is playing.
+    pub(crate) async fn wait_for_track(&self, position: u32) {
+        wait_for(&self.tracklist, |tracklist| {
+            tracklist.current_track().is_some() && tracklist.current_position() == position
+        })
+        .await;
+    }
+
+    pub(crate) async fn wait_for_position(&self, f: impl FnMut(&Duration) -> bool) {
+        wait_for(&self.position, f).await;
+    }
+
+    /// Wait until the sink has loaded exactly these tracks.
+    pub(crate) async fn wait_for_queued(&self, track_ids: &[u32]) {
+        wait_for(&self.queued, |queued| queued == track_ids).await;
+    }
+}
+
+pub(crate) async fn wait_for<T>(receiver: &Receiver<T>, f: impl FnMut(&T) -> bool) {
+    let mut receiver = receiver.clone();
+    tokio::time::timeout(TIMEOUT, receiver.wait_for(f))
+        .await
+        .expect("timed out waiting for the player")
+        .expect("player dropped the channel");
+}
@@ -1,16 +1,59 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Source of the current time, replaced by a manual clock in tests.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
 
 #[derive(Debug)]
 pub struct Timer {
+    clock: Arc<dyn Clock>,
     start_time: Option<Instant>,
     elapsed: Duration,
+    rate: f64,
+}
+
+/// Playback position at a point in time, shared with sync group followers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockReference {
+    pub position: Duration,
+    pub instant: Instant,
+    pub running: bool,
+}
+
+impl ClockReference {
+    /// Position the clock has reached by now.
+    pub fn position_now(&self) -> Duration {
+        match self.running {
+            true => self.position + self.instant.elapsed(),
+            false => self.position,
+        }
+    }
 }
 
 impl Timer {
     pub(crate) fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub(crate) fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
+            clock,
             start_time: None,
             elapsed: Duration::ZERO,
+            rate: 1.0,
         }
     }
 
@@ -26,29 +69,47 @@ impl Timer {
 
     pub(crate) fn start(&mut self) {
         if self.start_time.is_none() {
-            self.start_time = Some(Instant::now());
+            self.start_time = Some(self.clock.now());
         }
     }
 
     pub(crate) fn pause(&mut self) {
-        if let Some(start) = self.start_time {
-            self.elapsed += start.elapsed();
+        if self.start_time.is_some() {
+            self.elapsed = self.elapsed();
             self.start_time = None;
         }
     }
 
     pub(crate) fn elapsed(&self) -> Duration {
         match self.start_time {
-            Some(start) => self.elapsed + start.elapsed(),
+            Some(start) => self.elapsed + (self.clock.now() - start).mul_f64(self.rate),
             None => self.elapsed,
         }
     }
 
+    /// Advance at `rate` times wall clock speed, to follow audio played faster or slower.
+    pub(crate) fn set_rate(&mut self, rate: f64) {
+        if self.start_time.is_some() {
+            self.elapsed = self.elapsed();
+            self.start_time = Some(self.clock.now());
+        }
+
+        self.rate = rate;
+    }
+
+    pub(crate) fn reference(&self) -> ClockReference {
+        ClockReference {
+            position: self.elapsed(),
+            instant: self.clock.now(),
+            running: self.start_time.is_some(),
+        }
+    }
+
     pub(crate) fn set_time(&mut self, time: Duration) {
         self.elapsed = time;
 
         if self.start_time.is_some() {
-            self.start_time = Some(Instant::now());
+            self.start_time = Some(self.clock.now());
         }
     }
 }
@@ -88,6 +149,21 @@ mod tests {
         assert!(total_elapsed >= Duration::from_millis(200));
     }
 
+    #[test]
+    fn test_rate_scales_elapsed_time() {
+        let mut sw = Timer::new();
+
+        sw.start();
+        sw.set_rate(0.5);
+        std::thread::sleep(Duration::from_millis(200));
+        sw.pause();
+
+        assert!(
+            approx_eq(sw.elapsed(), Duration::from_millis(100), 10),
+            "Elapsed time should advance at half speed"
+        );
+    }
+
     #[test]
     fn test_pause_stops_time_accumulation() {
         let mut sw = Timer::new();
@@ -41,9 +41,39 @@ pub enum TracklistType {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "StoredTracklist")]
 pub struct Tracklist {
     pub(crate) queue: Vec<Track>,
     pub(crate) list_type: TracklistType,
+    /// Id of the entry at each position of the queue. An entry keeps its id when it moves,
+    /// which tells apart entries of the same track.
+    entries: Vec<u64>,
+    next_entry: u64,
+}
+
+/// A tracklist as persisted, which may predate entry ids.
+#[derive(serde::Deserialize)]
+struct StoredTracklist {
+    queue: Vec<Track>,
+    list_type: TracklistType,
+    #[serde(default)]
+    entries: Vec<u64>,
+    #[serde(default)]
+    next_entry: u64,
+}
+
+impl From<StoredTracklist> for Tracklist {
+    fn from(stored: StoredTracklist) -> Self {
+        match stored.entries.len() == stored.queue.len() {
+            true => Self {
+                queue: stored.queue,
+                list_type: stored.list_type,
+                entries: stored.entries,
+                next_entry: stored.next_entry,
+            },
+            false => Self::with_queue(stored.queue, stored.list_type),
+        }
+    }
 }
 
 impl Tracklist {
@@ -51,10 +81,48 @@ impl Tracklist {
         Default::default()
     }
 
+    pub(crate) fn with_queue(queue: Vec<Track>, list_type: TracklistType) -> Self {
+        let entries = (0..queue.len() as u64).collect();
+
+        Self {
+            next_entry: queue.len() as u64,
+            queue,
+            list_type,
+            entries,
+        }
+    }
+
+    /// Number the entries after the ones of `previous`, so entry ids of the queue this one
+    /// replaces don't refer to its entries.
+    pub(crate) fn number_entries_after(&mut self, previous: &Tracklist) {
+        let first = previous.next_entry;
+        self.entries = (first..first + self.queue.len() as u64).collect();
+        self.next_entry = first + self.queue.len() as u64;
+    }
+
     pub fn queue(&self) -> &Vec<Track> {
         &self.queue
     }
 
+    /// Entry ids in queue order.
+    pub fn entries(&self) -> &[u64] {
+        &self.entries
+    }
+
+    /// Position of the entry with id `entry`.
+    pub fn entry_position(&self, entry: u64) -> Option<u32> {
+        self.entries
+            .iter()
+            .position(|id| *id == entry)
+            .map(|position| position as u32)
+    }
+
+    fn new_entry(&mut self) -> u64 {
+        let entry = self.next_entry;
+        self.next_entry += 1;
+        entry
+    }
+
     pub fn total(&self) -> u32 {
         self.queue.len() as u32
     }
@@ -134,4 +202,327 @@ impl Tracklist {
 
         new_track
     }
+
+    /// Position the track at `position` ends up at when moved to play next.
+    pub fn play_next_position(&self, position: u32) -> u32 {
+        let current_position = self.current_position();
+
+        match position.cmp(&current_position) {
+            std::cmp::Ordering::Less | std::cmp::Ordering::Equal => current_position,
+            std::cmp::Ordering::Greater => current_position + 1,
+        }
+    }
+
+    /// Append a track to the end of the queue.
+    pub(crate) fn push(&mut self, mut track: Track) {
+        track.status = TrackStatus::Unplayed;
+        self.queue.push(track);
+        let entry = self.new_entry();
+        self.entries.push(entry);
+    }
+
+    /// Insert a track right after the one playing.
+    pub(crate) fn insert_next(&mut self, mut track: Track) {
+        track.status = TrackStatus::Unplayed;
+        let position = (self.current_position() as usize + 1).min(self.queue.len());
+        self.queue.insert(position, track);
+        let entry = self.new_entry();
+        self.entries.insert(position, entry);
+    }
+
+    /// Remove the track at `position`. An emptied queue no longer belongs to the album or
+    /// playlist it was started from.
+    pub(crate) fn remove(&mut self, position: u32) -> Option<Track> {
+        let position = position as usize;
+        if position >= self.queue.len() {
+            return None;
+        }
+
+        let track = self.queue.remove(position);
+        self.entries.remove(position);
+        if self.queue.is_empty() {
+            self.list_type = TracklistType::None;
+        }
+
+        Some(track)
+    }
+
+    /// Move the track at `from` so it ends up at `to`. The playing track keeps playing, tracks
+    /// moved before it count as played and tracks moved after it as unplayed.
+    pub(crate) fn move_track(&mut self, from: u32, to: u32) -> bool {
+        let (from, to) = (from as usize, to as usize);
+        if from >= self.queue.len() || to >= self.queue.len() || from == to {
+            return false;
+        }
+
+        let track = self.queue.remove(from);
+        self.queue.insert(to, track);
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+
+        if let Some(current_position) = self
+            .queue
+            .iter()
+            .position(|track| track.status == TrackStatus::Playing)
+        {
+            for (position, track) in self.queue.iter_mut().enumerate() {
+                match position.cmp(&current_position) {
+                    std::cmp::Ordering::Less => track.status = TrackStatus::Played,
+                    std::cmp::Ordering::Equal => (),
+                    std::cmp::Ordering::Greater => track.status = TrackStatus::Unplayed,
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Drop every track after the one playing.
+    pub(crate) fn clear_upcoming(&mut self) {
+        let length = (self.current_position() as usize + 1).min(self.queue.len());
+        self.queue.truncate(length);
+        self.entries.truncate(length);
+    }
+
+    /// Changes turning this tracklist into `new`, for clients keeping their own copy of the queue.
+    ///
+    /// Entries are matched by id, so moving or removing an entry doesn't resend the entries
+    /// after it.
+    pub fn diff(&self, new: &Tracklist) -> TracklistDiff {
+        let old_tracks = self
+            .entries
+            .iter()
+            .zip(&self.queue)
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let changed = new
+            .entries
+            .iter()
+            .zip(&new.queue)
+            .filter(|(entry, track)| old_tracks.get(entry) != Some(track))
+            .map(|(entry, track)| ChangedEntry {
+                entry: *entry,
+                track: track.clone(),
+            })
+            .collect();
+
+        TracklistDiff {
+            entries: new.entries.clone(),
+            changed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TracklistDiff {
+    /// Entry ids of the new queue in order. Entries left out were removed.
+    pub entries: Vec<u64>,
+    /// Entries that are new or whose track changed, the others are unchanged.
+    pub changed: Vec<ChangedEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ChangedEntry {
+    pub entry: u64,
+    pub track: Track,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracklist(ids: &[u32]) -> Tracklist {
+        Tracklist::with_queue(
+            ids.iter()
+                .map(|id| Track {
+                    id: *id,
+                    ..Default::default()
+                })
+                .collect(),
+            TracklistType::None,
+        )
+    }
+
+    #[test]
+    fn diff_contains_only_changed_tracks() {
+        let mut old = tracklist(&[1, 2, 3]);
+        old.reset();
+        let mut new = old.clone();
+        new.skip_to_track(1);
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.entries, [0, 1, 2]);
+        assert_eq!(
+            diff.changed
+                .iter()
+                .map(|change| (change.entry, change.track.status.clone()))
+                .collect::<Vec<_>>(),
+            vec![(0, TrackStatus::Played), (1, TrackStatus::Playing)]
+        );
+    }
+
+    #[test]
+    fn diff_of_removed_or_moved_entries_only_lists_entries() {
+        let old = tracklist(&[1, 2, 3, 4]);
+
+        let mut new = old.clone();
+        new.remove(0);
+        let diff = old.diff(&new);
+        assert_eq!(diff.entries, [1, 2, 3]);
+        assert!(diff.changed.is_empty());
+
+        let mut new = old.clone();
+        new.move_track(3, 1);
+        let diff = old.diff(&new);
+        assert_eq!(diff.entries, [0, 3, 1, 2]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn entries_of_the_same_track_are_told_apart() {
+        let mut old = tracklist(&[1, 2]);
+        old.push(Track {
+            id: 1,
+            ..Default::default()
+        });
+        assert_eq!(old.entries(), [0, 1, 2]);
+
+        let mut new = old.clone();
+        new.remove(0);
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.entries, [1, 2]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(new.entry_position(2), Some(1));
+    }
+
+    #[test]
+    fn replacing_queue_numbers_entries_after_previous_ones() {
+        let old = tracklist(&[1, 2]);
+        let mut new = tracklist(&[1, 2]);
+        new.number_entries_after(&old);
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.entries, [2, 3]);
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(old.entry_position(0), Some(0));
+        assert_eq!(new.entry_position(0), None);
+    }
+
+    #[test]
+    fn tracklists_persisted_without_entries_are_numbered() {
+        let mut persisted = serde_json::to_value(tracklist(&[1, 2])).unwrap();
+        let persisted = persisted.as_object_mut().unwrap();
+        persisted.remove("entries");
+        persisted.remove("next_entry");
+
+        let mut tracklist: Tracklist = serde_json::from_value(persisted.clone().into()).unwrap();
+        tracklist.push(Track::default());
+
+        assert_eq!(tracklist.entries(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn tracks_persisted_without_disc_number_are_on_the_first_disc() {
+        let mut persisted = serde_json::to_value(tracklist(&[1])).unwrap();
+        persisted["queue"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("disc_number");
+
+        let tracklist: Tracklist = serde_json::from_value(persisted).unwrap();
+
+        assert_eq!(tracklist.queue[0].disc_number, 1);
+    }
+
+    fn ids(tracklist: &Tracklist) -> Vec<u32> {
+        tracklist.queue.iter().map(|track| track.id).collect()
+    }
+
+    fn statuses(tracklist: &Tracklist) -> Vec<TrackStatus> {
+        tracklist
+            .queue
+            .iter()
+            .map(|track| track.status.clone())
+            .collect()
+    }
+
+    #[test]
+    fn inserts_next_after_playing_track() {
+        let mut tracklist = tracklist(&[1, 2, 3]);
+        tracklist.skip_to_track(1);
+
+        tracklist.insert_next(Track {
+            id: 4,
+            status: TrackStatus::Playing,
+            ..Default::default()
+        });
+        tracklist.push(Track {
+            id: 5,
+            ..Default::default()
+        });
+
+        assert_eq!(ids(&tracklist), vec![1, 2, 4, 3, 5]);
+        assert_eq!(tracklist.currently_playing(), Some(2));
+        assert_eq!(tracklist.next_track().map(|track| track.id), Some(4));
+    }
+
+    #[test]
+    fn moving_tracks_keeps_playing_track() {
+        let mut tracklist = tracklist(&[1, 2, 3, 4]);
+        tracklist.skip_to_track(1);
+
+        assert!(tracklist.move_track(3, 0));
+        assert_eq!(ids(&tracklist), vec![4, 1, 2, 3]);
+        assert_eq!(
+            statuses(&tracklist),
+            vec![
+                TrackStatus::Played,
+                TrackStatus::Played,
+                TrackStatus::Playing,
+                TrackStatus::Unplayed
+            ]
+        );
+
+        assert!(tracklist.move_track(0, 3));
+        assert_eq!(ids(&tracklist), vec![1, 2, 3, 4]);
+        assert_eq!(tracklist.current_position(), 1);
+        assert_eq!(tracklist.queue[3].status, TrackStatus::Unplayed);
+
+        assert!(!tracklist.move_track(1, 4));
+    }
+
+    #[test]
+    fn play_next_position_accounts_for_removed_track() {
+        let mut tracklist = tracklist(&[1, 2, 3, 4]);
+        tracklist.skip_to_track(1);
+
+        assert_eq!(tracklist.play_next_position(0), 1);
+        assert_eq!(tracklist.play_next_position(3), 2);
+
+        let position = tracklist.play_next_position(0);
+        tracklist.move_track(0, position);
+        assert_eq!(ids(&tracklist), vec![2, 1, 3, 4]);
+        assert_eq!(tracklist.next_track().map(|track| track.id), Some(1));
+    }
+
+    #[test]
+    fn removes_and_clears_upcoming_tracks() {
+        let mut tracklist = tracklist(&[1, 2, 3, 4]);
+        tracklist.list_type = TracklistType::Track(Default::default());
+        tracklist.skip_to_track(1);
+
+        assert_eq!(tracklist.remove(2).map(|track| track.id), Some(3));
+        assert_eq!(tracklist.remove(9), None);
+        assert_eq!(ids(&tracklist), vec![1, 2, 4]);
+
+        tracklist.clear_upcoming();
+        assert_eq!(ids(&tracklist), vec![1, 2]);
+
+        tracklist.remove(0);
+        tracklist.remove(0);
+        assert_eq!(tracklist.list_type, TracklistType::None);
+    }
 }
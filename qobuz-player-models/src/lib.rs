@@ -1,3 +1,8 @@
+/// Tracks persisted before disc numbers were tracked are on the first disc.
+fn first_disc() -> u32 {
+    1
+}
+
 #[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum TrackStatus {
     Played,
@@ -12,6 +17,8 @@ pub struct Track {
     pub id: u32,
     pub title: String,
     pub number: u32,
+    #[serde(default = "first_disc")]
+    pub disc_number: u32,
     pub explicit: bool,
     pub hires_available: bool,
     pub available: bool,
@@ -23,9 +30,117 @@ pub struct Track {
     pub artist_id: Option<u32>,
     pub album_title: Option<String>,
     pub album_id: Option<String>,
+    #[serde(default)]
+    pub credits: Vec<Credit>,
+    #[serde(default)]
+    pub composer: Option<String>,
+    #[serde(default)]
+    pub work: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub copyright: Option<String>,
+    #[serde(default)]
+    pub isrc: Option<String>,
+    #[serde(default)]
+    pub release_date: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub audio_specs: Option<AudioSpecs>,
 }
 
+impl Track {
+    /// Classical recordings are part of a work and credit a composer, who is shown first.
+    pub fn is_classical(&self) -> bool {
+        self.work.is_some() && self.composer.is_some()
+    }
+
+    /// The title without the work it is a movement of, `"I. Allegro"` for
+    /// `"Symphony No. 5: I. Allegro"`.
+    pub fn movement_title(&self) -> &str {
+        self.work
+            .as_deref()
+            .and_then(|work| self.title.strip_prefix(work))
+            .map(|movement| movement.trim_start_matches([':', ' ', '-']))
+            .filter(|movement| !movement.is_empty())
+            .unwrap_or(&self.title)
+    }
+
+    /// Labelled details shown on the track page, leaving out the ones Qobuz has no value for.
+    pub fn detail_rows(&self) -> Vec<(String, String)> {
+        present_rows([
+            ("Work", self.work.clone()),
+            ("Version", self.version.clone()),
+            ("Composer", self.composer.clone()),
+            ("Released", self.release_date.clone()),
+            ("Quality", self.audio_specs.map(|specs| specs.to_string())),
+            ("Copyright", self.copyright.clone()),
+            ("ISRC", self.isrc.clone()),
+        ])
+    }
+}
+
+fn present_rows<const N: usize>(rows: [(&str, Option<String>); N]) -> Vec<(String, String)> {
+    rows.into_iter()
+        .filter_map(|(label, value)| value.map(|value| (label.to_string(), value)))
+        .collect()
+}
+
+/// Consecutive tracks of an album disc belonging to the same work.
 #[derive(Debug, Clone, PartialEq)]
+pub struct Work {
+    pub title: Option<String>,
+    pub composer: Option<String>,
+    pub disc_number: u32,
+    pub tracks: std::ops::Range<usize>,
+}
+
+/// Group consecutive tracks by work. Runs of tracks without a work form a group without title.
+///
+/// A work continued on the next disc starts a new group, so every group lies on one disc.
+pub fn works(tracks: &[Track]) -> Vec<Work> {
+    let mut works: Vec<Work> = vec![];
+
+    for (index, track) in tracks.iter().enumerate() {
+        match works.last_mut() {
+            Some(work) if work.title == track.work && work.disc_number == track.disc_number => {
+                work.tracks.end = index + 1
+            }
+            _ => works.push(Work {
+                title: track.work.clone(),
+                composer: track.composer.clone(),
+                disc_number: track.disc_number,
+                tracks: index..index + 1,
+            }),
+        }
+    }
+
+    works
+}
+
+/// A contributor to a recording together with the roles they had on it.
+#[derive(Default, Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Credit {
+    pub name: String,
+    pub roles: Vec<String>,
+}
+
+/// The highest quality a track or album is available in.
+#[derive(Default, Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AudioSpecs {
+    pub bit_depth: u32,
+    /// Sampling rate in kHz.
+    pub sampling_rate: f64,
+}
+
+impl std::fmt::Display for AudioSpecs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-bit / {} kHz", self.bit_depth, self.sampling_rate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct Album {
     pub id: String,
     pub title: String,
@@ -40,9 +155,31 @@ pub struct Album {
     pub image_thumbnail: String,
     pub duration_seconds: u32,
     pub description: Option<String>,
+    pub composer: Option<String>,
+    pub label: Option<String>,
+    pub copyright: Option<String>,
+    pub upc: Option<String>,
+    pub version: Option<String>,
+    pub release_date: Option<String>,
+    pub audio_specs: Option<AudioSpecs>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Album {
+    /// Labelled details shown on the album page, leaving out the ones Qobuz has no value for.
+    pub fn detail_rows(&self) -> Vec<(String, String)> {
+        present_rows([
+            ("Version", self.version.clone()),
+            ("Composer", self.composer.clone()),
+            ("Label", self.label.clone()),
+            ("Released", self.release_date.clone()),
+            ("Quality", self.audio_specs.map(|specs| specs.to_string())),
+            ("Copyright", self.copyright.clone()),
+            ("UPC", self.upc.clone()),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct AlbumSimple {
     pub id: String,
     pub title: String,
@@ -62,21 +199,79 @@ pub struct SearchResults {
     pub tracks: Vec<Track>,
 }
 
-#[derive(Default, Debug, Clone)]
+/// A search-as-you-type suggestion.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Suggestion {
+    Artist {
+        id: u32,
+        name: String,
+    },
+    Album {
+        id: String,
+        title: String,
+        artist: String,
+    },
+    Track {
+        id: u32,
+        title: String,
+        artist: Option<String>,
+    },
+    Playlist {
+        id: u32,
+        title: String,
+    },
+}
+
+impl Suggestion {
+    /// Text completing the search query.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Artist { name, .. } => name,
+            Self::Album { title, .. }
+            | Self::Track { title, .. }
+            | Self::Playlist { title, .. } => title,
+        }
+    }
+}
+
+/// Restricts search results to releases in hi-res or without explicit content.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchFilter {
+    pub hires_only: bool,
+    pub hide_explicit: bool,
+}
+
+impl SearchFilter {
+    pub fn matches(&self, hires_available: bool, explicit: bool) -> bool {
+        (!self.hires_only || hires_available) && !(self.hide_explicit && explicit)
+    }
+}
+
+/// One page of search results of a single type.
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SearchPage<T> {
+    pub query: String,
+    pub items: Vec<T>,
+    /// Offset of the following page, `None` on the last page.
+    pub next_offset: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone, serde::Serialize)]
 pub struct Favorites {
     pub albums: Vec<Album>,
     pub artists: Vec<Artist>,
     pub playlists: Vec<Playlist>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
 pub struct Artist {
     pub id: u32,
     pub name: String,
     pub image: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
 pub struct ArtistPage {
     pub id: u32,
     pub name: String,
@@ -85,7 +280,88 @@ pub struct ArtistPage {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+#[serde(try_from = "String", into = "&'static str")]
+pub enum ReleaseType {
+    #[default]
+    Album,
+    Live,
+    Compilation,
+    EpSingle,
+    Other,
+}
+
+impl ReleaseType {
+    /// Name of the release type in the Qobuz api, also used in urls.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Album => "album",
+            Self::Live => "live",
+            Self::Compilation => "compilation",
+            Self::EpSingle => "epSingle",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl std::str::FromStr for ReleaseType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "album" => Ok(Self::Album),
+            "live" => Ok(Self::Live),
+            "compilation" => Ok(Self::Compilation),
+            "epSingle" => Ok(Self::EpSingle),
+            "other" => Ok(Self::Other),
+            _ => Err(format!("unknown release type {value}")),
+        }
+    }
+}
+
+impl TryFrom<String> for ReleaseType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ReleaseType> for &'static str {
+    fn from(value: ReleaseType) -> Self {
+        value.as_str()
+    }
+}
+
+impl std::fmt::Display for ReleaseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Album => write!(f, "Albums"),
+            Self::Live => write!(f, "Live albums"),
+            Self::Compilation => write!(f, "Compilations"),
+            Self::EpSingle => write!(f, "EPs & singles"),
+            Self::Other => write!(f, "Other releases"),
+        }
+    }
+}
+
+/// One page of an artist's releases of a single type.
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ArtistReleases {
+    pub release_type: ReleaseType,
+    pub albums: Vec<AlbumSimple>,
+    pub has_more: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Genre {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
 pub struct Playlist {
     pub is_owned: bool,
     pub title: String,
@@ -95,3 +371,93 @@ pub struct Playlist {
     pub image: Option<String>,
     pub tracks: Vec<Track>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str, work: Option<&str>, disc_number: u32) -> Track {
+        Track {
+            title: title.to_string(),
+            work: work.map(str::to_string),
+            composer: work.map(|_| "Ludwig van Beethoven".to_string()),
+            disc_number,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn movement_title_strips_the_work() {
+        let symphony = Some("Symphony No. 5");
+
+        assert_eq!(
+            track("Symphony No. 5: I. Allegro", symphony, 1).movement_title(),
+            "I. Allegro"
+        );
+        assert_eq!(
+            track("Symphony No. 5 - II. Andante", symphony, 1).movement_title(),
+            "II. Andante"
+        );
+        assert_eq!(
+            track("Egmont Overture", symphony, 1).movement_title(),
+            "Egmont Overture"
+        );
+        assert_eq!(
+            track("Symphony No. 5", symphony, 1).movement_title(),
+            "Symphony No. 5"
+        );
+        assert_eq!(track("Intro", None, 1).movement_title(), "Intro");
+    }
+
+    #[test]
+    fn works_group_consecutive_tracks_per_disc() {
+        let tracks = [
+            track("Intro", None, 1),
+            track("Sonata: I.", Some("Sonata"), 1),
+            track("Sonata: II.", Some("Sonata"), 1),
+            track("Sonata: III.", Some("Sonata"), 2),
+            track("Interlude", None, 2),
+            track("Outro", None, 2),
+        ];
+
+        let works = works(&tracks);
+
+        let groups = works
+            .iter()
+            .map(|work| (work.title.as_deref(), work.disc_number, work.tracks.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                (None, 1, 0..1),
+                (Some("Sonata"), 1, 1..3),
+                (Some("Sonata"), 2, 3..4),
+                (None, 2, 4..6),
+            ]
+        );
+        assert_eq!(works[1].composer.as_deref(), Some("Ludwig van Beethoven"));
+        assert!(works[0].composer.is_none());
+    }
+
+    #[test]
+    fn detail_rows_leave_out_missing_values() {
+        let track = Track {
+            version: Some("Remastered".to_string()),
+            isrc: Some("GBAYE0601498".to_string()),
+            audio_specs: Some(AudioSpecs {
+                bit_depth: 24,
+                sampling_rate: 96.0,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            track.detail_rows(),
+            [
+                ("Version".to_string(), "Remastered".to_string()),
+                ("Quality".to_string(), "24-bit / 96 kHz".to_string()),
+                ("ISRC".to_string(), "GBAYE0601498".to_string()),
+            ]
+        );
+    }
+}
@@ -1,22 +1,48 @@
-use std::time::Duration;
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
 
 use mpris_server::{
     LoopStatus, Metadata, PlaybackRate, PlaybackStatus, PlayerInterface, Property, RootInterface,
-    Server, Time, TrackId, Volume,
+    Server, Signal, Time, TrackId, TrackListInterface, TrackListSignal, Volume,
     zbus::{self, fdo},
 };
 use qobuz_player_controls::{
-    PositionReceiver, Result, Status, StatusReceiver, TracklistReceiver, VolumeReceiver,
-    controls::Controls, error::Error,
+    PositionReceiver, Result, SeekedReceiver, Status, StatusReceiver, TracklistReceiver,
+    VolumeReceiver, controls::Controls, error::Error, tracklist::Tracklist,
 };
 use qobuz_player_models::Track;
 
+use crate::queue_ids::{QueueChange, QueueIds};
+
+mod queue_ids;
+
+/// How long adding a track as the current one waits for the player to queue it.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
 struct MprisPlayer {
     controls: Controls,
     position_receiver: PositionReceiver,
-    tracklist_receiver: TracklistReceiver,
     volume_receiver: VolumeReceiver,
     status_receiver: StatusReceiver,
+    tracklist_receiver: TracklistReceiver,
+    /// Web player the track links point to.
+    play_url: String,
+    /// The queue as last announced to clients, with the object path of every track.
+    queue_ids: Mutex<QueueIds>,
+}
+
+impl MprisPlayer {
+    fn queue_ids(&self) -> MutexGuard<'_, QueueIds> {
+        self.queue_ids.lock().expect("infailable")
+    }
+
+    fn position_of(&self, track_id: &TrackId) -> fdo::Result<u32> {
+        self.queue_ids()
+            .position(track_id.as_str())
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Unknown track {}", track_id.as_str())))
+    }
 }
 
 impl RootInterface for MprisPlayer {
@@ -45,13 +71,13 @@ impl RootInterface for MprisPlayer {
         Ok(false)
     }
     async fn has_track_list(&self) -> fdo::Result<bool> {
-        Ok(false)
+        Ok(true)
     }
     async fn desktop_entry(&self) -> fdo::Result<String> {
         Ok("com.github.sofusa-quboz-player".into())
     }
     async fn supported_uri_schemes(&self) -> fdo::Result<Vec<String>> {
-        Ok(vec![])
+        Ok(vec!["https".into(), "http".into()])
     }
     async fn supported_mime_types(&self) -> fdo::Result<Vec<String>> {
         Ok(vec![])
@@ -90,17 +116,32 @@ impl PlayerInterface for MprisPlayer {
     }
 
     async fn seek(&self, offset: Time) -> fdo::Result<()> {
-        let clock = Duration::from_secs(offset.as_secs() as u64);
-        self.controls.seek(clock);
+        self.controls.seek_relative(offset.as_millis());
         Ok(())
     }
 
-    async fn set_position(&self, _track_id: TrackId, _position: Time) -> fdo::Result<()> {
-        Err(fdo::Error::NotSupported("Not supported".into()))
+    async fn set_position(&self, track_id: TrackId, position: Time) -> fdo::Result<()> {
+        let length = match self.queue_ids().current() {
+            Some((path, track)) if path == track_id.as_str() => {
+                Time::from_secs(track.duration_seconds as i64)
+            }
+            // Requests for a track that is no longer playing are stale, the spec says to ignore them.
+            _ => return Ok(()),
+        };
+
+        if position.is_negative() || position > length {
+            return Ok(());
+        }
+
+        self.controls
+            .seek(Duration::from_millis(position.as_millis() as u64));
+        Ok(())
     }
 
-    async fn open_uri(&self, _uri: String) -> fdo::Result<()> {
-        Err(fdo::Error::NotSupported("Not supported".into()))
+    async fn open_uri(&self, uri: String) -> fdo::Result<()> {
+        self.controls
+            .play_url(&uri)
+            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))
     }
 
     async fn playback_status(&self) -> fdo::Result<PlaybackStatus> {
@@ -137,14 +178,12 @@ impl PlayerInterface for MprisPlayer {
     }
 
     async fn metadata(&self) -> fdo::Result<Metadata> {
-        let tracklist = self.tracklist_receiver.borrow();
-        let current_track = tracklist.current_track();
-
-        if let Some(current_track) = current_track {
-            return Ok(track_to_metadata(current_track));
+        let metadata = match self.queue_ids().current() {
+            Some((path, track)) => track_to_metadata(path, track, &self.play_url),
+            None => Metadata::new(),
         };
 
-        Ok(Metadata::new())
+        Ok(metadata)
     }
 
     async fn volume(&self) -> fdo::Result<Volume> {
@@ -172,11 +211,11 @@ impl PlayerInterface for MprisPlayer {
     }
 
     async fn can_go_next(&self) -> fdo::Result<bool> {
-        Ok(true)
+        Ok(self.queue_ids().can_go_next())
     }
 
     async fn can_go_previous(&self) -> fdo::Result<bool> {
-        Ok(true)
+        Ok(self.queue_ids().can_go_previous())
     }
 
     async fn can_play(&self) -> fdo::Result<bool> {
@@ -196,21 +235,127 @@ impl PlayerInterface for MprisPlayer {
     }
 }
 
+impl TrackListInterface for MprisPlayer {
+    async fn get_tracks_metadata(&self, track_ids: Vec<TrackId>) -> fdo::Result<Vec<Metadata>> {
+        let queue_ids = self.queue_ids();
+
+        let metadata = track_ids
+            .iter()
+            .filter_map(|track_id| {
+                let track = queue_ids.track(track_id.as_str())?;
+                Some(track_to_metadata(track_id.as_str(), track, &self.play_url))
+            })
+            .collect();
+
+        Ok(metadata)
+    }
+
+    /// Only Qobuz track links can be added: right after the playing track, at the end, or to an
+    /// empty queue.
+    async fn add_track(
+        &self,
+        uri: String,
+        after_track: TrackId,
+        set_as_current: bool,
+    ) -> fdo::Result<()> {
+        let (next, position) = {
+            let queue_ids = self.queue_ids();
+            let current = queue_ids.current().map(|(path, _)| path);
+            let current_position = current.and_then(|path| queue_ids.position(path));
+
+            if current == Some(after_track.as_str()) {
+                (true, current_position.map_or(0, |position| position + 1))
+            } else if queue_ids
+                .last()
+                .is_none_or(|last| last == after_track.as_str())
+            {
+                (false, queue_ids.paths().count() as u32)
+            } else {
+                return Err(fdo::Error::NotSupported(
+                    "Tracks can only be added after the current or the last track".into(),
+                ));
+            }
+        };
+
+        let mut tracklist_receiver = self.tracklist_receiver.clone();
+        let queue_length = tracklist_receiver.borrow_and_update().queue().len();
+
+        let id = self
+            .controls
+            .queue_url(&uri, next)
+            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+
+        if set_as_current {
+            // The player edits the queue in its own time, skip once the track is in it.
+            let inserted = tokio::time::timeout(
+                QUEUE_TIMEOUT,
+                tracklist_receiver.wait_for(|tracklist| {
+                    tracklist.queue().len() > queue_length
+                        && tracklist
+                            .queue()
+                            .get(position as usize)
+                            .is_some_and(|track| track.id == id)
+                }),
+            )
+            .await
+            .is_ok_and(|inserted| inserted.is_ok());
+
+            if !inserted {
+                return Err(fdo::Error::Failed(
+                    "The track was not added to the queue".into(),
+                ));
+            }
+
+            self.controls.skip_to_position(position, true);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_track(&self, track_id: TrackId) -> fdo::Result<()> {
+        let entry = self.queue_ids().entry(track_id.as_str()).ok_or_else(|| {
+            fdo::Error::InvalidArgs(format!("Unknown track {}", track_id.as_str()))
+        })?;
+        self.controls.remove_from_queue(entry);
+        Ok(())
+    }
+
+    async fn go_to(&self, track_id: TrackId) -> fdo::Result<()> {
+        let position = self.position_of(&track_id)?;
+        self.controls.skip_to_position(position, true);
+        Ok(())
+    }
+
+    async fn tracks(&self) -> fdo::Result<Vec<TrackId>> {
+        Ok(self.queue_ids().paths().map(track_id).collect())
+    }
+
+    async fn can_edit_tracks(&self) -> fdo::Result<bool> {
+        Ok(true)
+    }
+}
+
 pub async fn init(
     position_receiver: PositionReceiver,
+    mut seeked_receiver: SeekedReceiver,
     mut tracklist_receiver: TracklistReceiver,
     mut volume_receiver: VolumeReceiver,
     mut status_receiver: StatusReceiver,
     controls: Controls,
+    play_url: String,
 ) -> Result<()> {
-    let Ok(server) = Server::new(
+    let queue_ids = QueueIds::new(&tracklist_receiver.borrow_and_update());
+
+    let Ok(server) = Server::new_with_track_list(
         "com.github.sofusa-quboz-player",
         MprisPlayer {
             controls,
             position_receiver,
-            tracklist_receiver: tracklist_receiver.clone(),
+            queue_ids: Mutex::new(queue_ids),
             volume_receiver: volume_receiver.clone(),
             status_receiver: status_receiver.clone(),
+            tracklist_receiver: tracklist_receiver.clone(),
+            play_url: play_url.trim_end_matches('/').to_string(),
         },
     )
     .await
@@ -222,27 +367,52 @@ pub async fn init(
         tokio::select! {
             Ok(_) = tracklist_receiver.changed() => {
                 let tracklist = tracklist_receiver.borrow_and_update().clone();
-                let current_track = tracklist.current_track();
-
-                if let Some(current_track) = current_track {
-                    let metadata = track_to_metadata(current_track);
-
-                    let current_position = tracklist.current_position();
-                    let total_tracks = tracklist.total();
+                let signals = track_list_signals(
+                    &mut server.imp().queue_ids(),
+                    &tracklist,
+                    &server.imp().play_url,
+                );
+
+                for signal in signals {
+                    let Ok(_) = server.track_list_emit(signal).await else {
+                        return Err(Error::MprisPropertyError { property: "Tracks".into() });
+                    };
+                }
 
-                    let can_previous = current_position != 0;
-                    let can_next = !(total_tracks != 0 && current_position == total_tracks - 1);
+                let properties = {
+                    let queue_ids = server.imp().queue_ids();
+                    // keep the last metadata while stopped, clear it once the queue is empty
+                    let metadata = match (queue_ids.current(), queue_ids.paths().next()) {
+                        (Some((path, track)), _) => {
+                            Some(track_to_metadata(path, track, &server.imp().play_url))
+                        }
+                        (None, None) => Some(Metadata::new()),
+                        (None, Some(_)) => None,
+                    };
 
-                    let Ok(_) = server
-                        .properties_changed([
-                            Property::Metadata(metadata),
-                            Property::CanGoPrevious(can_previous),
-                            Property::CanGoNext(can_next),
+                    metadata
+                        .map(Property::Metadata)
+                        .into_iter()
+                        .chain([
+                            Property::CanGoPrevious(queue_ids.can_go_previous()),
+                            Property::CanGoNext(queue_ids.can_go_next()),
                         ])
-                        .await else {
-                            return Err(Error::MprisPropertyError { property: "Metadata, CanGoPrevious, CanGoNext".into() });
-                        };
-                }
+                        .collect::<Vec<_>>()
+                };
+
+                let Ok(_) = server
+                    .properties_changed(properties)
+                    .await else {
+                        return Err(Error::MprisPropertyError { property: "Metadata, CanGoPrevious, CanGoNext".into() });
+                    };
+            },
+            Ok(_) = seeked_receiver.changed() => {
+                let position = *seeked_receiver.borrow_and_update();
+                let Ok(_) = server
+                    .emit(Signal::Seeked { position: Time::from_millis(position.as_millis() as i64) })
+                    .await else {
+                        return Err(Error::MprisPropertyError { property: "Seeked".into() });
+                    };
             },
             Ok(_) = volume_receiver.changed() => {
                 let volume = *volume_receiver.borrow_and_update();
@@ -279,23 +449,84 @@ pub async fn init(
     }
 }
 
-fn track_to_metadata(track: &Track) -> Metadata {
+/// Update the track paths to the new queue and tell clients what changed.
+fn track_list_signals(
+    queue_ids: &mut QueueIds,
+    tracklist: &Tracklist,
+    play_url: &str,
+) -> Vec<TrackListSignal> {
+    match queue_ids.update(tracklist) {
+        QueueChange::Replaced => vec![TrackListSignal::TrackListReplaced {
+            tracks: queue_ids.paths().map(track_id).collect(),
+            current_track: queue_ids
+                .current()
+                .map_or(TrackId::NO_TRACK, |(path, _)| track_id(path)),
+        }],
+        QueueChange::Edited { removed, added } => {
+            let removed = removed.iter().map(|path| TrackListSignal::TrackRemoved {
+                track_id: track_id(path),
+            });
+
+            let added = added.into_iter().filter_map(|position| {
+                let (path, track) = queue_ids.get(position)?;
+                let after_track = match position {
+                    0 => TrackId::NO_TRACK,
+                    _ => queue_ids
+                        .path(position - 1)
+                        .map_or(TrackId::NO_TRACK, track_id),
+                };
+
+                Some(TrackListSignal::TrackAdded {
+                    metadata: track_to_metadata(path, track, play_url),
+                    after_track,
+                })
+            });
+
+            removed.chain(added).collect()
+        }
+    }
+}
+
+fn track_id(path: &str) -> TrackId {
+    TrackId::try_from(path).expect("infailable")
+}
+
+fn track_to_metadata(path: &str, track: &Track, play_url: &str) -> Metadata {
     let mut metadata = Metadata::new();
+    metadata.set_trackid(Some(track_id(path)));
     let duration = mpris_server::Time::from_secs(track.duration_seconds as i64);
     metadata.set_length(Some(duration));
 
     metadata.set_album(track.album_title.clone());
     metadata.set_art_url(track.image.clone());
 
-    // artist
+    // artist, with the composer first for classical works
     let artist_name = track.artist_name.clone();
+    let artists = match (track.is_classical(), &track.composer) {
+        (true, Some(composer)) => Some(
+            std::iter::once(composer.clone())
+                .chain(artist_name.clone())
+                .collect::<Vec<_>>(),
+        ),
+        _ => artist_name.as_ref().map(|a| vec![a.clone()]),
+    };
 
-    metadata.set_artist(artist_name.as_ref().map(|a| vec![a]));
+    metadata.set_artist(artists);
     metadata.set_album_artist(artist_name.as_ref().map(|a| vec![a]));
+    metadata.set_composer(track.composer.as_ref().map(|c| vec![c]));
 
     // track
-    metadata.set_title(Some(track.title.clone()));
+    let title = match &track.work {
+        Some(work) if track.is_classical() => format!("{work}: {}", track.movement_title()),
+        _ => track.title.clone(),
+    };
+    metadata.set_title(Some(title));
     metadata.set_track_number(Some(track.number as i32));
+    metadata.set_disc_number(Some(track.disc_number as i32));
+    metadata.set_genre(track.genre.as_ref().map(|genre| vec![genre]));
+    metadata.set_url(Some(format!("{play_url}/track/{}", track.id)));
+    // not part of xesam, so under our own namespace
+    metadata.set("qobuz:explicit", Some(track.explicit));
 
     metadata
 }
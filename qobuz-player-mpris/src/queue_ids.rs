@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+
+use qobuz_player_controls::tracklist::{Tracklist, TracklistType};
+use qobuz_player_models::{Track, TrackStatus};
+
+const TRACK_PATH: &str = "/com/github/sofusa/quboz_player/track";
+
+/// Object paths for the tracks in the queue. A track keeps its path for as long as it stays
+/// in the queue, so clients can follow tracks being added and removed.
+#[derive(Debug, Default)]
+pub(crate) struct QueueIds {
+    entries: Vec<(String, Track)>,
+    /// Queue entry id of each track, see [`Tracklist::entries`].
+    queue_entries: Vec<u64>,
+    list_type: TracklistType,
+    next_id: u64,
+}
+
+/// How the queue changed since the last update, in terms of the TrackList signals.
+#[derive(Debug, PartialEq)]
+pub(crate) enum QueueChange {
+    /// Another album or playlist, or tracks moved around.
+    Replaced,
+    Edited {
+        removed: Vec<String>,
+        /// Positions of new tracks, in queue order.
+        added: Vec<u32>,
+    },
+}
+
+impl QueueIds {
+    pub(crate) fn new(tracklist: &Tracklist) -> Self {
+        let mut queue_ids = Self::default();
+        queue_ids.update(tracklist);
+        queue_ids
+    }
+
+    pub(crate) fn update(&mut self, tracklist: &Tracklist) -> QueueChange {
+        self.queue_entries = tracklist.entries().to_vec();
+        self.update_queue(tracklist.queue(), tracklist.list_type())
+    }
+
+    fn update_queue(&mut self, queue: &[Track], list_type: &TracklistType) -> QueueChange {
+        // Paths of the old entries by track id, the first in the queue last.
+        let mut unused: HashMap<u32, Vec<&String>> = HashMap::new();
+        for (path, track) in self.entries.iter().rev() {
+            unused.entry(track.id).or_default().push(path);
+        }
+
+        let mut kept = Vec::new();
+        let entries: Vec<(String, Track)> = queue
+            .iter()
+            .map(|track| {
+                let reused = unused.get_mut(&track.id).and_then(|paths| paths.pop());
+
+                let path = match reused {
+                    Some(path) => {
+                        kept.push(path.clone());
+                        path.clone()
+                    }
+                    None => {
+                        self.next_id += 1;
+                        format!("{TRACK_PATH}/{}", self.next_id)
+                    }
+                };
+
+                (path, track.clone())
+            })
+            .collect();
+
+        let kept_paths: HashSet<&String> = kept.iter().collect();
+
+        let (kept_in_old_order, removed): (Vec<&String>, Vec<&String>) = self
+            .entries
+            .iter()
+            .map(|(path, _)| path)
+            .partition(|path| kept_paths.contains(path));
+        let removed: Vec<String> = removed.into_iter().cloned().collect();
+        let moved = kept_in_old_order.into_iter().ne(kept.iter());
+
+        let replaced =
+            moved || list_type != &self.list_type || (kept.is_empty() && !self.entries.is_empty());
+
+        let added = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (path, _))| !kept_paths.contains(path))
+            .map(|(position, _)| position as u32)
+            .collect();
+
+        self.entries = entries;
+        self.list_type = list_type.clone();
+
+        match replaced {
+            true => QueueChange::Replaced,
+            false => QueueChange::Edited { removed, added },
+        }
+    }
+
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(path, _)| path.as_str())
+    }
+
+    pub(crate) fn path(&self, position: u32) -> Option<&str> {
+        self.entries
+            .get(position as usize)
+            .map(|(path, _)| path.as_str())
+    }
+
+    pub(crate) fn position(&self, path: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .position(|(entry, _)| entry == path)
+            .map(|position| position as u32)
+    }
+
+    /// Queue entry id of the track with path `path`.
+    pub(crate) fn entry(&self, path: &str) -> Option<u64> {
+        let position = self.position(path)?;
+        self.queue_entries.get(position as usize).copied()
+    }
+
+    pub(crate) fn get(&self, position: u32) -> Option<(&str, &Track)> {
+        self.entries
+            .get(position as usize)
+            .map(|(path, track)| (path.as_str(), track))
+    }
+
+    pub(crate) fn track(&self, path: &str) -> Option<&Track> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == path)
+            .map(|(_, track)| track)
+    }
+
+    /// The playing track and its path.
+    pub(crate) fn current(&self) -> Option<(&str, &Track)> {
+        self.entries
+            .iter()
+            .find(|(_, track)| track.status == TrackStatus::Playing)
+            .map(|(path, track)| (path.as_str(), track))
+    }
+
+    pub(crate) fn last(&self) -> Option<&str> {
+        self.entries.last().map(|(path, _)| path.as_str())
+    }
+
+    /// Position of the playing track, the start of the queue while nothing plays.
+    fn current_position(&self) -> usize {
+        self.entries
+            .iter()
+            .position(|(_, track)| track.status == TrackStatus::Playing)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn can_go_previous(&self) -> bool {
+        !self.entries.is_empty() && self.current_position() != 0
+    }
+
+    pub(crate) fn can_go_next(&self) -> bool {
+        self.current_position() + 1 < self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qobuz_player_controls::tracklist::AlbumTracklist;
+
+    use super::*;
+
+    fn update(queue_ids: &mut QueueIds, ids: &[u32]) -> QueueChange {
+        let queue: Vec<Track> = ids
+            .iter()
+            .map(|id| Track {
+                id: *id,
+                ..Default::default()
+            })
+            .collect();
+
+        queue_ids.update_queue(&queue, &TracklistType::Album(AlbumTracklist::default()))
+    }
+
+    fn queue_ids(ids: &[u32]) -> QueueIds {
+        let mut queue_ids = QueueIds::default();
+        update(&mut queue_ids, ids);
+        queue_ids
+    }
+
+    #[test]
+    fn tracks_keep_their_paths_when_tracks_are_added_and_removed() {
+        let mut queue_ids = queue_ids(&[1, 2, 3]);
+        let paths: Vec<String> = queue_ids.paths().map(String::from).collect();
+
+        let change = update(&mut queue_ids, &[1, 3, 4]);
+
+        assert_eq!(
+            change,
+            QueueChange::Edited {
+                removed: vec![paths[1].clone()],
+                added: vec![2],
+            }
+        );
+        assert_eq!(queue_ids.path(0), Some(paths[0].as_str()));
+        assert_eq!(queue_ids.path(1), Some(paths[2].as_str()));
+        assert_eq!(queue_ids.position(&paths[2]), Some(1));
+        assert!(queue_ids.track(&paths[1]).is_none());
+    }
+
+    #[test]
+    fn moved_tracks_replace_the_track_list() {
+        let mut queue_ids = queue_ids(&[1, 2, 3]);
+
+        assert_eq!(update(&mut queue_ids, &[2, 1, 3]), QueueChange::Replaced);
+        assert_eq!(update(&mut queue_ids, &[5, 6]), QueueChange::Replaced);
+    }
+
+    #[test]
+    fn can_go_next_and_previous_follow_the_playing_track() {
+        let mut queue_ids = QueueIds::default();
+        assert!(!queue_ids.can_go_previous());
+        assert!(!queue_ids.can_go_next());
+
+        let mut queue: Vec<Track> = [1, 2, 3]
+            .into_iter()
+            .map(|id| Track {
+                id,
+                ..Default::default()
+            })
+            .collect();
+        let list_type = TracklistType::Album(AlbumTracklist::default());
+
+        queue_ids.update_queue(&queue, &list_type);
+        assert!(!queue_ids.can_go_previous());
+        assert!(queue_ids.can_go_next());
+
+        queue[2].status = TrackStatus::Playing;
+        queue_ids.update_queue(&queue, &list_type);
+        assert!(queue_ids.can_go_previous());
+        assert!(!queue_ids.can_go_next());
+    }
+
+    #[test]
+    fn a_track_queued_twice_gets_two_paths() {
+        let mut queue_ids = queue_ids(&[1]);
+
+        let change = update(&mut queue_ids, &[1, 1]);
+
+        assert_eq!(
+            change,
+            QueueChange::Edited {
+                removed: vec![],
+                added: vec![1],
+            }
+        );
+        assert_ne!(queue_ids.path(0), queue_ids.path(1));
+    }
+}
@@ -35,7 +35,7 @@ pub(crate) struct App {
     pub(crate) broadcast: Arc<NotificationBroadcast>,
 }
 
-#[derive(Default, PartialEq)]
+#[derive(Default)]
 pub(crate) enum AppState {
     #[default]
     Normal,
@@ -56,6 +56,7 @@ pub(crate) enum PlayOutcome {
     Playlist((u32, bool)),
     Track(u32),
     SkipToPosition(u32),
+    Url(String),
 }
 
 #[derive(Default, PartialEq)]
@@ -112,6 +113,10 @@ impl App {
                     self.should_draw = true;
                 },
 
+                Ok(_) = self.search.suggestions.changed() => {
+                    self.should_draw = true;
+                }
+
                 Ok(_) = self.status.changed() => {
                     let status = self.status.borrow_and_update();
                     self.now_playing.status = *status;
@@ -234,6 +239,10 @@ impl App {
                         self.controls.jump_backward();
                         self.should_draw = true;
                     }
+                    KeyCode::Char('o') => {
+                        self.app_state = AppState::Popup(Popup::OpenLink(Default::default()));
+                        self.should_draw = true;
+                    }
                     _ => {}
                 };
             }
@@ -261,6 +270,12 @@ impl App {
             PlayOutcome::SkipToPosition(index) => {
                 self.controls.skip_to_position(index, true);
             }
+
+            PlayOutcome::Url(url) => {
+                if let Err(err) = self.controls.play_url(&url) {
+                    self.broadcast.send_error(err.to_string());
+                }
+            }
         }
     }
 
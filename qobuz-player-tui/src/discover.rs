@@ -1,4 +1,7 @@
-use qobuz_player_models::{AlbumSimple, Playlist};
+use std::sync::Arc;
+
+use qobuz_player_controls::{Result, client::Client};
+use qobuz_player_models::{AlbumSimple, Genre, Playlist};
 use ratatui::{
     crossterm::event::{Event, KeyCode, KeyEventKind},
     prelude::*,
@@ -12,20 +15,46 @@ use crate::{
 };
 
 pub(crate) struct DiscoverState {
+    pub(crate) client: Arc<Client>,
     pub(crate) featured_albums: Vec<(String, UnfilteredListState<AlbumSimple>)>,
     pub(crate) featured_playlists: Vec<(String, UnfilteredListState<Playlist>)>,
+    pub(crate) genres: UnfilteredListState<Genre>,
+    pub(crate) genre: Option<Genre>,
+    pub(crate) picking_genre: bool,
     pub(crate) sub_tab: usize,
 }
 
 impl DiscoverState {
     pub(crate) fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if self.picking_genre {
+            let rows = std::iter::once("All genres".to_string())
+                .chain(self.genres.items.iter().map(|genre| genre.name.clone()))
+                .map(|name| Row::new(Line::from(name)))
+                .collect::<Vec<_>>();
+
+            let table = basic_list_table(rows, "Genre");
+            frame.render_stateful_widget(table, area, &mut self.genres.state);
+            return;
+        }
+
+        let genre_name = self.genre.as_ref().map(|genre| genre.name.clone());
+        let title = |name: &str| match &genre_name {
+            Some(genre_name) => format!("{name}: {genre_name}"),
+            None => name.to_string(),
+        };
+
         let is_album = self.album_selected();
 
+        let title = match is_album {
+            true => title(&self.featured_albums[self.sub_tab].0),
+            false => title(&self.featured_playlists[self.sub_tab - self.featured_albums.len()].0),
+        };
+
         let (table, state) = match is_album {
             true => {
                 let list_state = &mut self.featured_albums[self.sub_tab];
                 (
-                    album_simple_table(&list_state.1.items, &list_state.0),
+                    album_simple_table(&list_state.1.items, &title),
                     &mut list_state.1.state,
                 )
             }
@@ -40,7 +69,7 @@ impl DiscoverState {
                             .iter()
                             .map(|playlist| Row::new(Line::from(playlist.title.clone())))
                             .collect::<Vec<_>>(),
-                        &list_state.0,
+                        &title,
                     ),
                     &mut list_state.1.state,
                 )
@@ -52,8 +81,44 @@ impl DiscoverState {
 
     pub(crate) async fn handle_events(&mut self, event: Event) -> Output {
         match event {
+            Event::Key(key_event)
+                if key_event.kind == KeyEventKind::Press && self.picking_genre =>
+            {
+                match key_event.code {
+                    KeyCode::Esc => {
+                        self.picking_genre = false;
+                        Output::Consumed
+                    }
+                    KeyCode::Down => {
+                        self.genres.state.select_next();
+                        Output::Consumed
+                    }
+                    KeyCode::Up => {
+                        self.genres.state.select_previous();
+                        Output::Consumed
+                    }
+                    KeyCode::Enter => {
+                        let genre = match self.genres.state.selected() {
+                            Some(0) | None => None,
+                            Some(index) => self.genres.items.get(index - 1).cloned(),
+                        };
+
+                        self.picking_genre = false;
+
+                        if let Err(err) = self.set_genre(genre).await {
+                            return Output::Error(format!("{err}"));
+                        }
+                        Output::Consumed
+                    }
+                    _ => Output::Consumed,
+                }
+            }
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 match key_event.code {
+                    KeyCode::Char('g') => {
+                        self.picking_genre = true;
+                        Output::Consumed
+                    }
                     KeyCode::Left => {
                         self.cycle_subtab_backwards();
                         Output::Consumed
@@ -108,6 +173,46 @@ impl DiscoverState {
         }
     }
 
+    async fn set_genre(&mut self, genre: Option<Genre>) -> Result<()> {
+        let genre_id = genre.as_ref().map(|genre| genre.id);
+
+        let (featured_albums, featured_playlists) = tokio::try_join!(
+            self.client.featured_albums(genre_id),
+            self.client.featured_playlists(genre_id),
+        )?;
+
+        self.featured_albums = featured_albums
+            .into_iter()
+            .map(|(name, items)| {
+                (
+                    name,
+                    UnfilteredListState {
+                        items,
+                        state: Default::default(),
+                    },
+                )
+            })
+            .collect();
+
+        self.featured_playlists = featured_playlists
+            .into_iter()
+            .map(|(name, items)| {
+                (
+                    name,
+                    UnfilteredListState {
+                        items,
+                        state: Default::default(),
+                    },
+                )
+            })
+            .collect();
+
+        self.genre = genre;
+        self.sub_tab = 0;
+
+        Ok(())
+    }
+
     fn album_selected(&self) -> bool {
         self.sub_tab < self.featured_albums.len()
     }
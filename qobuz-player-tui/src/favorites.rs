@@ -1,6 +1,6 @@
 use std::{fmt, sync::Arc};
 
-use qobuz_player_controls::client::Client;
+use qobuz_player_controls::{ReleaseSort, client::Client};
 use qobuz_player_models::{Album, Artist, Playlist};
 use ratatui::{
     crossterm::event::{Event, KeyCode, KeyEventKind},
@@ -11,7 +11,7 @@ use tui_input::{Input, backend::crossterm::EventHandler};
 
 use crate::{
     app::{FilteredListState, Output, PlayOutcome},
-    popup::{ArtistPopupState, PlaylistPopupState, Popup},
+    popup::{ArtistPopupState, DetailsPopupState, PlaylistPopupState, Popup},
     ui::{album_table, basic_list_table, render_input},
 };
 
@@ -138,6 +138,20 @@ impl FavoritesState {
                             self.current_list_state().select_previous();
                             Output::Consumed
                         }
+                        KeyCode::Char('i') if self.sub_tab == SubTab::Albums => {
+                            let index = self.albums.state.selected();
+                            let Some(selected) = index.map(|index| &self.albums.filter[index])
+                            else {
+                                return Output::Consumed;
+                            };
+
+                            match self.client.album(&selected.id).await {
+                                Ok(album) => {
+                                    Output::Popup(Popup::Details(DetailsPopupState::album(&album)))
+                                }
+                                Err(err) => Output::Error(format!("{err}")),
+                            }
+                        }
                         KeyCode::Enter => match self.sub_tab {
                             SubTab::Albums => {
                                 let index = self.albums.state.selected();
@@ -159,15 +173,18 @@ impl FavoritesState {
                                     return Output::Consumed;
                                 };
 
-                                let artist_albums =
-                                    match self.client.artist_albums(selected.id).await {
-                                        Ok(res) => res,
-                                        Err(err) => return Output::Error(format!("{err}")),
-                                    };
+                                let releases = match self
+                                    .client
+                                    .artist_discography(selected.id, ReleaseSort::ReleaseDate)
+                                    .await
+                                {
+                                    Ok(res) => res,
+                                    Err(err) => return Output::Error(format!("{err}")),
+                                };
 
                                 Output::Popup(Popup::Artist(ArtistPopupState {
                                     artist_name: selected.name.clone(),
-                                    albums: artist_albums,
+                                    releases,
                                     state: Default::default(),
                                 }))
                             }
@@ -9,7 +9,7 @@ use qobuz_player_controls::{
 use queue::QueueState;
 use ratatui::{prelude::*, widgets::*};
 use search::SearchState;
-use tokio::try_join;
+use tokio::{join, try_join};
 use ui::center;
 
 mod app;
@@ -33,11 +33,16 @@ pub async fn init(
 
     draw_loading_screen(&mut terminal);
 
-    let (favorites, featured_albums, featured_playlists) = try_join!(
-        client.favorites(),
-        client.featured_albums(),
-        client.featured_playlists(),
-    )?;
+    let (genres, featured) = join!(client.genres(), async {
+        try_join!(
+            client.favorites(),
+            client.featured_albums(None),
+            client.featured_playlists(None),
+        )
+    });
+    let (favorites, featured_albums, featured_playlists) = featured?;
+    // Without genres the discover tab only offers the unfiltered lists.
+    let genres = genres.unwrap_or_default();
 
     let featured_albums = featured_albums
         .into_iter()
@@ -70,6 +75,7 @@ pub async fn init(
     let now_playing = get_current_state(tracklist_value, status_value).await;
 
     let client_clone = client.clone();
+    let suggestions_tx = tokio::sync::watch::Sender::new(vec![]);
 
     let mut app = App {
         broadcast,
@@ -104,7 +110,7 @@ pub async fn init(
             sub_tab: Default::default(),
         },
         search: SearchState {
-            client: client_clone,
+            client: client_clone.clone(),
             editing: Default::default(),
             filter: Default::default(),
             albums: UnfilteredListState {
@@ -124,16 +130,30 @@ pub async fn init(
                 state: Default::default(),
             },
             sub_tab: Default::default(),
+            search_filter: Default::default(),
+            next_offset: None,
+            suggestions: suggestions_tx.subscribe(),
+            suggestions_tx,
+            selected_suggestion: None,
+            suggestions_request: None,
         },
         queue: QueueState {
             queue: UnfilteredListState {
                 items: Default::default(),
                 state: Default::default(),
             },
+            window_start: 0,
         },
         discover: discover::DiscoverState {
+            client: client_clone,
             featured_albums,
             featured_playlists,
+            genres: UnfilteredListState {
+                items: genres,
+                state: Default::default(),
+            },
+            genre: None,
+            picking_genre: false,
             sub_tab: Default::default(),
         },
     };
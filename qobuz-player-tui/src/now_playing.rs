@@ -55,11 +55,22 @@ pub(crate) fn render(frame: &mut Frame, area: Rect, state: &mut NowPlayingState)
         lines.push(Line::from(album.clone()).style(Style::new().bold()));
     }
 
+    if track.is_classical()
+        && let Some(composer) = &track.composer
+    {
+        lines.push(Line::from(composer.clone()));
+    }
+
     if let Some(artist) = &track.artist_name {
         lines.push(Line::from(artist.clone()));
     }
 
-    lines.push(Line::from(track.title.clone()));
+    match &track.work {
+        Some(work) if track.is_classical() => {
+            lines.push(Line::from(format!("{work}: {}", track.movement_title())))
+        }
+        _ => lines.push(Line::from(track.title.clone())),
+    }
 
     let track_number = if state.show_tracklist_position {
         state.tracklist_position + 1
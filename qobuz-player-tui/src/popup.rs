@@ -1,18 +1,49 @@
-use qobuz_player_models::AlbumSimple;
+use qobuz_player_models::{Album, AlbumSimple, ArtistReleases, Track};
 use ratatui::{crossterm::event::KeyCode, prelude::*, widgets::*};
+use tui_input::{Input, InputRequest};
 
 use crate::{
     app::PlayOutcome,
-    ui::{block, center},
+    ui::{block, center, render_input},
 };
 
 #[derive(PartialEq)]
 pub(crate) struct ArtistPopupState {
     pub artist_name: String,
-    pub albums: Vec<AlbumSimple>,
+    pub releases: Vec<ArtistReleases>,
     pub state: ListState,
 }
 
+impl ArtistPopupState {
+    /// Rows of the popup, `None` being the heading of a release group.
+    fn rows(&self) -> Vec<Option<&AlbumSimple>> {
+        self.releases
+            .iter()
+            .flat_map(|releases| std::iter::once(None).chain(releases.albums.iter().map(Some)))
+            .collect()
+    }
+
+    fn select_next(&mut self) {
+        let rows = self.rows();
+        let start = self.state.selected().map_or(0, |index| index + 1);
+
+        let next = (start..rows.len()).find(|index| rows[*index].is_some());
+        if next.is_some() {
+            self.state.select(next);
+        }
+    }
+
+    fn select_previous(&mut self) {
+        let rows = self.rows();
+        let end = self.state.selected().unwrap_or(0);
+
+        let previous = (0..end).rev().find(|index| rows[*index].is_some());
+        if previous.is_some() {
+            self.state.select(previous);
+        }
+    }
+}
+
 #[derive(PartialEq)]
 pub(crate) struct PlaylistPopupState {
     pub playlist_name: String,
@@ -21,27 +52,99 @@ pub(crate) struct PlaylistPopupState {
 }
 
 #[derive(PartialEq)]
+pub(crate) struct DetailsPopupState {
+    pub title: String,
+    pub rows: Vec<(String, String)>,
+    pub scroll: u16,
+}
+
+impl DetailsPopupState {
+    pub(crate) fn track(track: &Track) -> Self {
+        let names = [
+            ("Artist", track.artist_name.clone()),
+            ("Album", track.album_title.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| value.map(|value| (label.to_string(), value)));
+
+        let credits = track
+            .credits
+            .iter()
+            .map(|credit| (credit.name.clone(), credit.roles.join(", ")));
+
+        Self {
+            title: track.title.clone(),
+            rows: names.chain(track.detail_rows()).chain(credits).collect(),
+            scroll: 0,
+        }
+    }
+
+    pub(crate) fn album(album: &Album) -> Self {
+        let artist = ("Artist".to_string(), album.artist.name.clone());
+
+        Self {
+            title: album.title.clone(),
+            rows: std::iter::once(artist).chain(album.detail_rows()).collect(),
+            scroll: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct OpenLinkPopupState {
+    pub input: Input,
+}
+
+impl OpenLinkPopupState {
+    fn handle_key(&mut self, key: KeyCode) {
+        let request = match key {
+            KeyCode::Char(c) => InputRequest::InsertChar(c),
+            KeyCode::Backspace => InputRequest::DeletePrevChar,
+            KeyCode::Delete => InputRequest::DeleteNextChar,
+            KeyCode::Left => InputRequest::GoToPrevChar,
+            KeyCode::Right => InputRequest::GoToNextChar,
+            KeyCode::Home => InputRequest::GoToStart,
+            KeyCode::End => InputRequest::GoToEnd,
+            _ => return,
+        };
+
+        self.input.handle(request);
+    }
+}
+
 pub(crate) enum Popup {
     Artist(ArtistPopupState),
     Playlist(PlaylistPopupState),
+    Details(DetailsPopupState),
+    OpenLink(OpenLinkPopupState),
 }
 
 impl Popup {
     pub(crate) fn render(&mut self, frame: &mut Frame) {
         match self {
             Popup::Artist(artist) => {
+                let list: Vec<ListItem> = artist
+                    .releases
+                    .iter()
+                    .flat_map(|releases| {
+                        let heading =
+                            ListItem::from(Line::from(releases.release_type.to_string()).bold());
+
+                        std::iter::once(heading).chain(
+                            releases
+                                .albums
+                                .iter()
+                                .map(|album| ListItem::from(Line::from(album.title.clone()))),
+                        )
+                    })
+                    .collect();
+
                 let area = center(
                     frame.area(),
                     Constraint::Percentage(50),
-                    Constraint::Length(artist.albums.len() as u16 + 2),
+                    Constraint::Length(list.len() as u16 + 2),
                 );
 
-                let list: Vec<ListItem> = artist
-                    .albums
-                    .iter()
-                    .map(|album| ListItem::from(Line::from(album.title.clone())))
-                    .collect();
-
                 let list = List::new(list)
                     .block(block(&artist.artist_name, false))
                     .highlight_style(Style::default().bg(Color::Blue))
@@ -63,6 +166,42 @@ impl Popup {
                 frame.render_widget(Clear, area);
                 frame.render_widget(tabs, area);
             }
+            Popup::Details(details) => {
+                let lines: Vec<Line> = details
+                    .rows
+                    .iter()
+                    .map(|(label, value)| {
+                        Line::from(vec![
+                            Span::from(format!("{label}: ")).bold(),
+                            Span::from(value.clone()),
+                        ])
+                    })
+                    .collect();
+
+                let area = center(
+                    frame.area(),
+                    Constraint::Percentage(60),
+                    Constraint::Length(lines.len() as u16 + 2),
+                );
+
+                let paragraph = Paragraph::new(lines)
+                    .block(block(&details.title, false))
+                    .wrap(Wrap { trim: false })
+                    .scroll((details.scroll, 0));
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(paragraph, area);
+            }
+            Popup::OpenLink(open_link) => {
+                let area = center(
+                    frame.area(),
+                    Constraint::Percentage(60),
+                    Constraint::Length(3),
+                );
+
+                frame.render_widget(Clear, area);
+                render_input(&open_link.input, true, area, frame, "Open Qobuz link");
+            }
         };
     }
 
@@ -70,18 +209,19 @@ impl Popup {
         match self {
             Popup::Artist(artist_popup_state) => match key {
                 KeyCode::Up => {
-                    artist_popup_state.state.select_previous();
+                    artist_popup_state.select_previous();
                     None
                 }
                 KeyCode::Down => {
-                    artist_popup_state.state.select_next();
+                    artist_popup_state.select_next();
                     None
                 }
                 KeyCode::Enter => {
                     let index = artist_popup_state.state.selected();
+                    let rows = artist_popup_state.rows();
 
                     let id = index
-                        .map(|index| &artist_popup_state.albums[index])
+                        .and_then(|index| rows.get(index).copied().flatten())
                         .map(|album| album.id.clone());
                     if let Some(id) = id {
                         return Some(PlayOutcome::Album(id));
@@ -105,6 +245,26 @@ impl Popup {
                 }
                 _ => None,
             },
+            Popup::Details(details_popup_state) => match key {
+                KeyCode::Up => {
+                    details_popup_state.scroll = details_popup_state.scroll.saturating_sub(1);
+                    None
+                }
+                KeyCode::Down => {
+                    details_popup_state.scroll = details_popup_state.scroll.saturating_add(1);
+                    None
+                }
+                _ => None,
+            },
+            Popup::OpenLink(open_link_popup_state) => match key {
+                KeyCode::Enter => Some(PlayOutcome::Url(
+                    open_link_popup_state.input.value().to_string(),
+                )),
+                key => {
+                    open_link_popup_state.handle_key(key);
+                    None
+                }
+            },
         }
     }
 }
@@ -8,20 +8,78 @@ use ratatui::{
 
 use crate::{
     app::{Output, PlayOutcome, UnfilteredListState},
+    popup::{DetailsPopupState, Popup},
     ui::basic_list_table,
 };
 
 pub(crate) struct QueueState {
     pub queue: UnfilteredListState<Track>,
+    /// First queue entry drawn. The whole queue is in memory, but only the entries that fit
+    /// on screen are turned into rows, which keeps long playlists cheap to render.
+    pub window_start: usize,
 }
 
 impl QueueState {
     pub(crate) fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let len = self.queue.items.len();
+        let visible_height = (area.height.saturating_sub(2) as usize).max(1);
+
+        if self
+            .queue
+            .state
+            .selected()
+            .is_some_and(|selected| selected >= len)
+        {
+            self.queue.state.select(len.checked_sub(1));
+        }
+
+        let multiple_discs = self.queue.items.windows(2).any(|tracks| {
+            tracks[0].album_id == tracks[1].album_id
+                && tracks[0].disc_number != tracks[1].disc_number
+        });
+        let items = &self.queue.items;
+        let disc_header = |index: usize| multiple_discs && starts_disc(items, index);
+        let row_height = |index: usize| 1 + disc_header(index) as usize;
+        let fits = |mut range: std::ops::RangeInclusive<usize>| {
+            range
+                .try_fold(0, |height, index| {
+                    Some(height + row_height(index)).filter(|height| *height <= visible_height)
+                })
+                .is_some()
+        };
+
+        if let Some(selected) = self.queue.state.selected() {
+            // Every row is at least one line high.
+            self.window_start = self
+                .window_start
+                .clamp(selected.saturating_sub(visible_height), selected);
+            while self.window_start < selected && !fits(self.window_start..=selected) {
+                self.window_start += 1;
+            }
+        }
+        // Fill the space below the last entry, like a list scrolled to the end.
+        self.window_start = self.window_start.min(len.saturating_sub(1));
+        while self.window_start > 0 && fits(self.window_start - 1..=len - 1) {
+            self.window_start -= 1;
+        }
+
+        let mut window_end = self.window_start;
+        let mut used_height = 0;
+        while window_end < len
+            && (window_end == self.window_start
+                || used_height + row_height(window_end) <= visible_height)
+        {
+            used_height += row_height(window_end);
+            window_end += 1;
+        }
+
         let table = basic_list_table(
             self.queue
                 .items
                 .iter()
                 .enumerate()
+                .skip(self.window_start)
+                .take(window_end - self.window_start)
                 .map(|(index, track)| {
                     let style = match track.status {
                         TrackStatus::Played => Style::default().add_modifier(Modifier::CROSSED_OUT),
@@ -31,15 +89,31 @@ impl QueueState {
                             Style::default().add_modifier(Modifier::CROSSED_OUT)
                         }
                     };
-                    Row::new(Line::from(vec![
+                    let line = Line::from(vec![
                         format!("{} {}", index + 1, track.title.clone()).set_style(style),
-                    ]))
+                    ]);
+
+                    match disc_header(index) {
+                        true => Row::new(Text::from(vec![
+                            Line::from(format!("Disc {}", track.disc_number)).bold(),
+                            line,
+                        ]))
+                        .height(2),
+                        false => Row::new(line),
+                    }
                 })
                 .collect(),
             " Queue ",
         );
 
-        frame.render_stateful_widget(table, area, &mut self.queue.state);
+        let mut window_state = TableState::default().with_selected(
+            self.queue
+                .state
+                .selected()
+                .map(|selected| selected - self.window_start),
+        );
+
+        frame.render_stateful_widget(table, area, &mut window_state);
     }
 
     pub(crate) async fn handle_events(&mut self, event: Event) -> Output {
@@ -62,6 +136,20 @@ impl QueueState {
                         }
                         Output::Consumed
                     }
+                    KeyCode::Char('i') => {
+                        let track = self
+                            .queue
+                            .state
+                            .selected()
+                            .and_then(|index| self.queue.items.get(index));
+
+                        match track {
+                            Some(track) => {
+                                Output::Popup(Popup::Details(DetailsPopupState::track(track)))
+                            }
+                            None => Output::Consumed,
+                        }
+                    }
 
                     _ => Output::NotConsumed,
                 }
@@ -70,3 +158,13 @@ impl QueueState {
         }
     }
 }
+
+/// Whether the track at `index` is the first of its disc in the queue.
+fn starts_disc(items: &[Track], index: usize) -> bool {
+    let track = &items[index];
+    let previous = index.checked_sub(1).map(|index| &items[index]);
+
+    previous.is_none_or(|previous| {
+        previous.album_id != track.album_id || previous.disc_number != track.disc_number
+    })
+}
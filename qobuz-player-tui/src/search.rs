@@ -1,18 +1,19 @@
 use std::{fmt, sync::Arc};
 
-use qobuz_player_controls::{Result, client::Client};
-use qobuz_player_models::{Album, Artist, Playlist, Track};
+use qobuz_player_controls::{ReleaseSort, Result, client::Client};
+use qobuz_player_models::{Album, Artist, Playlist, SearchFilter, Suggestion, Track};
 use ratatui::{
     crossterm::event::{Event, KeyCode, KeyEventKind},
     prelude::*,
     widgets::*,
 };
+use tokio::{sync::watch, task::AbortHandle};
 use tui_input::{Input, backend::crossterm::EventHandler};
 
 use crate::{
     app::{Output, PlayOutcome, UnfilteredListState},
-    popup::{ArtistPopupState, PlaylistPopupState, Popup},
-    ui::{album_table, basic_list_table, render_input},
+    popup::{ArtistPopupState, DetailsPopupState, PlaylistPopupState, Popup},
+    ui::{album_table, basic_list_table, block, render_input},
 };
 
 pub(crate) struct SearchState {
@@ -24,6 +25,14 @@ pub(crate) struct SearchState {
     pub playlists: UnfilteredListState<Playlist>,
     pub tracks: UnfilteredListState<Track>,
     pub sub_tab: SubTab,
+    pub search_filter: SearchFilter,
+    /// Offset of the next page of the active sub tab, `None` when everything is loaded.
+    pub next_offset: Option<u32>,
+    pub suggestions: watch::Receiver<Vec<Suggestion>>,
+    pub suggestions_tx: watch::Sender<Vec<Suggestion>>,
+    pub selected_suggestion: Option<usize>,
+    /// The pending suggestions request, aborted when newer input replaces it.
+    pub suggestions_request: Option<AbortHandle>,
 }
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -83,7 +92,16 @@ impl SearchState {
         );
 
         let tab_content_area = tab_content_area_split[1];
-        let title = format!("Search: {}", self.sub_tab);
+        let mut title = format!("Search: {}", self.sub_tab);
+        if self.search_filter.hires_only {
+            title.push_str(" [hi-res]");
+        }
+        if self.search_filter.hide_explicit {
+            title.push_str(" [no explicit]");
+        }
+        if self.next_offset.is_some() {
+            title.push_str(" [more]");
+        }
 
         let (table, state) = match self.sub_tab {
             SubTab::Albums => (
@@ -126,6 +144,52 @@ impl SearchState {
         };
 
         frame.render_stateful_widget(table, tab_content_area, state);
+
+        if self.editing {
+            self.render_suggestions(frame, tab_content_area);
+        }
+    }
+
+    fn render_suggestions(&self, frame: &mut Frame, area: Rect) {
+        let suggestions = self.suggestions.borrow();
+        if suggestions.is_empty() {
+            return;
+        }
+
+        let items: Vec<ListItem> = suggestions
+            .iter()
+            .map(|suggestion| {
+                let (kind, detail) = match suggestion {
+                    Suggestion::Artist { .. } => ("Artist", None),
+                    Suggestion::Album { artist, .. } => ("Album", Some(artist.as_str())),
+                    Suggestion::Track { artist, .. } => ("Track", artist.as_deref()),
+                    Suggestion::Playlist { .. } => ("Playlist", None),
+                };
+
+                let mut spans = vec![
+                    Span::from(format!("{kind:<9}")).dim(),
+                    Span::from(suggestion.text().to_string()),
+                ];
+                if let Some(detail) = detail {
+                    spans.push(Span::from(format!(" - {detail}")).dim());
+                }
+
+                ListItem::from(Line::from(spans))
+            })
+            .collect();
+
+        let area = Rect {
+            height: (items.len() as u16 + 2).min(area.height),
+            ..area
+        };
+
+        let list = List::new(items)
+            .block(block("Suggestions", false))
+            .highlight_style(Style::default().bg(Color::Blue));
+        let mut state = ListState::default().with_selected(self.selected_suggestion);
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut state);
     }
 
     pub(crate) async fn handle_events(&mut self, event: Event) -> Output {
@@ -139,13 +203,26 @@ impl SearchState {
                         }
                         KeyCode::Left => {
                             self.cycle_subtab_backwards();
-                            Output::Consumed
+                            self.search_output().await
                         }
                         KeyCode::Right => {
                             self.cycle_subtab();
-                            Output::Consumed
+                            self.search_output().await
+                        }
+                        KeyCode::Char('r') => {
+                            self.search_filter.hires_only = !self.search_filter.hires_only;
+                            self.search_output().await
+                        }
+                        KeyCode::Char('x') => {
+                            self.search_filter.hide_explicit = !self.search_filter.hide_explicit;
+                            self.search_output().await
                         }
                         KeyCode::Down => {
+                            if self.last_selected()
+                                && let Err(err) = self.load_more().await
+                            {
+                                return Output::Error(format!("{err}"));
+                            }
                             self.current_list_state().select_next();
                             Output::Consumed
                         }
@@ -153,6 +230,33 @@ impl SearchState {
                             self.current_list_state().select_previous();
                             Output::Consumed
                         }
+                        KeyCode::Char('i') => match self.sub_tab {
+                            SubTab::Albums => {
+                                let index = self.albums.state.selected();
+                                let Some(selected) = index.map(|index| &self.albums.items[index])
+                                else {
+                                    return Output::Consumed;
+                                };
+
+                                match self.client.album(&selected.id).await {
+                                    Ok(album) => Output::Popup(Popup::Details(
+                                        DetailsPopupState::album(&album),
+                                    )),
+                                    Err(err) => Output::Error(format!("{err}")),
+                                }
+                            }
+                            SubTab::Tracks => {
+                                let index = self.tracks.state.selected();
+
+                                match index.map(|index| &self.tracks.items[index]) {
+                                    Some(track) => Output::Popup(Popup::Details(
+                                        DetailsPopupState::track(track),
+                                    )),
+                                    None => Output::Consumed,
+                                }
+                            }
+                            SubTab::Artists | SubTab::Playlists => Output::Consumed,
+                        },
                         KeyCode::Enter => match self.sub_tab {
                             SubTab::Albums => {
                                 let index = self.albums.state.selected();
@@ -174,15 +278,18 @@ impl SearchState {
                                     return Output::Consumed;
                                 };
 
-                                let artist_albums =
-                                    match self.client.artist_albums(selected.id).await {
-                                        Ok(res) => res,
-                                        Err(err) => return Output::Error(format!("{err}")),
-                                    };
+                                let releases = match self
+                                    .client
+                                    .artist_discography(selected.id, ReleaseSort::ReleaseDate)
+                                    .await
+                                {
+                                    Ok(res) => res,
+                                    Err(err) => return Output::Error(format!("{err}")),
+                                };
 
                                 Output::Popup(Popup::Artist(ArtistPopupState {
                                     artist_name: selected.name.clone(),
-                                    albums: artist_albums,
+                                    releases,
                                     state: Default::default(),
                                 }))
                             }
@@ -217,14 +324,37 @@ impl SearchState {
                     },
                     true => match key_event.code {
                         KeyCode::Esc | KeyCode::Enter => {
+                            let suggestion = self
+                                .selected_suggestion
+                                .and_then(|index| self.suggestions.borrow().get(index).cloned());
+                            if let Some(suggestion) = suggestion {
+                                self.filter = Input::new(suggestion.text().to_string());
+                            }
+
                             self.stop_editing();
                             if let Err(err) = self.update_search().await {
                                 return Output::Error(format!("{err}"));
                             };
                             Output::Consumed
                         }
+                        KeyCode::Down => {
+                            let count = self.suggestions.borrow().len();
+                            self.selected_suggestion = match self.selected_suggestion {
+                                _ if count == 0 => None,
+                                Some(index) => Some((index + 1).min(count - 1)),
+                                None => Some(0),
+                            };
+                            Output::Consumed
+                        }
+                        KeyCode::Up => {
+                            self.selected_suggestion = self
+                                .selected_suggestion
+                                .and_then(|index| index.checked_sub(1));
+                            Output::Consumed
+                        }
                         _ => {
                             self.filter.handle_event(&event);
+                            self.request_suggestions();
                             Output::Consumed
                         }
                     },
@@ -235,24 +365,110 @@ impl SearchState {
     }
 
     async fn update_search(&mut self) -> Result<()> {
-        if !self.filter.value().trim().is_empty() {
-            let search_results = self.client.search(self.filter.value().to_string()).await?;
+        self.next_offset = None;
+        self.albums.items.clear();
+        self.artists.items.clear();
+        self.playlists.items.clear();
+        self.tracks.items.clear();
+        *self.current_list_state() = Default::default();
+
+        self.load_page(0).await
+    }
+
+    async fn search_output(&mut self) -> Output {
+        match self.update_search().await {
+            Ok(()) => Output::Consumed,
+            Err(err) => Output::Error(format!("{err}")),
+        }
+    }
+
+    async fn load_more(&mut self) -> Result<()> {
+        match self.next_offset {
+            Some(offset) => self.load_page(offset).await,
+            None => Ok(()),
+        }
+    }
 
-            self.albums.items = search_results.albums;
-            self.artists.items = search_results.artists;
-            self.playlists.items = search_results.playlists;
-            self.tracks.items = search_results.tracks;
+    /// Fetch a page of the active sub tab only, appending it to what is already loaded.
+    async fn load_page(&mut self, offset: u32) -> Result<()> {
+        let query = self.filter.value().trim().to_string();
+        if query.is_empty() {
+            return Ok(());
         }
 
+        let filter = self.search_filter;
+
+        self.next_offset = match self.sub_tab {
+            SubTab::Albums => {
+                let page = self.client.search_albums(query, filter, offset).await?;
+                self.albums.items.extend(page.items);
+                page.next_offset
+            }
+            SubTab::Artists => {
+                let page = self.client.search_artists(query, offset).await?;
+                self.artists.items.extend(page.items);
+                page.next_offset
+            }
+            SubTab::Playlists => {
+                let page = self.client.search_playlists(query, offset).await?;
+                self.playlists.items.extend(page.items);
+                page.next_offset
+            }
+            SubTab::Tracks => {
+                let page = self.client.search_tracks(query, filter, offset).await?;
+                self.tracks.items.extend(page.items);
+                page.next_offset
+            }
+        };
+
         Ok(())
     }
 
+    fn last_selected(&mut self) -> bool {
+        let len = match self.sub_tab {
+            SubTab::Albums => self.albums.items.len(),
+            SubTab::Artists => self.artists.items.len(),
+            SubTab::Playlists => self.playlists.items.len(),
+            SubTab::Tracks => self.tracks.items.len(),
+        };
+
+        self.current_list_state()
+            .selected()
+            .is_some_and(|index| index + 1 >= len)
+    }
+
+    /// Fetch suggestions in the background, replacing the request for earlier input.
+    fn request_suggestions(&mut self) {
+        let client = self.client.clone();
+        let suggestions_tx = self.suggestions_tx.clone();
+        let query = self.filter.value().to_string();
+        self.selected_suggestion = None;
+        self.cancel_suggestions();
+
+        let request = tokio::spawn(async move {
+            // Suggestions are a convenience, failures are left to the search itself.
+            if let Ok(suggestions) = client.autocomplete(query).await {
+                suggestions_tx.send_replace(suggestions);
+            }
+        });
+        self.suggestions_request = Some(request.abort_handle());
+    }
+
+    fn cancel_suggestions(&mut self) {
+        if let Some(request) = self.suggestions_request.take() {
+            request.abort();
+        }
+    }
+
     fn start_editing(&mut self) {
         self.editing = true;
     }
 
     fn stop_editing(&mut self) {
         self.editing = false;
+        self.selected_suggestion = None;
+        self.cancel_suggestions();
+        self.suggestions_tx.send_replace(vec![]);
     }
 
     fn current_list_state(&mut self) -> &mut TableState {
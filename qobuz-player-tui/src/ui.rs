@@ -78,11 +78,16 @@ fn render_help(frame: &mut Frame) {
         ["Previous song", "p"],
         ["Jump forward", "f"],
         ["Jump backwards", "b"],
+        ["Open Qobuz link", "o"],
         ["e", "Edit filter"],
         ["esc", "Stop edit filter"],
         ["Up/Down", "Select in list"],
         ["Enter", "Select selected item"],
         ["Left/right", "Cycle subgrup"],
+        ["g", "Pick genre in discover"],
+        ["i", "Show details of selected item"],
+        ["r", "Toggle hi-res only in search"],
+        ["x", "Toggle hide explicit in search"],
         ["q", "Exit"],
     ];
 
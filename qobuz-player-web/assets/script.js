@@ -1,7 +1,10 @@
 let evtSource;
 
+// Resolved from this script's own url, so it also works when served below a base path.
+const sseUrl = new URL("../sse", document.currentScript.src);
+
 function initSse() {
-  evtSource = new EventSource("/sse");
+  evtSource = new EventSource(sseUrl);
 
   evtSource.addEventListener("status", (_event) => {
     const elements = document.querySelectorAll("[data-sse=status]");
@@ -19,6 +22,17 @@ function initSse() {
     }
   });
 
+  // The event names the zone that changed, only its card is redrawn.
+  evtSource.addEventListener("zones", (event) => {
+    const elements = document.querySelectorAll(
+      `[data-sse=zones][data-zone="${event.data}"]`,
+    );
+
+    for (const element of elements) {
+      htmx.trigger(element, "zones");
+    }
+  });
+
   evtSource.addEventListener("volume", (event) => {
     const slider = document.getElementById("volume-slider");
     if (slider === null) {
@@ -100,3 +114,67 @@ function loadSearchInput() {
 function setSearchQuery(value) {
   sessionStorage.setItem("search-query", value);
 }
+
+// Reorder the queue by dragging a row's handle. Pointer events also cover touch screens,
+// which lack html drag and drop.
+let queueDrag = null;
+
+function rowIndex(row) {
+  return Array.prototype.indexOf.call(row.parentElement.children, row);
+}
+
+document.addEventListener("pointerdown", (event) => {
+  const handle = event.target.closest("[data-queue-handle]");
+  if (handle === null) {
+    return;
+  }
+
+  event.preventDefault();
+  handle.setPointerCapture(event.pointerId);
+
+  const row = handle.closest("li");
+  row.classList.add("opacity-50");
+  queueDrag = { row, entry: handle.dataset.queueHandle, from: rowIndex(row) };
+});
+
+document.addEventListener("pointermove", (event) => {
+  if (queueDrag === null) {
+    return;
+  }
+
+  const target = document
+    .elementFromPoint(event.clientX, event.clientY)
+    ?.closest("li");
+  const row = queueDrag.row;
+
+  if (!target || target === row || target.parentElement !== row.parentElement) {
+    return;
+  }
+
+  const rect = target.getBoundingClientRect();
+  const after = event.clientY > rect.top + rect.height / 2;
+  row.parentElement.insertBefore(row, after ? target.nextSibling : target);
+});
+
+function endQueueDrag() {
+  if (queueDrag === null) {
+    return;
+  }
+
+  const { row, entry, from } = queueDrag;
+  queueDrag = null;
+  row.classList.remove("opacity-50");
+
+  const to = rowIndex(row);
+  if (from === to) {
+    return;
+  }
+
+  const form = document.getElementById("queue-move");
+  form.elements.entry.value = entry;
+  form.elements.to.value = to;
+  htmx.trigger(form, "queue-move");
+}
+
+document.addEventListener("pointerup", endQueueDrag);
+document.addEventListener("pointercancel", endQueueDrag);
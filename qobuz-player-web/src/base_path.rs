@@ -0,0 +1,72 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, Response, StatusCode, Uri, header},
+    response::IntoResponse,
+};
+
+tokio::task_local! {
+    /// Base path of the request being handled, set by [`strip_prefix`].
+    static BASE_PATH: String;
+}
+
+/// Normalize a configured base path to `/prefix`, or `None` when serving from the root.
+pub(crate) fn normalize(base_path: &str) -> Option<String> {
+    let trimmed = base_path.trim_matches('/');
+    (!trimmed.is_empty()).then(|| format!("/{trimmed}"))
+}
+
+/// Link to one of the server's own routes, below the base path when one is configured.
+pub(crate) fn url(path: &str) -> String {
+    BASE_PATH
+        .try_with(|base_path| format!("{base_path}{path}"))
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Serve the app below `base_path`: strip the prefix before routing, and prefix redirects in
+/// responses so they stay behind the reverse proxy. Pages prefix their links with [`url`].
+pub(crate) async fn strip_prefix(
+    State(base_path): State<String>,
+    mut request: Request,
+    next: axum::middleware::Next,
+) -> Response<Body> {
+    let Some(path) = request
+        .uri()
+        .path()
+        .strip_prefix(base_path.as_str())
+        .filter(|path| path.is_empty() || path.starts_with('/'))
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let path = match path.is_empty() {
+        true => "/",
+        false => path,
+    };
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+
+    let Ok(uri) = Uri::builder().path_and_query(path_and_query).build() else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    *request.uri_mut() = uri;
+
+    let mut response = BASE_PATH.scope(base_path.clone(), next.run(request)).await;
+
+    for name in [header::LOCATION.as_str(), "HX-Redirect"] {
+        let prefixed = response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| value.starts_with('/'))
+            .and_then(|value| HeaderValue::from_str(&format!("{base_path}{value}")).ok());
+
+        if let Some(prefixed) = prefixed {
+            response.headers_mut().insert(name, prefixed);
+        }
+    }
+
+    response
+}
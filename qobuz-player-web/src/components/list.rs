@@ -2,9 +2,10 @@ use leptos::{IntoView, component, prelude::*};
 use qobuz_player_models::{Album, AlbumSimple, Artist, Playlist, Track};
 
 use crate::{
+    base_path::url,
     components::Info,
     html,
-    icons::{Play, User},
+    icons::{BarsTwo, ChevronDoubleUp, Play, Plus, User, XMark},
 };
 
 #[component]
@@ -23,22 +24,25 @@ pub(crate) fn list_albums_vertical(albums: Vec<AlbumSimple>) -> impl IntoView {
         <div class="flex overflow-scroll gap-4 p-2 w-full">
             {albums
                 .into_iter()
-                .map(|album| {
-                    html! {
-                        <a href=format!("/album/{}", album.id) class="h-full shrink-0 size-32">
-                            <img class="rounded-lg" alt=album.title.clone() src=album.image />
-                            <p class="text-sm truncate">{album.title}</p>
-                            <p class="text-sm text-gray-500 truncate">{album.artist.name}</p>
-                        </a>
-                    }
-                        .attr("preload", "mousedown")
-                        .attr("preload-images", "true")
-                })
+                .map(|album| html! { <AlbumTile album=album /> })
                 .collect::<Vec<_>>()}
         </div>
     }
 }
 
+#[component]
+pub(crate) fn album_tile(album: AlbumSimple) -> impl IntoView {
+    html! {
+        <a href=url(&format!("/album/{}", album.id)) class="h-full shrink-0 size-32">
+            <img class="rounded-lg" alt=album.title.clone() src=album.image />
+            <p class="text-sm truncate">{album.title}</p>
+            <p class="text-sm text-gray-500 truncate">{album.artist.name}</p>
+        </a>
+    }
+    .attr("preload", "mousedown")
+    .attr("preload-images", "true")
+}
+
 #[component]
 pub(crate) fn list_playlists_vertical(playlists: Vec<Playlist>) -> impl IntoView {
     html! {
@@ -51,7 +55,7 @@ pub(crate) fn list_playlists_vertical(playlists: Vec<Playlist>) -> impl IntoView
                         .map(|image| format!("background-image: url({image});"));
                     html! {
                         <a
-                            href=format!("/playlist/{}", playlist.id)
+                            href=url(&format!("/playlist/{}", playlist.id))
                             class="h-full shrink-0 size-32"
                         >
                             <div
@@ -79,7 +83,7 @@ pub(crate) fn list_artists_vertical(artists: Vec<Artist>) -> impl IntoView {
                         .image
                         .map(|image| format!("background-image: url({image});"));
                     html! {
-                        <a href=format!("/artist/{}", artist.id) class="w-32 h-full text-center">
+                        <a href=url(&format!("/artist/{}", artist.id)) class="w-32 h-full text-center">
                             {match artist_image_style {
                                 Some(img_src) => {
                                     html! {
@@ -152,7 +156,7 @@ fn album(album: Album) -> impl IntoView {
         <a
             class="flex gap-4 items-center w-full"
             hx-push-url="true"
-            href=format!("/album/{}", album.id)
+            href=url(&format!("/album/{}", album.id))
         >
             <img
                 class="inline text-sm text-gray-500 bg-gray-800 rounded-md aspect-square size-12"
@@ -204,7 +208,7 @@ pub(crate) fn list_artists(mut artists: Vec<Artist>, sort: ArtistSort) -> impl I
                                 <a
                                     class="flex gap-4 items-center"
                                     hx-push-url="true"
-                                    href=format!("/artist/{}", artist.id)
+                                    href=url(&format!("/artist/{}", artist.id))
                                 >
                                     {match artist_image_style {
                                         Some(img_src) => {
@@ -246,6 +250,15 @@ pub(crate) enum TrackNumberDisplay {
     Cover,
 }
 
+/// Buttons shown next to each track row.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub(crate) enum TrackActions {
+    None,
+    AddToQueue,
+    /// Play next, remove and a handle to drag the track to another position in the queue.
+    Queue,
+}
+
 #[component]
 pub(crate) fn list_tracks(
     tracks: Vec<Track>,
@@ -253,6 +266,10 @@ pub(crate) fn list_tracks(
     show_artist: bool,
     dim_played: bool,
     now_playing_id: Option<u32>,
+    actions: TrackActions,
+    /// Queue entry id of each track, for [`TrackActions::Queue`].
+    #[prop(optional)]
+    queue_entries: Vec<u64>,
     #[prop(into)] api_call: Callback<(usize,), String>,
 ) -> impl IntoView {
     html! {
@@ -262,86 +279,102 @@ pub(crate) fn list_tracks(
                 .enumerate()
                 .map(|(index, track)| {
                     let is_playing = now_playing_id.is_some_and(|id| id == track.id);
+                    let track_id = track.id;
+                    let entry = queue_entries.get(index).copied();
+                    // Unavailable tracks can't be queued, but can still be moved or removed.
+                    let show_actions = track.available || actions == TrackActions::Queue;
                     html! {
                         <ListItem>
-                            <button
-                                hx-swap="none"
-                                hx-put=api_call.run((index,))
-                                class=format!(
-                                    "flex justify-between items-center w-full text-left cursor-pointer disabled:text-gray-500 disabled:cursor-default {}",
-                                    if dim_played { "disabled:text-gray-500" } else { "" },
-                                )
-                                disabled=!track.available
-                            >
-                                <div class="flex overflow-hidden gap-4 items-center w-full">
-                                    <div class=format!(
-                                        "flex justify-center items-center {}",
-                                        if track_number_display == TrackNumberDisplay::Cover {
-                                            "size-12 aspect-square"
-                                        } else {
-                                            ""
-                                        },
-                                    )>
-                                        {is_playing
-                                            .then_some({
-                                                html! {
-                                                    <div class="text-blue-500 size-5">
-                                                        <Play />
-                                                    </div>
-                                                }
-                                            })}
-                                        {(!is_playing)
-                                            .then_some({
-                                                html! {
-                                                    {match track_number_display {
-                                                        TrackNumberDisplay::Number => {
-                                                            html! {
-                                                                <span class="w-5 text-center text-gray-400">
-                                                                    {track.number}
-                                                                </span>
+                            <div class="flex gap-2 items-center">
+                                <button
+                                    hx-swap="none"
+                                    hx-put=api_call.run((index,))
+                                    class=format!(
+                                        "flex justify-between items-center w-full text-left cursor-pointer disabled:text-gray-500 disabled:cursor-default {}",
+                                        if dim_played { "disabled:text-gray-500" } else { "" },
+                                    )
+                                    disabled=!track.available
+                                >
+                                    <div class="flex overflow-hidden gap-4 items-center w-full">
+                                        <div class=format!(
+                                            "flex justify-center items-center {}",
+                                            if track_number_display == TrackNumberDisplay::Cover {
+                                                "size-12 aspect-square"
+                                            } else {
+                                                ""
+                                            },
+                                        )>
+                                            {is_playing
+                                                .then_some({
+                                                    html! {
+                                                        <div class="text-blue-500 size-5">
+                                                            <Play />
+                                                        </div>
+                                                    }
+                                                })}
+                                            {(!is_playing)
+                                                .then_some({
+                                                    html! {
+                                                        {match track_number_display {
+                                                            TrackNumberDisplay::Number => {
+                                                                html! {
+                                                                    <span class="w-5 text-center text-gray-400">
+                                                                        {track.number}
+                                                                    </span>
+                                                                }
+                                                                    .into_any()
                                                             }
-                                                                .into_any()
-                                                        }
-                                                        TrackNumberDisplay::Cover => {
-                                                            html! {
-                                                                <div
-                                                                    class="bg-gray-800 bg-center bg-no-repeat bg-cover rounded-md aspect-square size-12"
-                                                                    style=track
-                                                                        .image
-                                                                        .map(|image| { format!("background-image: url({image});") })
-                                                                ></div>
+                                                            TrackNumberDisplay::Cover => {
+                                                                html! {
+                                                                    <div
+                                                                        class="bg-gray-800 bg-center bg-no-repeat bg-cover rounded-md aspect-square size-12"
+                                                                        style=track
+                                                                            .image
+                                                                            .map(|image| { format!("background-image: url({image});") })
+                                                                    ></div>
+                                                                }
+                                                                    .into_any()
                                                             }
-                                                                .into_any()
-                                                        }
-                                                    }}
-                                                }
-                                            })}
-                                    </div>
+                                                        }}
+                                                    }
+                                                })}
+                                        </div>
 
-                                    {match show_artist && track.artist_name.is_some() {
-                                        true => {
-                                            html! {
-                                                <div class="flex overflow-hidden flex-col">
-                                                    <h2 class="truncate">{track.title}</h2>
-                                                    <h3 class="text-sm text-gray-400 truncate">
-                                                        {track.artist_name.unwrap_or_default()}
-                                                    </h3>
-                                                </div>
+                                        {match show_artist && track.artist_name.is_some() {
+                                            true => {
+                                                html! {
+                                                    <div class="flex overflow-hidden flex-col">
+                                                        <h2 class="truncate">{track.title}</h2>
+                                                        <h3 class="text-sm text-gray-400 truncate">
+                                                            {track.artist_name.unwrap_or_default()}
+                                                        </h3>
+                                                    </div>
+                                                }
+                                                    .into_any()
                                             }
-                                                .into_any()
-                                        }
-                                        false => {
-                                            html! { <h2 class="w-full truncate">{track.title}</h2> }
-                                                .into_any()
-                                        }
-                                    }}
+                                            false => {
+                                                html! { <h2 class="w-full truncate">{track.title}</h2> }
+                                                    .into_any()
+                                            }
+                                        }}
 
-                                </div>
-                                <Info
-                                    explicit=track.explicit
-                                    hires_available=track.hires_available
-                                />
-                            </button>
+                                    </div>
+                                    <Info
+                                        explicit=track.explicit
+                                        hires_available=track.hires_available
+                                    />
+                                </button>
+                                {show_actions
+                                    .then(|| {
+                                        html! {
+                                            <TrackActionButtons
+                                                actions=actions
+                                                track_id=track_id
+                                                entry=entry
+                                            />
+                                        }
+                                    })}
+                            </div>
                         </ListItem>
                     }
                 })
@@ -350,6 +383,60 @@ pub(crate) fn list_tracks(
     }
 }
 
+fn action_button_class() -> &'static str {
+    "p-2 text-gray-400 rounded cursor-pointer shrink-0 size-10 hover:text-white"
+}
+
+#[component]
+fn track_action_buttons(actions: TrackActions, track_id: u32, entry: Option<u64>) -> impl IntoView {
+    match (actions, entry) {
+        (TrackActions::AddToQueue, _) => {
+            html! { <AddToQueueButton track_id=track_id /> }.into_any()
+        }
+        (TrackActions::Queue, Some(entry)) => html! {
+            <button
+                class=action_button_class()
+                hx-put=url(&format!("/queue/{entry}/play-next"))
+                hx-swap="none"
+                title="Play next"
+            >
+                <ChevronDoubleUp />
+            </button>
+            <button
+                class=action_button_class()
+                hx-delete=url(&format!("/queue/{entry}"))
+                hx-swap="none"
+                title="Remove from queue"
+            >
+                <XMark />
+            </button>
+            <div
+                class="p-2 text-gray-400 cursor-grab touch-none shrink-0 size-10"
+                data-queue-handle=entry.to_string()
+                title="Drag to reorder"
+            >
+                <BarsTwo />
+            </div>
+        }
+        .into_any(),
+        (TrackActions::None, _) | (TrackActions::Queue, None) => ().into_any(),
+    }
+}
+
+#[component]
+pub(crate) fn add_to_queue_button(track_id: u32) -> impl IntoView {
+    html! {
+        <button
+            class=action_button_class()
+            hx-put=url(&format!("/queue/add/{track_id}"))
+            hx-swap="none"
+            title="Add to queue"
+        >
+            <Plus />
+        </button>
+    }
+}
+
 pub(crate) enum PlaylistSort {
     Default,
     Title,
@@ -375,7 +462,7 @@ pub(crate) fn list_playlists(mut playlists: Vec<Playlist>, sort: PlaylistSort) -
                             {html! {
                                 <a
                                     class="flex gap-4 items-center w-full text-lg text-left"
-                                    href=format!("/playlist/{}", playlist.id)
+                                    href=url(&format!("/playlist/{}", playlist.id))
                                 >
                                     <div
                                         class="bg-gray-800 bg-center bg-no-repeat bg-cover rounded-md aspect-square size-12"
@@ -40,6 +40,29 @@ pub(crate) fn description(description: Option<String>, entity_title: String) ->
     })
 }
 
+/// Labelled metadata rows, rendered only when there is something to show.
+#[component]
+pub(crate) fn details(title: String, rows: Vec<(String, String)>) -> impl IntoView {
+    (!rows.is_empty()).then(|| {
+        let rows = rows
+            .into_iter()
+            .map(|(label, value)| {
+                html! {
+                    <dt class="text-gray-400">{label}</dt>
+                    <dd>{value}</dd>
+                }
+            })
+            .collect::<Vec<_>>();
+
+        html! {
+            <div class="flex flex-col gap-4 p-4 bg-gray-800 inset-shadow-lg">
+                <h3 class="text-lg">{title}</h3>
+                <dl class="grid grid-cols-[auto_1fr] gap-x-4 gap-y-1">{rows}</dl>
+            </div>
+        }
+    })
+}
+
 #[component]
 pub(crate) fn button_group(children: ChildrenFragment) -> impl IntoView {
     let nodes = children()
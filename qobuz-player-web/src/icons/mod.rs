@@ -217,6 +217,95 @@ pub(crate) fn queue_list() -> impl IntoView {
     }
 }
 
+#[component]
+pub(crate) fn plus() -> impl IntoView {
+    html! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke-width="1.5"
+            stroke="currentColor"
+            width="100%"
+            height="100%"
+        >
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                d="M12 4.5v15m7.5-7.5h-15"
+            />
+        </svg>
+    }
+}
+
+#[component]
+pub(crate) fn x_mark() -> impl IntoView {
+    html! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke-width="1.5"
+            stroke="currentColor"
+            width="100%"
+            height="100%"
+        >
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                d="M6 18 18 6M6 6l12 12"
+            />
+        </svg>
+    }
+}
+
+#[component]
+pub(crate) fn bars_two() -> impl IntoView {
+    html! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke-width="1.5"
+            stroke="currentColor"
+            width="100%"
+            height="100%"
+        >
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                d="M3.75 9h16.5m-16.5 6.75h16.5"
+            />
+        </svg>
+    }
+}
+
+#[component]
+pub(crate) fn chevron_double_up() -> impl IntoView {
+    html! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke-width="1.5"
+            stroke="currentColor"
+            width="100%"
+            height="100%"
+        >
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                d="m4.5 18.75 7.5-7.5 7.5 7.5"
+            />
+            <path
+                stroke-linecap="round"
+                stroke-linejoin="round"
+                d="m4.5 12.75 7.5-7.5 7.5 7.5"
+            />
+        </svg>
+    }
+}
+
 #[component]
 pub(crate) fn star(solid: bool) -> impl IntoView {
     match solid {
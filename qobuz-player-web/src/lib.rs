@@ -8,19 +8,27 @@ use axum::{
 use futures::stream::Stream;
 use leptos::*;
 use leptos::{html::*, prelude::RenderHtml};
+pub use listener::{BindAddress, TlsConfig};
+use listener::{ClientIp, TlsListener};
 use qobuz_player_controls::{
     PositionReceiver, Result, Status, StatusReceiver, TracklistReceiver, VolumeReceiver,
     client::Client,
     controls::Controls,
+    database::Database,
     error::Error,
     notification::{Notification, NotificationBroadcast},
 };
 use qobuz_player_models::{Album, AlbumSimple, Favorites, Playlist};
 use qobuz_player_rfid::RfidState;
 use routes::{
-    album, artist, auth, controls, discover, favorites, now_playing, playlist, queue, search,
+    album, api, artist, auth, controls, discover, favorites, now_playing, playlist, queue, search,
+    settings, track, ws, zones,
+};
+use std::{
+    convert::Infallible,
+    net::IpAddr,
+    sync::{Arc, Mutex},
 };
-use std::{convert::Infallible, sync::Arc};
 use tokio::{
     sync::broadcast::{self, Receiver, Sender},
     try_join,
@@ -28,14 +36,23 @@ use tokio::{
 use tokio_stream::StreamExt as _;
 use tokio_stream::wrappers::BroadcastStream;
 
-use crate::view::render;
+use crate::{
+    view::render,
+    zone::{LocalZone, RemoteZone, Zone},
+};
+pub use zone::{Peer, ZonesConfig};
 
 mod assets;
+mod base_path;
 mod components;
 mod icons;
+mod listener;
 mod page;
 mod routes;
+#[cfg(test)]
+mod testing;
 mod view;
+mod zone;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn init(
@@ -44,16 +61,37 @@ pub async fn init(
     tracklist_receiver: TracklistReceiver,
     volume_receiver: VolumeReceiver,
     status_receiver: StatusReceiver,
-    port: u16,
+    server: ServerConfig,
+    zones: ZonesConfig,
     web_secret: Option<String>,
     rfid_state: Option<RfidState>,
     broadcast: Arc<NotificationBroadcast>,
     client: Arc<Client>,
+    database: Arc<Database>,
 ) -> Result<()> {
-    let interface = format!("0.0.0.0:{port}");
-    let listener = tokio::net::TcpListener::bind(&interface)
-        .await
-        .or(Err(Error::PortInUse { port }))?;
+    // The secret is read once, changing the stored one takes a restart. A secret given on the
+    // command line overrides the stored one. Sessions are signed for the secret in use, so
+    // they end when it changes.
+    let web_auth = database.get_web_auth().await?;
+    let (secret_hash, session_key) = match web_secret {
+        Some(secret) => (
+            Some(qobuz_player_controls::auth::hash_secret(&secret)),
+            qobuz_player_controls::auth::session_key(&web_auth.session_key, &secret),
+        ),
+        None => {
+            let session_key = qobuz_player_controls::auth::session_key(
+                &web_auth.session_key,
+                web_auth.secret_hash.as_deref().unwrap_or_default(),
+            );
+            (web_auth.secret_hash, session_key)
+        }
+    };
+
+    let tls_acceptor = server
+        .tls
+        .as_ref()
+        .map(listener::tls_acceptor)
+        .transpose()?;
 
     let router = create_router(
         controls,
@@ -61,17 +99,73 @@ pub async fn init(
         tracklist_receiver,
         volume_receiver,
         status_receiver,
-        web_secret,
+        zones,
+        secret_hash,
+        session_key,
+        server.trusted_proxies.clone(),
         rfid_state,
         broadcast,
         client,
+        database,
     )
     .await;
 
-    axum::serve(listener, router).await.expect("infailable");
+    let router = match server.base_path.as_deref().and_then(base_path::normalize) {
+        Some(prefix) => {
+            Router::new()
+                .fallback_service(router)
+                .layer(axum::middleware::from_fn_with_state(
+                    prefix,
+                    base_path::strip_prefix,
+                ))
+        }
+        None => router,
+    };
+
+    let bind_error = |err: std::io::Error| Error::Bind {
+        address: server.address.to_string(),
+        message: err.to_string(),
+    };
+
+    let router = router.into_make_service_with_connect_info::<ClientIp>();
+
+    match &server.address {
+        BindAddress::Tcp(address) => {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .map_err(bind_error)?;
+
+            match tls_acceptor {
+                Some(acceptor) => axum::serve(TlsListener::new(listener, acceptor), router).await,
+                None => axum::serve(listener, router).await,
+            }
+        }
+        #[cfg(unix)]
+        BindAddress::Unix(path) => {
+            let listener = listener::bind_unix(path).await?;
+
+            match tls_acceptor {
+                Some(acceptor) => axum::serve(TlsListener::new(listener, acceptor), router).await,
+                None => axum::serve(listener, router).await,
+            }
+        }
+    }
+    .expect("infailable");
+
     Ok(())
 }
 
+/// How the web server is exposed.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub address: BindAddress,
+    pub tls: Option<TlsConfig>,
+    /// Path prefix when served behind a reverse proxy, e.g. `/music`.
+    pub base_path: Option<String>,
+    /// Reverse proxies trusted to name the client in `X-Forwarded-For`.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn create_router(
     controls: Controls,
@@ -79,19 +173,53 @@ async fn create_router(
     tracklist_receiver: TracklistReceiver,
     volume_receiver: VolumeReceiver,
     status_receiver: StatusReceiver,
-    web_secret: Option<String>,
+    zones: ZonesConfig,
+    secret_hash: Option<String>,
+    session_key: String,
+    trusted_proxies: Vec<IpAddr>,
     rfid_state: Option<RfidState>,
     broadcast: Arc<NotificationBroadcast>,
     client: Arc<Client>,
+    database: Arc<Database>,
 ) -> Router {
     let (tx, _rx) = broadcast::channel::<ServerSentEvent>(100);
     let broadcast_subscribe = broadcast.subscribe();
+
+    let local_zone = Zone::Local(LocalZone {
+        name: zones.name,
+        controls: controls.clone(),
+        position_receiver: position_receiver.clone(),
+        tracklist_receiver: tracklist_receiver.clone(),
+        volume_receiver: volume_receiver.clone(),
+        status_receiver: status_receiver.clone(),
+    });
+    let zones: Vec<Arc<Zone>> = std::iter::once(local_zone)
+        .chain(
+            zones
+                .peers
+                .into_iter()
+                .map(|peer| Zone::Remote(RemoteZone::new(peer))),
+        )
+        .map(Arc::new)
+        .collect();
+
+    for (id, zone) in zones.iter().enumerate() {
+        let zone = zone.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move { zone.watch(id, tx).await });
+    }
+
     let shared_state = Arc::new(AppState {
         controls,
-        web_secret,
+        zones,
+        secret_hash,
+        session_key,
+        trusted_proxies,
+        login_throttle: Mutex::new(auth::LoginThrottle::default()),
         rfid_state,
         broadcast,
         client,
+        database,
         tx: tx.clone(),
         position_receiver: position_receiver.clone(),
         tracklist_receiver: tracklist_receiver.clone(),
@@ -109,20 +237,28 @@ async fn create_router(
 
     axum::Router::new()
         .route("/sse", get(sse_handler))
+        .route("/api/v1/events", get(sse_handler))
         .merge(now_playing::routes())
         .merge(search::routes())
         .merge(album::routes())
         .merge(artist::routes())
         .merge(playlist::routes())
+        .merge(track::routes())
         .merge(favorites::routes())
         .merge(queue::routes())
         .merge(discover::routes())
         .merge(controls::routes())
+        .merge(settings::routes())
+        .merge(api::routes())
+        .merge(ws::routes())
+        .merge(zones::routes())
+        .merge(auth::session_routes())
         .layer(axum::middleware::from_fn_with_state(
             shared_state.clone(),
             auth::auth_middleware,
         ))
         .route("/assets/{*file}", get(static_handler))
+        .merge(api::openapi_routes())
         .merge(auth::routes())
         .with_state(shared_state.clone())
 }
@@ -232,11 +368,18 @@ async fn sse_handler(
 
 pub(crate) struct AppState {
     tx: Sender<ServerSentEvent>,
-    pub(crate) web_secret: Option<String>,
+    pub(crate) secret_hash: Option<String>,
+    pub(crate) session_key: String,
+    /// Reverse proxies whose `X-Forwarded-For` header names the client.
+    pub(crate) trusted_proxies: Vec<IpAddr>,
+    pub(crate) login_throttle: Mutex<auth::LoginThrottle>,
     pub(crate) rfid_state: Option<RfidState>,
     pub(crate) broadcast: Arc<NotificationBroadcast>,
     pub(crate) client: Arc<Client>,
+    pub(crate) database: Arc<Database>,
     pub(crate) controls: Controls,
+    /// This player first, then its peers.
+    pub(crate) zones: Vec<Arc<Zone>>,
     pub(crate) position_receiver: PositionReceiver,
     pub(crate) tracklist_receiver: TracklistReceiver,
     pub(crate) status_receiver: StatusReceiver,
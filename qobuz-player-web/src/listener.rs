@@ -0,0 +1,201 @@
+use std::{
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::connect_info::Connected,
+    serve::{IncomingStream, Listener},
+};
+use qobuz_player_controls::{Result, error::Error};
+use tokio::sync::mpsc;
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig,
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+    server::TlsStream,
+};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Finished handshakes waiting for axum to pick them up.
+const HANDSHAKE_BACKLOG: usize = 16;
+
+/// Where the web server listens.
+#[derive(Debug, Clone)]
+pub enum BindAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Display for BindAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindAddress::Tcp(address) => write!(f, "{address}"),
+            #[cfg(unix)]
+            BindAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Ip address of the client, `None` on a unix socket. Behind a reverse proxy this is the
+/// address of the proxy.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClientIp(pub(crate) Option<IpAddr>);
+
+/// Listener addresses a client ip can be read from.
+pub(crate) trait PeerIp {
+    fn peer_ip(&self) -> Option<IpAddr>;
+}
+
+impl PeerIp for SocketAddr {
+    fn peer_ip(&self) -> Option<IpAddr> {
+        Some(self.ip())
+    }
+}
+
+#[cfg(unix)]
+impl PeerIp for tokio::net::unix::SocketAddr {
+    fn peer_ip(&self) -> Option<IpAddr> {
+        None
+    }
+}
+
+impl Connected<IncomingStream<'_, tokio::net::TcpListener>> for ClientIp {
+    fn connect_info(stream: IncomingStream<'_, tokio::net::TcpListener>) -> Self {
+        Self(stream.remote_addr().peer_ip())
+    }
+}
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for ClientIp {
+    fn connect_info(stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self(stream.remote_addr().peer_ip())
+    }
+}
+
+impl<L> Connected<IncomingStream<'_, TlsListener<L>>> for ClientIp
+where
+    L: Listener,
+    L::Addr: PeerIp + 'static,
+{
+    fn connect_info(stream: IncomingStream<'_, TlsListener<L>>) -> Self {
+        Self(stream.remote_addr().peer_ip())
+    }
+}
+
+/// Pem encoded certificate chain and private key used to serve https.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+pub(crate) fn tls_acceptor(tls: &TlsConfig) -> Result<TlsAcceptor> {
+    let tls_error = |message: String| Error::Tls { message };
+
+    let certs = CertificateDer::pem_file_iter(&tls.cert)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|err| tls_error(format!("{}: {err}", tls.cert.display())))?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key)
+        .map_err(|err| tls_error(format!("{}: {err}", tls.key.display())))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|err| tls_error(err.to_string()))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| tls_error(err.to_string()))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Wraps a listener and completes the tls handshake before handing connections to axum.
+/// Handshakes run in their own tasks, so a slow client does not hold up the others.
+pub(crate) struct TlsListener<L: Listener> {
+    inner: L,
+    acceptor: TlsAcceptor,
+    handshaken_tx: mpsc::Sender<(TlsStream<L::Io>, L::Addr)>,
+    handshaken_rx: mpsc::Receiver<(TlsStream<L::Io>, L::Addr)>,
+}
+
+impl<L: Listener> TlsListener<L> {
+    pub(crate) fn new(inner: L, acceptor: TlsAcceptor) -> Self {
+        let (handshaken_tx, handshaken_rx) = mpsc::channel(HANDSHAKE_BACKLOG);
+
+        Self {
+            inner,
+            acceptor,
+            handshaken_tx,
+            handshaken_rx,
+        }
+    }
+}
+
+impl<L> Listener for TlsListener<L>
+where
+    L: Listener,
+    L::Addr: 'static,
+{
+    type Io = TlsStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                Some(connection) = self.handshaken_rx.recv() => return connection,
+                (stream, address) = self.inner.accept() => {
+                    let acceptor = self.acceptor.clone();
+                    let handshaken_tx = self.handshaken_tx.clone();
+
+                    tokio::spawn(async move {
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                            Ok(Ok(stream)) => _ = handshaken_tx.send((stream, address)).await,
+                            Ok(Err(err)) => tracing::debug!("tls handshake failed: {err}"),
+                            Err(_) => tracing::debug!("tls handshake timed out"),
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+#[cfg(unix)]
+pub(crate) async fn bind_unix(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bind_error = |message: String| Error::Bind {
+        address: format!("unix:{}", path.display()),
+        message,
+    };
+
+    // Replace a socket left behind by a server that did not shut down cleanly.
+    if path.exists() {
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return Err(bind_error("already in use".to_string()));
+        }
+
+        std::fs::remove_file(path).map_err(|err| bind_error(err.to_string()))?;
+    }
+
+    let listener =
+        tokio::net::UnixListener::bind(path).map_err(|err| bind_error(err.to_string()))?;
+
+    // Only the user running the player may connect, the same as the control socket.
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|err| bind_error(err.to_string()))?;
+
+    Ok(listener)
+}
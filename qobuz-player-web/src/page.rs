@@ -2,6 +2,7 @@ use leptos::{IntoView, component, prelude::*};
 use qobuz_player_controls::{Status, tracklist::Tracklist};
 
 use crate::{
+    base_path::url,
     html,
     icons::{self, MagnifyingGlass, PlayCircle, QueueList, Star},
     routes::controls::Controls,
@@ -73,13 +74,13 @@ pub(crate) fn unauthorized_page(children: Children) -> impl IntoView {
 
 #[component]
 fn head(load_htmx: bool) -> impl IntoView {
-    let style_url = "/assets/styles.css?version=16";
+    let style_url = url("/assets/styles.css?version=16");
     html! {
         <head>
             <title>Qobuz Player</title>
-            <link rel="shortcut icon" href="/assets/favicon.svg" type="image/svg" />
-            <link rel="manifest" href="/assets/manifest.json" />
-            <link rel="apple-touch-icon" href="/assets/apple-touch-icon.png" />
+            <link rel="shortcut icon" href=url("/assets/favicon.svg") type="image/svg" />
+            <link rel="manifest" href=url("/assets/manifest.json") />
+            <link rel="apple-touch-icon" href=url("/assets/apple-touch-icon.png") />
             <meta
                 name="viewport"
                 content="width=device-width, initial-scale=1, maximum-scale=5 viewport-fit=cover"
@@ -94,7 +95,7 @@ fn head(load_htmx: bool) -> impl IntoView {
                         <script src="https://unpkg.com/htmx-ext-preload@2.1.0/preload.js"></script>
                         <script src="https://unpkg.com/htmx-ext-remove-me@2.0.0/remove-me.js"></script>
                         <script src="https://unpkg.com/idiomorph@0.7.3"></script>
-                        <script src="/assets/script.js?version=1"></script>
+                        <script src=url("/assets/script.js?version=6")></script>
                     }
                 })}
         </head>
@@ -110,7 +111,7 @@ fn navigation(active_page: Page) -> impl IntoView {
         <nav class="flex fixed bottom-0 justify-evenly w-full pb-safe px-safe backdrop-blur bg-black/80 *:flex *:h-[3.25rem] *:w-20 *:flex-col *:items-center *:overflow-visible *:text-nowrap *:px-4 *:py-1 *:text-[10px] *:font-medium *:transition-colors">
             {html! {
                 <a
-                    href="/"
+                    href=url("/")
                     class=if active_page == Page::NowPlaying {
                         "text-blue-500"
                     } else {
@@ -124,7 +125,7 @@ fn navigation(active_page: Page) -> impl IntoView {
                 .attr("preload", "mouseover")
                 .attr("preload-images", "true")}
             <a
-                href="/queue"
+                href=url("/queue")
                 class=if active_page == Page::Queue { "text-blue-500" } else { "text-gray-500" }
             >
                 <QueueList />
@@ -132,7 +133,7 @@ fn navigation(active_page: Page) -> impl IntoView {
             </a>
             {html! {
                 <a
-                    href="/discover"
+                    href=url("/discover")
                     class=if active_page == Page::Discover {
                         "text-blue-500"
                     } else {
@@ -147,7 +148,7 @@ fn navigation(active_page: Page) -> impl IntoView {
                 .attr("preload-images", "true")}
             {html! {
                 <a
-                    href="/favorites/albums"
+                    href=url("/favorites/albums")
                     class=if active_page == Page::Favorites {
                         "text-blue-500"
                     } else {
@@ -170,7 +171,7 @@ fn navigation(active_page: Page) -> impl IntoView {
                     .into_any()
             } else {
                 html! {
-                    <a href="/search/albums" class="text-gray-500">
+                    <a href=url("/search/albums") class="text-gray-500">
                         <MagnifyingGlass />
                         Search
                     </a>
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use axum::{
     Router,
     extract::{Path, State},
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, put},
 };
@@ -11,15 +12,17 @@ use qobuz_player_models::{Album, AlbumSimple, Track};
 
 use crate::{
     AppState, ResponseResult,
+    base_path::url,
     components::{
-        ButtonGroup, Description, ToggleFavorite, button_class,
-        list::{ListAlbumsVertical, ListTracks, TrackNumberDisplay},
+        ButtonGroup, Description, Details, ToggleFavorite, button_class,
+        list::{ListAlbumsVertical, ListTracks, TrackActions, TrackNumberDisplay},
         parse_duration,
     },
     html,
     icons::{Link, Play},
     ok_or_broadcast, ok_or_error_component,
     page::Page,
+    routes::zones::SelectedZone,
     view::{LazyLoadComponent, render},
 };
 
@@ -32,14 +35,34 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
         .route("/album/{id}/unset-favorite", put(unset_favorite))
         .route("/album/{id}/play", put(play))
         .route("/album/{id}/play/{track_position}", put(play_track))
+        .route("/album/{id}/play-work/{track_position}", put(play_work))
         .route("/album/{id}/link", put(link))
 }
 
 async fn play_track(
     State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
     Path((id, track_position)): Path<(String, u32)>,
-) -> impl IntoResponse {
-    state.controls.play_album(&id, track_position);
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_album(&id, track_position).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn play_work(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Path((id, track_position)): Path<(String, u32)>,
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_work(&id, track_position).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn set_favorite(
@@ -63,8 +86,14 @@ async fn unset_favorite(
     Ok(render(html! { <ToggleFavorite id=id is_favorite=false /> }))
 }
 
-async fn play(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> impl IntoResponse {
-    state.controls.play_album(&id, 0);
+async fn play(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Path(id): Path<String>,
+) -> ResponseResult {
+    ok_or_broadcast(&state.broadcast, selected.zone.play_album(&id, 0).await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn link(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> impl IntoResponse {
@@ -81,7 +110,7 @@ async fn link(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> imp
 }
 
 async fn index(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> impl IntoResponse {
-    let url = format!("/album/{id}/content");
+    let url = url(&format!("/album/{id}/content"));
     let current_status = state.status_receiver.borrow();
     let tracklist = state.tracklist_receiver.borrow();
 
@@ -131,24 +160,90 @@ fn album_tracks(
     album_id: String,
 ) -> impl IntoView {
     let album_id_clone = album_id.clone();
+    let multiple_discs = tracks
+        .iter()
+        .any(|track| track.disc_number != tracks[0].disc_number);
+
+    let works = qobuz_player_models::works(&tracks)
+        .into_iter()
+        .enumerate()
+        .map(|(index, work)| {
+            let album_id = album_id.clone();
+            let work_offset = work.tracks.start;
+            let starts_disc = index == 0 || tracks[work_offset - 1].disc_number != work.disc_number;
+            let work_tracks = tracks[work.tracks.clone()]
+                .iter()
+                .map(|track| Track {
+                    title: track.movement_title().to_string(),
+                    ..track.clone()
+                })
+                .collect::<Vec<_>>();
+
+            html! {
+                {(multiple_discs && starts_disc)
+                    .then_some(
+                        html! {
+                            <h3 class="px-4 pt-4 text-gray-400">
+                                {format!("Disc {}", work.disc_number)}
+                            </h3>
+                        },
+                    )}
+                {work
+                    .title
+                    .map(|work_title| {
+                        html! {
+                            <WorkHeading
+                                title=work_title
+                                composer=work.composer
+                                play_url=format!("{}/play-work/{}", album_id, work_offset)
+                            />
+                        }
+                    })}
+                <ListTracks
+                    now_playing_id=now_playing_id
+                    track_number_display=TrackNumberDisplay::Number
+                    tracks=work_tracks
+                    show_artist=false
+                    dim_played=false
+                    actions=TrackActions::AddToQueue
+                    api_call=move |index: usize| {
+                        url(&format!("/album/{}/play/{}", album_id, work_offset + index))
+                    }
+                />
+            }
+        })
+        .collect::<Vec<_>>();
 
     html! {
         <div
             class="w-full"
-            hx-get=format!("/album/{}/tracks", album_id_clone)
+            hx-get=url(&format!("/album/{}/tracks", album_id_clone))
             hx-target="this"
             hx-trigger="tracklist"
             data-sse="tracklist"
             hx-swap="morph:outerHTML"
         >
-            <ListTracks
-                now_playing_id=now_playing_id
-                track_number_display=TrackNumberDisplay::Number
-                tracks=tracks
-                show_artist=false
-                dim_played=false
-                api_call=move |index: usize| format!("/album/{album_id}/play/{index}")
-            />
+            {works}
+        </div>
+    }
+}
+
+#[component]
+fn work_heading(title: String, composer: Option<String>, play_url: String) -> impl IntoView {
+    html! {
+        <div class="flex gap-2 justify-between items-center px-4 pt-4">
+            <div class="flex flex-col">
+                <span class="text-gray-400">{composer}</span>
+                <span>{title}</span>
+            </div>
+            <button
+                class="cursor-pointer size-6 shrink-0"
+                title="Play work"
+                hx-swap="none"
+                hx-put=play_url
+            >
+                <Play />
+            </button>
         </div>
     }
 }
@@ -162,6 +257,7 @@ fn album(
     rfid: bool,
 ) -> impl IntoView {
     let duration = parse_duration(album.duration_seconds);
+    let details = album.detail_rows();
 
     let album_id_clone_1 = album.id.clone();
     let album_id_clone_2 = album.id.clone();
@@ -177,7 +273,7 @@ fn album(
             <div class="flex flex-col flex-grow gap-4 items-center w-full">
                 <div class="flex flex-col gap-2 justify-center items-center w-full text-center">
                     <a
-                        href=format!("/artist/{}", album.artist.id)
+                        href=url(&format!("/artist/{}", album.artist.id))
                         class="text-gray-400 rounded sm:text-lg"
                     >
                         {album.artist.name}
@@ -243,6 +339,7 @@ fn album(
             } else {
                 None
             }}
+            <Details title="Album details".to_string() rows=details />
             <Description description=album.description entity_title=album.title />
         </div>
     }
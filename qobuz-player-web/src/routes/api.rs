@@ -0,0 +1,546 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use qobuz_player_controls::{ReleaseSort, Status, error::Error, tracklist::Tracklist};
+use qobuz_player_models::{
+    Album, Artist, ArtistPage, ArtistReleases, Favorites, Playlist, SearchFilter, SearchPage, Track,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, base_path::url};
+
+const OPENAPI: &str = include_str!("openapi.json");
+
+/// Versioned json api, served behind the same auth as the web ui.
+pub(crate) fn routes() -> Router<Arc<AppState>> {
+    let api = Router::new()
+        .route("/status", get(status))
+        .route("/position", get(position).put(seek))
+        .route("/volume", get(volume).put(set_volume))
+        .route("/tracklist", get(tracklist))
+        .route("/queue/tracks/{id}", post(add_to_queue))
+        .route("/queue/entries/{entry}", delete(remove_from_queue))
+        .route("/queue/entries/{entry}/position", put(move_in_queue))
+        .route("/queue/clear-upcoming", post(clear_upcoming))
+        .route("/search/albums", get(search_albums))
+        .route("/search/artists", get(search_artists))
+        .route("/search/playlists", get(search_playlists))
+        .route("/search/tracks", get(search_tracks))
+        .route("/albums/{id}", get(album))
+        .route("/artists/{id}", get(artist))
+        .route("/artists/{id}/releases", get(artist_releases))
+        .route("/playlists/{id}", get(playlist))
+        .route("/tracks/{id}", get(track))
+        .route("/favorites", get(favorites))
+        .route(
+            "/favorites/albums/{id}",
+            put(add_favorite_album).delete(remove_favorite_album),
+        )
+        .route(
+            "/favorites/artists/{id}",
+            put(add_favorite_artist).delete(remove_favorite_artist),
+        )
+        .route(
+            "/favorites/playlists/{id}",
+            put(add_favorite_playlist).delete(remove_favorite_playlist),
+        )
+        .route("/controls/play", post(play))
+        .route("/controls/pause", post(pause))
+        .route("/controls/play-pause", post(play_pause))
+        .route("/controls/next", post(next))
+        .route("/controls/previous", post(previous))
+        .route("/controls/jump-forward", post(jump_forward))
+        .route("/controls/jump-backward", post(jump_backward))
+        .route("/controls/skip-to/{position}", post(skip_to))
+        .route("/play/album/{id}", post(play_album))
+        .route("/play/album/{id}/work", post(play_work))
+        .route("/play/playlist/{id}", post(play_playlist))
+        .route("/play/track/{id}", post(play_track))
+        .route("/play/artist/{id}", post(play_top_tracks))
+        .route("/play/url", post(play_url));
+
+    Router::new().nest("/api/v1", api)
+}
+
+/// The OpenAPI document, public so tooling can discover the api before authenticating.
+pub(crate) fn openapi_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/openapi.json", get(openapi))
+}
+
+/// The document with its server url below the base path the request came in on.
+async fn openapi() -> impl IntoResponse {
+    let mut document: serde_json::Value = serde_json::from_str(OPENAPI).expect("infailable");
+    document["servers"][0]["url"] = url("/api/v1").into();
+
+    Json(document)
+}
+
+struct ApiError(Error);
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        Self(error)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            Error::NotFound { .. } => StatusCode::NOT_FOUND,
+            Error::InvalidUrl { .. } => StatusCode::BAD_REQUEST,
+            Error::RegionRestricted { .. } => StatusCode::FORBIDDEN,
+            Error::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Error::Unauthorized { .. } | Error::Network | Error::Client { .. } => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        let body = ErrorBody {
+            error: self.0.to_string(),
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PlayerStatus {
+    pub(crate) status: Status,
+    pub(crate) position_ms: u64,
+    pub(crate) volume: u32,
+    pub(crate) current_track: Option<Track>,
+}
+
+async fn status(State(state): State<Arc<AppState>>) -> Json<PlayerStatus> {
+    Json(PlayerStatus {
+        status: *state.status_receiver.borrow(),
+        position_ms: state.position_receiver.borrow().as_millis() as u64,
+        volume: volume_percent(*state.volume_receiver.borrow()),
+        current_track: state.tracklist_receiver.borrow().current_track().cloned(),
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct Position {
+    position_ms: u64,
+}
+
+async fn position(State(state): State<Arc<AppState>>) -> Json<Position> {
+    Json(Position {
+        position_ms: state.position_receiver.borrow().as_millis() as u64,
+    })
+}
+
+async fn seek(State(state): State<Arc<AppState>>, Json(position): Json<Position>) -> StatusCode {
+    state
+        .controls
+        .seek(Duration::from_millis(position.position_ms));
+    StatusCode::NO_CONTENT
+}
+
+/// Volume in percent, like the volume slider of the web ui.
+#[derive(Serialize, Deserialize)]
+struct Volume {
+    volume: u32,
+}
+
+pub(crate) fn volume_percent(volume: f32) -> u32 {
+    (volume * 100.0).round() as u32
+}
+
+async fn volume(State(state): State<Arc<AppState>>) -> Json<Volume> {
+    Json(Volume {
+        volume: volume_percent(*state.volume_receiver.borrow()),
+    })
+}
+
+async fn set_volume(State(state): State<Arc<AppState>>, Json(volume): Json<Volume>) -> StatusCode {
+    state
+        .controls
+        .set_volume(volume.volume.min(100) as f32 / 100.0);
+    StatusCode::NO_CONTENT
+}
+
+async fn tracklist(State(state): State<Arc<AppState>>) -> Json<Tracklist> {
+    Json(state.tracklist_receiver.borrow().clone())
+}
+
+#[derive(Deserialize)]
+struct QueueParameters {
+    #[serde(default)]
+    next: bool,
+}
+
+async fn add_to_queue(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<QueueParameters>,
+) -> StatusCode {
+    match parameters.next {
+        true => state.controls.play_next(id),
+        false => state.controls.add_to_queue(id),
+    }
+    StatusCode::NO_CONTENT
+}
+
+async fn remove_from_queue(
+    State(state): State<Arc<AppState>>,
+    Path(entry): Path<u64>,
+) -> StatusCode {
+    state.controls.remove_from_queue(entry);
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct QueuePosition {
+    position: u32,
+}
+
+async fn move_in_queue(
+    State(state): State<Arc<AppState>>,
+    Path(entry): Path<u64>,
+    Json(position): Json<QueuePosition>,
+) -> StatusCode {
+    state.controls.move_in_queue(entry, position.position);
+    StatusCode::NO_CONTENT
+}
+
+async fn clear_upcoming(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.clear_upcoming();
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct SearchParameters {
+    query: String,
+    #[serde(default)]
+    offset: u32,
+    #[serde(default)]
+    hires_only: bool,
+    #[serde(default)]
+    hide_explicit: bool,
+}
+
+impl SearchParameters {
+    fn filter(&self) -> SearchFilter {
+        SearchFilter {
+            hires_only: self.hires_only,
+            hide_explicit: self.hide_explicit,
+        }
+    }
+}
+
+async fn search_albums(
+    State(state): State<Arc<AppState>>,
+    Query(parameters): Query<SearchParameters>,
+) -> ApiResult<SearchPage<Album>> {
+    let filter = parameters.filter();
+    Ok(Json(
+        state
+            .client
+            .search_albums(parameters.query, filter, parameters.offset)
+            .await?,
+    ))
+}
+
+async fn search_artists(
+    State(state): State<Arc<AppState>>,
+    Query(parameters): Query<SearchParameters>,
+) -> ApiResult<SearchPage<Artist>> {
+    Ok(Json(
+        state
+            .client
+            .search_artists(parameters.query, parameters.offset)
+            .await?,
+    ))
+}
+
+async fn search_playlists(
+    State(state): State<Arc<AppState>>,
+    Query(parameters): Query<SearchParameters>,
+) -> ApiResult<SearchPage<Playlist>> {
+    Ok(Json(
+        state
+            .client
+            .search_playlists(parameters.query, parameters.offset)
+            .await?,
+    ))
+}
+
+async fn search_tracks(
+    State(state): State<Arc<AppState>>,
+    Query(parameters): Query<SearchParameters>,
+) -> ApiResult<SearchPage<Track>> {
+    let filter = parameters.filter();
+    Ok(Json(
+        state
+            .client
+            .search_tracks(parameters.query, filter, parameters.offset)
+            .await?,
+    ))
+}
+
+async fn album(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> ApiResult<Album> {
+    Ok(Json(state.client.album(&id).await?))
+}
+
+async fn artist(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ApiResult<ArtistPage> {
+    Ok(Json(state.client.artist_page(id).await?))
+}
+
+#[derive(Deserialize)]
+struct ReleasesParameters {
+    #[serde(default)]
+    sort: ReleaseSort,
+}
+
+async fn artist_releases(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<ReleasesParameters>,
+) -> ApiResult<Vec<ArtistReleases>> {
+    Ok(Json(
+        state.client.artist_discography(id, parameters.sort).await?,
+    ))
+}
+
+async fn playlist(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ApiResult<Playlist> {
+    Ok(Json(state.client.playlist(id).await?))
+}
+
+async fn track(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ApiResult<Track> {
+    Ok(Json(state.client.track(id).await?))
+}
+
+async fn favorites(State(state): State<Arc<AppState>>) -> ApiResult<Favorites> {
+    Ok(Json(state.get_favorites().await?))
+}
+
+async fn add_favorite_album(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.add_favorite_album(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_favorite_album(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.remove_favorite_album(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_favorite_artist(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.add_favorite_artist(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_favorite_artist(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.remove_favorite_artist(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_favorite_playlist(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.add_favorite_playlist(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_favorite_playlist(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.client.remove_favorite_playlist(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn play(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.play();
+    StatusCode::NO_CONTENT
+}
+
+async fn pause(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.pause();
+    StatusCode::NO_CONTENT
+}
+
+async fn play_pause(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.play_pause();
+    StatusCode::NO_CONTENT
+}
+
+async fn next(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.next();
+    StatusCode::NO_CONTENT
+}
+
+async fn previous(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.previous();
+    StatusCode::NO_CONTENT
+}
+
+async fn jump_forward(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.jump_forward();
+    StatusCode::NO_CONTENT
+}
+
+async fn jump_backward(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.controls.jump_backward();
+    StatusCode::NO_CONTENT
+}
+
+async fn skip_to(State(state): State<Arc<AppState>>, Path(position): Path<u32>) -> StatusCode {
+    state.controls.skip_to_position(position, true);
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct PlayParameters {
+    #[serde(default)]
+    index: u32,
+    #[serde(default)]
+    shuffle: bool,
+}
+
+async fn play_album(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(parameters): Query<PlayParameters>,
+) -> StatusCode {
+    state.controls.play_album(&id, parameters.index);
+    StatusCode::NO_CONTENT
+}
+
+async fn play_work(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(parameters): Query<PlayParameters>,
+) -> StatusCode {
+    state.controls.play_work(&id, parameters.index);
+    StatusCode::NO_CONTENT
+}
+
+async fn play_playlist(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<PlayParameters>,
+) -> StatusCode {
+    state
+        .controls
+        .play_playlist(id, parameters.index, parameters.shuffle);
+    StatusCode::NO_CONTENT
+}
+
+async fn play_track(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> StatusCode {
+    state.controls.play_track(id);
+    StatusCode::NO_CONTENT
+}
+
+async fn play_top_tracks(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<PlayParameters>,
+) -> StatusCode {
+    state.controls.play_top_tracks(id, parameters.index);
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct PlayUrl {
+    url: String,
+}
+
+async fn play_url(
+    State(state): State<Arc<AppState>>,
+    Json(parameters): Json<PlayUrl>,
+) -> Result<StatusCode, ApiError> {
+    state.controls.play_url(&parameters.url)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Method, StatusCode};
+
+    use super::OPENAPI;
+    use crate::testing::TestServer;
+
+    #[tokio::test]
+    async fn router_answers_every_documented_operation() {
+        let server = TestServer::start().await;
+        let client = reqwest::Client::new();
+        let document: serde_json::Value = serde_json::from_str(OPENAPI).unwrap();
+        let base = document["servers"][0]["url"].as_str().unwrap();
+
+        for (path, operations) in document["paths"].as_object().unwrap() {
+            // Any value reaches the handler, which may then reject it.
+            let path = path
+                .split('/')
+                .map(|segment| match segment.starts_with('{') {
+                    true => "1",
+                    false => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            for method in operations.as_object().unwrap().keys() {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+                let response = client
+                    .request(method.clone(), server.url(&format!("{base}{path}")))
+                    .send()
+                    .await
+                    .unwrap();
+                let status = response.status();
+
+                assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {path}");
+                // A handler answers a missing item with a json error, the router with nothing.
+                if status == StatusCode::NOT_FOUND {
+                    let body: Option<serde_json::Value> = response.json().await.ok();
+                    assert!(
+                        body.is_some_and(|body| body["error"].is_string()),
+                        "{method} {path} is not routed"
+                    );
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn album_comes_from_the_client() {
+        let server = TestServer::start().await;
+
+        let response = reqwest::get(server.url("/api/v1/albums/fixturealbum"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let album: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(album["id"], "fixturealbum");
+        assert_eq!(album["title"], "Fixture Album");
+        assert_eq!(album["tracks"].as_array().map(Vec::len), Some(2));
+    }
+}
@@ -2,24 +2,29 @@ use std::sync::Arc;
 
 use axum::{
     Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, put},
 };
 use leptos::prelude::*;
-use qobuz_player_models::{AlbumSimple, Artist, ArtistPage, Track};
+use qobuz_player_controls::ReleaseSort;
+use qobuz_player_models::{Artist, ArtistPage, ArtistReleases, ReleaseType, Track};
+use serde::Deserialize;
 use tokio::try_join;
 
 use crate::{
     AppState, ResponseResult,
+    base_path::url,
     components::{
         ButtonGroup, Description, Info, ToggleFavorite, button_class,
-        list::{ListAlbumsVertical, ListArtistsVertical},
+        list::{AddToQueueButton, AlbumTile, ListArtistsVertical},
     },
     html,
     icons::Play,
     ok_or_broadcast, ok_or_error_component,
     page::Page,
+    routes::zones::SelectedZone,
     view::{LazyLoadComponent, render},
 };
 
@@ -28,6 +33,14 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
         .route("/artist/{id}", get(index))
         .route("/artist/{id}/content", get(content))
         .route("/artist/{id}/top-tracks", get(top_tracks_partial))
+        .route(
+            "/artist/{id}/releases/{release_type}",
+            get(releases_partial),
+        )
+        .route(
+            "/artist/{id}/discography/{release_type}",
+            get(discography_partial),
+        )
         .route("/artist/{id}/set-favorite", put(set_favorite))
         .route("/artist/{id}/unset-favorite", put(unset_favorite))
         .route(
@@ -48,11 +61,61 @@ async fn top_tracks_partial(
     ))
 }
 
+#[derive(Deserialize, Clone)]
+struct ReleasesParameters {
+    offset: Option<u32>,
+    #[serde(default)]
+    sort: ReleaseSort,
+}
+
+async fn releases_partial(
+    State(state): State<Arc<AppState>>,
+    Path((id, release_type)): Path<(u32, ReleaseType)>,
+    Query(parameters): Query<ReleasesParameters>,
+) -> ResponseResult {
+    let offset = parameters.offset.unwrap_or(0);
+    let sort = parameters.sort;
+    let releases = ok_or_error_component(
+        state
+            .client
+            .artist_releases(id, release_type, sort, offset)
+            .await,
+    )?;
+
+    Ok(render(
+        html! { <ReleaseTiles artist_id=id releases=releases sort=sort offset=offset /> },
+    ))
+}
+
+async fn discography_partial(
+    State(state): State<Arc<AppState>>,
+    Path((id, release_type)): Path<(u32, ReleaseType)>,
+    Query(parameters): Query<ReleasesParameters>,
+) -> ResponseResult {
+    let sort = parameters.sort;
+    let releases = ok_or_error_component(
+        state
+            .client
+            .artist_releases(id, release_type, sort, 0)
+            .await,
+    )?;
+
+    Ok(render(
+        html! { <Discography artist_id=id releases=releases sort=sort /> },
+    ))
+}
+
 async fn play_top_track(
     State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
     Path((artist_id, track_index)): Path<(u32, u32)>,
-) -> impl IntoResponse {
-    state.controls.play_top_tracks(artist_id, track_index);
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_top_tracks(artist_id, track_index).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn set_favorite(
@@ -78,7 +141,7 @@ async fn unset_favorite(
 }
 
 async fn index(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
-    let url = format!("/artist/{id}/content");
+    let url = url(&format!("/artist/{id}/content"));
 
     let current_status = state.status_receiver.borrow();
     let tracklist = state.tracklist_receiver.borrow().clone();
@@ -91,9 +154,11 @@ async fn index(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl
 }
 
 async fn content(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ResponseResult {
-    let (artist, albums, similar_artists) = ok_or_error_component(try_join!(
+    let (artist, releases, similar_artists) = ok_or_error_component(try_join!(
         state.client.artist_page(id),
-        state.client.artist_albums(id),
+        state
+            .client
+            .artist_releases(id, ReleaseType::Album, ReleaseSort::ReleaseDate, 0),
         state.client.similar_artists(id),
     ))?;
 
@@ -105,7 +170,7 @@ async fn content(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> Res
     Ok(render(html! {
         <Artist
             artist=artist
-            albums=albums
+            releases=releases
             is_favorite=is_favorite
             similar_artists=similar_artists
             now_playing_id=now_playing_id
@@ -123,11 +188,13 @@ fn artist_image(artist_image: Option<String>, artist_name: String) -> impl IntoV
 #[component]
 fn artist(
     artist: ArtistPage,
-    albums: Vec<AlbumSimple>,
+    releases: ArtistReleases,
     similar_artists: Vec<Artist>,
     is_favorite: bool,
     now_playing_id: Option<u32>,
 ) -> impl IntoView {
+    let artist_id = artist.id;
+
     html! {
         <div class="flex flex-col">
             <div class="self-center p-4 max-w-md">
@@ -173,10 +240,7 @@ fn artist(
                     </div>
                 </div>
 
-                <div class="flex flex-col gap-2">
-                    <h3 class="px-4 text-lg">Albums</h3>
-                    <ListAlbumsVertical albums=albums />
-                </div>
+                <Discography artist_id=artist_id releases=releases sort=ReleaseSort::default() />
                 {if !similar_artists.is_empty() {
                     Some(
                         html! {
@@ -195,6 +259,121 @@ fn artist(
     }
 }
 
+/// Release types in the order of the discography tabs.
+const RELEASE_TABS: [ReleaseType; 5] = [
+    ReleaseType::Album,
+    ReleaseType::EpSingle,
+    ReleaseType::Live,
+    ReleaseType::Compilation,
+    ReleaseType::Other,
+];
+
+/// Orders offered for the releases, with their labels.
+const RELEASE_SORTS: [(ReleaseSort, &str); 3] = [
+    (ReleaseSort::ReleaseDate, "Newest"),
+    (ReleaseSort::ReleaseDateByPriority, "Featured"),
+    (ReleaseSort::Relevant, "Popular"),
+];
+
+/// Tabs for the release types, showing the releases of one. Other tabs load when selected.
+#[component]
+fn discography(artist_id: u32, releases: ArtistReleases, sort: ReleaseSort) -> impl IntoView {
+    let selected = releases.release_type;
+
+    let tabs = RELEASE_TABS
+        .into_iter()
+        .map(|release_type| {
+            html! {
+                <button
+                    class=(release_type == selected).then_some("bg-blue-800")
+                    hx-get=url(&format!("/artist/{}/discography/{}?sort={}", artist_id, release_type.as_str(), sort))
+                    hx-target="#discography"
+                    hx-swap="outerHTML"
+                >
+                    {release_type.to_string()}
+                </button>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let sorts = RELEASE_SORTS
+        .into_iter()
+        .map(|(release_sort, label)| {
+            html! {
+                <button
+                    class=(release_sort == sort).then_some("bg-gray-800")
+                    hx-get=url(&format!("/artist/{}/discography/{}?sort={}", artist_id, selected.as_str(), release_sort))
+                    hx-target="#discography"
+                    hx-swap="outerHTML"
+                >
+                    {label}
+                </button>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let tiles = match releases.albums.is_empty() {
+        true => html! { <p class="px-2 text-gray-400">No releases</p> }.into_any(),
+        false => {
+            html! { <ReleaseTiles artist_id=artist_id releases=releases sort=sort offset=0 /> }
+                .into_any()
+        }
+    };
+
+    html! {
+        <div id="discography" class="flex flex-col gap-2">
+            <div class="flex overflow-x-auto gap-2 px-4 whitespace-nowrap *:cursor-pointer *:rounded-full *:px-2 *:py-1 *:transition-colors">
+                {tabs}
+            </div>
+            <div class="flex overflow-x-auto gap-2 px-4 text-sm text-gray-400 whitespace-nowrap *:cursor-pointer *:rounded-full *:px-2 *:py-1 *:transition-colors">
+                {sorts}
+            </div>
+            <div class="flex overflow-scroll gap-4 p-2 w-full">{tiles}</div>
+        </div>
+    }
+}
+
+/// Album tiles of one page of releases, followed by a button loading the next page in its place.
+#[component]
+fn release_tiles(
+    artist_id: u32,
+    releases: ArtistReleases,
+    sort: ReleaseSort,
+    offset: u32,
+) -> impl IntoView {
+    let next_page_url = url(&format!(
+        "/artist/{}/releases/{}?offset={}&sort={}",
+        artist_id,
+        releases.release_type.as_str(),
+        offset + releases.albums.len() as u32,
+        sort
+    ));
+
+    let load_more = releases.has_more.then(|| {
+        html! {
+            <button
+                class="flex justify-center items-center bg-gray-800 rounded-lg cursor-pointer shrink-0 size-32"
+                hx-get=next_page_url
+                hx-target="this"
+                hx-swap="outerHTML"
+            >
+                More
+            </button>
+        }
+    });
+
+    let albums = releases
+        .albums
+        .into_iter()
+        .map(|album| html! { <AlbumTile album=album /> })
+        .collect::<Vec<_>>();
+
+    html! {
+        {albums}
+        {load_more}
+    }
+}
+
 #[component]
 fn list_tracks(artist_id: u32, tracks: Vec<Track>, now_playing_id: Option<u32>) -> impl IntoView {
     tracks
@@ -209,40 +388,45 @@ fn list_tracks(artist_id: u32, tracks: Vec<Track>, now_playing_id: Option<u32>)
 
 #[component]
 fn track(artist_id: u32, track: Track, index: usize, is_playing: bool) -> impl IntoView {
+    let track_id = track.id;
+
     html! {
-        <button
-            class="flex gap-4 items-center w-5/6 max-w-lg rounded cursor-pointer"
-            hx-put=format!("{}/play-top-track/{}", artist_id, index)
-            hx-swap="none"
-        >
-            <img
-                class="inline text-sm text-gray-500 bg-gray-800 rounded-md aspect-square size-12"
-                alt=track.title.clone()
-                src=track.image
-            />
-
-            <div class="overflow-hidden w-full">
-                <div class="flex gap-2 items-center">
-                    <h3 class="text-lg truncate">{track.title}</h3>
-                    <Info explicit=track.explicit hires_available=track.hires_available />
-                    {is_playing
-                        .then_some({
-                            html! {
-                                <div class="text-blue-500 size-6">
-                                    <Play />
-                                </div>
-                            }
-                        })}
-                </div>
+        <div class="flex gap-2 items-center w-5/6 max-w-lg">
+            <button
+                class="flex gap-4 items-center w-full rounded cursor-pointer"
+                hx-put=format!("{}/play-top-track/{}", artist_id, index)
+                hx-swap="none"
+            >
+                <img
+                    class="inline text-sm text-gray-500 bg-gray-800 rounded-md aspect-square size-12"
+                    alt=track.title.clone()
+                    src=track.image
+                />
 
-                <h4 class="flex gap-2 text-left text-gray-400">
-                    {track
-                        .album_title
-                        .map(|album_title| {
-                            html! { <span class="truncate">{album_title}</span> }
-                        })}
-                </h4>
-            </div>
-        </button>
+                <div class="overflow-hidden w-full">
+                    <div class="flex gap-2 items-center">
+                        <h3 class="text-lg truncate">{track.title}</h3>
+                        <Info explicit=track.explicit hires_available=track.hires_available />
+                        {is_playing
+                            .then_some({
+                                html! {
+                                    <div class="text-blue-500 size-6">
+                                        <Play />
+                                    </div>
+                                }
+                            })}
+                    </div>
+
+                    <h4 class="flex gap-2 text-left text-gray-400">
+                        {track
+                            .album_title
+                            .map(|album_title| {
+                                html! { <span class="truncate">{album_title}</span> }
+                            })}
+                    </h4>
+                </div>
+            </button>
+            <AddToQueueButton track_id=track_id />
+        </div>
     }
 }
@@ -1,9 +1,12 @@
-use crate::{AppState, components::button_class, html, page::UnauthorizedPage, view::render};
+use crate::{
+    AppState, base_path::url, components::button_class, html, listener::ClientIp,
+    page::UnauthorizedPage, view::render,
+};
 use axum::{
     Form, Router,
     body::Body,
-    extract::{Request, State},
-    http::{Response, StatusCode},
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, Response, StatusCode, header},
     response::IntoResponse,
     routing::{get, post},
 };
@@ -12,67 +15,231 @@ use axum_extra::extract::{
     cookie::{Cookie, SameSite},
 };
 use leptos::prelude::*;
+use qobuz_player_controls::auth::{new_session, verify_secret, verify_session};
 use serde::Deserialize;
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const SESSION_COOKIE: &str = "session";
+const SESSION_LIFETIME: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
+/// Failed logins allowed before the login is locked.
+const FREE_ATTEMPTS: u32 = 5;
+const MAX_LOCKOUT: Duration = Duration::from_secs(5 * 60);
+/// Failed logins of a client are forgotten after this long without another one.
+const FORGET_FAILURES: Duration = Duration::from_secs(60 * 60);
+
+/// Pages to log in, open without a session.
 pub(crate) fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/auth", get(index))
         .route("/auth/login", post(login))
 }
 
+/// Routes needing a session, behind [`auth_middleware`].
+pub(crate) fn session_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/auth/logout", post(logout))
+}
+
+/// Requests pass with a valid session cookie, or an api token on the api and websocket.
+/// Session requests that change something must also come from the web ui itself, to protect
+/// against cross-site requests.
 pub(crate) async fn auth_middleware(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
     request: Request,
     next: axum::middleware::Next,
-) -> (CookieJar, Response<Body>) {
-    let Some(state_secret) = state.web_secret.clone() else {
-        return (jar, next.run(request).await);
-    };
-
-    let redirect_response = (
-        StatusCode::FOUND,
-        [
-            (
-                axum::http::header::CONTENT_TYPE,
-                axum::http::HeaderValue::from_static(mime::TEXT_HTML_UTF_8.as_ref()),
-            ),
-            (
-                axum::http::header::LOCATION,
-                axum::http::HeaderValue::from_static("/auth"),
-            ),
-        ],
-    )
-        .into_response();
+) -> Response<Body> {
+    if state.secret_hash.is_none() {
+        return next.run(request).await;
+    }
 
-    let secret = jar.get("secret");
+    let path = request.uri().path();
+    let is_api = path.starts_with("/api/");
+    let accepts_token = is_api || path == "/ws";
 
-    match secret {
-        Some(result_secret) => {
-            if state_secret != result_secret.value() {
-                return (jar, redirect_response);
-            }
+    if let Some(token) = bearer_token(request.headers()) {
+        if !accepts_token {
+            return unauthorized_response(is_api);
         }
-        None => return (jar, redirect_response),
+
+        return match state.database.is_api_token_valid(&token).await {
+            Ok(true) => next.run(request).await,
+            _ => unauthorized_response(is_api),
+        };
     }
 
-    (set_auth_cookie(jar, state_secret), next.run(request).await)
+    let has_session = jar
+        .get(SESSION_COOKIE)
+        .is_some_and(|cookie| verify_session(&state.session_key, cookie.value()));
+
+    if !has_session {
+        return unauthorized_response(is_api);
+    }
+
+    if !request.method().is_safe() && !is_same_origin(request.headers()) {
+        return (StatusCode::FORBIDDEN, "Cross-site request rejected").into_response();
+    }
+
+    next.run(request).await
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
 }
 
-fn set_auth_cookie(jar: CookieJar, secret: String) -> CookieJar {
-    let mut cookie = Cookie::new("secret", secret);
+/// Htmx requests carry a custom header, which other sites can not set. Anything else needs
+/// an origin matching the host.
+fn is_same_origin(headers: &HeaderMap) -> bool {
+    if headers.contains_key("HX-Request") {
+        return true;
+    }
+
+    let origin = headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok());
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+
+    match (origin, host) {
+        (Some(origin), Some(host)) => origin
+            .split_once("://")
+            .is_some_and(|(_, origin_host)| origin_host == host),
+        _ => false,
+    }
+}
+
+fn unauthorized_response(is_api: bool) -> Response<Body> {
+    // Api clients get a json error instead of being sent to the login page.
+    match is_api {
+        true => (
+            StatusCode::UNAUTHORIZED,
+            [(
+                header::CONTENT_TYPE,
+                axum::http::HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+            )],
+            r#"{"error":"Unauthorized"}"#,
+        )
+            .into_response(),
+        false => (
+            StatusCode::FOUND,
+            [
+                (
+                    header::CONTENT_TYPE,
+                    axum::http::HeaderValue::from_static(mime::TEXT_HTML_UTF_8.as_ref()),
+                ),
+                (
+                    header::LOCATION,
+                    axum::http::HeaderValue::from_static("/auth"),
+                ),
+            ],
+        )
+            .into_response(),
+    }
+}
+
+fn set_session_cookie(jar: CookieJar, session: String) -> CookieJar {
+    let mut cookie = Cookie::new(SESSION_COOKIE, session);
     cookie.set_same_site(SameSite::Strict);
+    cookie.set_http_only(true);
     cookie.set_path("/");
-    cookie.set_max_age(time::Duration::weeks(1));
+    cookie.set_max_age(time::Duration::seconds(SESSION_LIFETIME.as_secs() as i64));
     jar.add(cookie)
 }
 
+/// Locks the login of a client for a doubling period once [`FREE_ATTEMPTS`] logins in a row
+/// have failed. Clients are told apart by ip, see [`client_address`]. Those on a unix socket
+/// share one lock, as do all clients of a reverse proxy that is not trusted.
+#[derive(Default)]
+pub(crate) struct LoginThrottle {
+    clients: HashMap<Option<IpAddr>, Failures>,
+}
+
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl LoginThrottle {
+    fn retry_after(&self, client: Option<IpAddr>) -> Option<Duration> {
+        self.clients
+            .get(&client)?
+            .locked_until?
+            .checked_duration_since(Instant::now())
+    }
+
+    fn failed(&mut self, client: Option<IpAddr>) {
+        let now = Instant::now();
+        self.clients
+            .retain(|_, failures| now.duration_since(failures.last) < FORGET_FAILURES);
+
+        let failures = self.clients.entry(client).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        failures.count += 1;
+        failures.last = now;
+
+        if failures.count >= FREE_ATTEMPTS {
+            let lockout = Duration::from_secs(1 << (failures.count - FREE_ATTEMPTS).min(16));
+            failures.locked_until = Some(now + lockout.min(MAX_LOCKOUT));
+        }
+    }
+
+    fn succeeded(&mut self, client: Option<IpAddr>) {
+        self.clients.remove(&client);
+    }
+}
+
+/// Address a login is throttled on: the peer, or for a trusted proxy the client it forwarded.
+fn client_address(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    // Every proxy appends the address it got the request from, so the client is the last
+    // entry not added by a trusted proxy. Anything before it could be made up by the client.
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+
+    for entry in forwarded.into_iter().rev() {
+        match entry.parse::<IpAddr>() {
+            Ok(address) if trusted_proxies.contains(&address) => continue,
+            Ok(address) => return Some(address),
+            Err(_) => break,
+        }
+    }
+
+    Some(peer)
+}
+
 async fn index() -> impl IntoResponse {
     render(html! {
         <UnauthorizedPage>
             <div class="flex justify-center items-center w-full h-full">
-                <form class="flex flex-col gap-4" action="/auth/login" method="post">
+                <form class="flex flex-col gap-4" action=url("/auth/login") method="post">
                     <input
                         class="p-2 w-full text-black bg-white rounded"
                         type="password"
@@ -96,34 +263,117 @@ struct LoginParameters {
 
 async fn login(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(ClientIp(peer)): ConnectInfo<ClientIp>,
+    headers: HeaderMap,
     jar: CookieJar,
     Form(parameters): Form<LoginParameters>,
 ) -> (CookieJar, Response<Body>) {
+    let client = client_address(peer, &headers, &state.trusted_proxies);
+
     let response = (
         StatusCode::FOUND,
         [
             (
-                axum::http::header::CONTENT_TYPE,
+                header::CONTENT_TYPE,
                 axum::http::HeaderValue::from_static(mime::TEXT_HTML_UTF_8.as_ref()),
             ),
-            (
-                axum::http::header::LOCATION,
-                axum::http::HeaderValue::from_static("/"),
-            ),
+            (header::LOCATION, axum::http::HeaderValue::from_static("/")),
         ],
         "Success",
     )
         .into_response();
 
-    match state.web_secret.clone() {
-        None => return (jar, response),
-        Some(secret) => {
-            if secret == parameters.secret {
-                return (set_auth_cookie(jar, secret), response);
-            };
-        }
+    let Some(secret_hash) = state.secret_hash.clone() else {
+        return (jar, response);
+    };
+
+    let retry_after = state
+        .login_throttle
+        .lock()
+        .expect("infailable")
+        .retry_after(client);
+    if let Some(retry_after) = retry_after {
+        let seconds = retry_after.as_secs().max(1);
+        let response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, seconds.to_string())],
+            format!("Too many failed attempts. Try again in {seconds} seconds"),
+        )
+            .into_response();
+        return (jar, response);
     }
 
+    let valid =
+        tokio::task::spawn_blocking(move || verify_secret(&parameters.secret, &secret_hash))
+            .await
+            .unwrap_or(false);
+
+    let mut throttle = state.login_throttle.lock().expect("infailable");
+    if valid {
+        throttle.succeeded(client);
+        let session = new_session(&state.session_key, SESSION_LIFETIME);
+        return (set_session_cookie(jar, session), response);
+    }
+
+    throttle.failed(client);
     let response = (StatusCode::UNAUTHORIZED, "Bad credentials").into_response();
     (jar, response)
 }
+
+async fn logout(jar: CookieJar) -> (CookieJar, Response<Body>) {
+    let response = (
+        StatusCode::OK,
+        [("HX-Redirect", axum::http::HeaderValue::from_static("/auth"))],
+    )
+        .into_response();
+
+    (
+        jar.remove(Cookie::build(SESSION_COOKIE).path("/")),
+        response,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_out_only_the_failing_client() {
+        let mut throttle = LoginThrottle::default();
+        let attacker = Some(IpAddr::from([192, 0, 2, 1]));
+        let user = Some(IpAddr::from([192, 0, 2, 2]));
+
+        for _ in 0..FREE_ATTEMPTS {
+            throttle.failed(attacker);
+        }
+
+        assert!(throttle.retry_after(attacker).is_some());
+        assert!(throttle.retry_after(user).is_none());
+        assert!(throttle.retry_after(None).is_none());
+
+        throttle.succeeded(attacker);
+        assert!(throttle.retry_after(attacker).is_none());
+    }
+
+    #[test]
+    fn reads_the_client_from_trusted_proxies_only() {
+        let proxy = IpAddr::from([10, 0, 0, 1]);
+        let client = IpAddr::from([192, 0, 2, 1]);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "203.0.113.9, 192.0.2.1, 10.0.0.1".parse().unwrap(),
+        );
+
+        assert_eq!(
+            client_address(Some(proxy), &headers, &[proxy]),
+            Some(client)
+        );
+        assert_eq!(client_address(Some(proxy), &headers, &[]), Some(proxy));
+        assert_eq!(client_address(None, &headers, &[proxy]), None);
+        assert_eq!(
+            client_address(Some(proxy), &HeaderMap::new(), &[proxy]),
+            Some(proxy)
+        );
+    }
+}
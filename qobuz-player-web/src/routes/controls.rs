@@ -8,7 +8,9 @@ use qobuz_player_controls::{
 };
 
 use crate::{
-    AppState, html,
+    AppState,
+    base_path::url,
+    html,
     now_playing::PlayerState,
     routes::now_playing::{Next, Previous},
     view::render,
@@ -22,7 +24,7 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
 pub(crate) fn controls<'a>(current_status: Status, tracklist: &'a Tracklist) -> impl IntoView {
     html! {
         <div
-            hx-get="/controls"
+            hx-get=url("/controls")
             hx-trigger="tracklist"
             data-sse="tracklist"
             hx-target="this"
@@ -49,22 +51,25 @@ fn controls_partial<'a>(current_status: Status, tracklist: &'a Tracklist) -> imp
         TracklistType::Album(tracklist) => (
             image(tracklist.image.clone(), false).into_any(),
             Some(tracklist.title.clone()),
-            Some(format!("/album/{}", tracklist.id)),
+            Some(url(&format!("/album/{}", tracklist.id))),
         ),
         TracklistType::Playlist(tracklist) => (
             image(tracklist.image.clone(), false).into_any(),
             Some(tracklist.title.clone()),
-            Some(format!("/playlist/{}", tracklist.id)),
+            Some(url(&format!("/playlist/{}", tracklist.id))),
         ),
         TracklistType::TopTracks(tracklist) => (
             image(tracklist.image.clone(), true).into_any(),
             Some(tracklist.artist_name.clone()),
-            Some(format!("/artist/{}", tracklist.id)),
+            Some(url(&format!("/artist/{}", tracklist.id))),
         ),
         TracklistType::Track(tracklist) => (
             image(tracklist.image.clone(), false).into_any(),
             Some(tracklist.track_title.clone()),
-            tracklist.album_id.as_ref().map(|id| format!("/album/{id}")),
+            tracklist
+                .album_id
+                .as_ref()
+                .map(|id| url(&format!("/album/{id}"))),
         ),
         TracklistType::None => (image(None, false).into_any(), None, None),
     };
@@ -1,15 +1,22 @@
 use std::sync::Arc;
 
-use axum::{Router, extract::State, routing::get};
+use axum::{
+    Router,
+    extract::{Query, State},
+    routing::get,
+};
 use leptos::prelude::*;
-use qobuz_player_models::{AlbumSimple, Playlist};
-use tokio::try_join;
+use qobuz_player_models::{AlbumSimple, Genre, Playlist};
+use serde::Deserialize;
+use tokio::{join, try_join};
 
 use crate::{
     AppState, Discover, ResponseResult,
+    base_path::url,
     components::list::{ListAlbumsVertical, ListPlaylistsVertical},
     html, ok_or_error_component,
     page::Page,
+    routes::zones::SelectedZone,
     view::render,
 };
 
@@ -17,16 +24,38 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
     Router::new().route("/discover", get(index))
 }
 
-async fn index(State(state): State<Arc<AppState>>) -> ResponseResult {
-    let (albums, playlists) = ok_or_error_component(try_join!(
-        state.client.featured_albums(),
-        state.client.featured_playlists(),
-    ))?;
+#[derive(Deserialize, Clone)]
+struct DiscoverParameters {
+    genre: Option<u32>,
+}
+
+async fn index(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Query(parameters): Query<DiscoverParameters>,
+) -> ResponseResult {
+    let genre = parameters.genre;
+
+    let (genres, featured) = join!(state.client.genres(), async {
+        try_join!(
+            state.client.featured_albums(genre),
+            state.client.featured_playlists(genre),
+        )
+    });
+    let (albums, playlists) = ok_or_error_component(featured)?;
+    // The genres only narrow down the lists, show them unfiltered when genres fail to load.
+    let genres = genres.unwrap_or_default();
 
     let discover = Discover { albums, playlists };
 
     let tracklist = state.tracklist_receiver.borrow().clone();
     let current_status = state.status_receiver.borrow();
+    let has_peers = state.zones.len() > 1;
+    // Music picked on the pages plays on the selected zone.
+    let zones_label = match selected.zone.is_local() {
+        true => "Zones".to_string(),
+        false => format!("Zone: {}", selected.zone.name()),
+    };
 
     let album_features = discover
         .albums
@@ -44,7 +73,23 @@ async fn index(State(state): State<Arc<AppState>>) -> ResponseResult {
         <Page active_page=Page::Discover current_status=*current_status tracklist=&tracklist>
             <div class="flex flex-col gap-8 px-4">
                 <div class="flex sticky top-0 flex-col flex-grow gap-4 pb-2 max-h-full pt-safe-or-4 bg-black/80 backdrop-blur">
-                    <h1 class="text-2xl">Discover</h1>
+                    <div class="flex justify-between items-center">
+                        <h1 class="text-2xl">Discover</h1>
+                        <div class="flex gap-4">
+                            {has_peers
+                                .then(|| {
+                                    html! {
+                                        <a href=url("/zones") class="text-gray-400">
+                                            {zones_label}
+                                        </a>
+                                    }
+                                })}
+                            <a href=url("/settings") class="text-gray-400">
+                                Settings
+                            </a>
+                        </div>
+                    </div>
+                    <GenrePicker genres=genres selected=genre />
                 </div>
                 {album_features}
                 {playlist_features}
@@ -53,6 +98,32 @@ async fn index(State(state): State<Arc<AppState>>) -> ResponseResult {
     }))
 }
 
+#[component]
+fn genre_picker(genres: Vec<Genre>, selected: Option<u32>) -> impl IntoView {
+    let genre_links = genres
+        .into_iter()
+        .map(|genre| {
+            html! {
+                <a
+                    href=url(&format!("/discover?genre={}", genre.id))
+                    class=(selected == Some(genre.id)).then_some("bg-blue-800")
+                >
+                    {genre.name}
+                </a>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    html! {
+        <div class="flex overflow-x-auto gap-2 whitespace-nowrap *:rounded-full *:px-2 *:py-1 *:transition-colors">
+            <a href=url("/discover") class=selected.is_none().then_some("bg-blue-800")>
+                All genres
+            </a>
+            {genre_links}
+        </div>
+    }
+}
+
 #[component]
 fn album_feature(albums: Vec<AlbumSimple>, name: String) -> impl IntoView {
     html! {
@@ -1,4 +1,5 @@
 pub(crate) mod album;
+pub(crate) mod api;
 pub(crate) mod artist;
 pub(crate) mod auth;
 pub(crate) mod controls;
@@ -8,3 +9,7 @@ pub(crate) mod now_playing;
 pub(crate) mod playlist;
 pub(crate) mod queue;
 pub(crate) mod search;
+pub(crate) mod settings;
+pub(crate) mod track;
+pub(crate) mod ws;
+pub(crate) mod zones;
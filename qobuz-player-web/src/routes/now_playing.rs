@@ -14,6 +14,7 @@ use qobuz_player_controls::{
 
 use crate::{
     AppState,
+    base_path::url,
     components::Info,
     html,
     icons::{Backward, Forward, LoadingSpinner, Pause, Play},
@@ -107,7 +108,7 @@ fn play_pause(status: Status) -> impl IntoView {
         <button
             class="transition-colors cursor-pointer"
             hx-swap="none"
-            hx-put=format!("{}", if playing { "/pause" } else { "/play" })
+            hx-put=url(if playing { "/pause" } else { "/play" })
         >
             {status_icon}
         </button>
@@ -117,7 +118,7 @@ fn play_pause(status: Status) -> impl IntoView {
 #[component]
 pub(crate) fn next() -> impl IntoView {
     html! {
-        <button hx-swap="none" hx-put="/next" class="transition-colors cursor-pointer">
+        <button hx-swap="none" hx-put=url("/next") class="transition-colors cursor-pointer">
             <Forward />
         </button>
     }
@@ -126,7 +127,7 @@ pub(crate) fn next() -> impl IntoView {
 #[component]
 pub(crate) fn previous() -> impl IntoView {
     html! {
-        <button hx-swap="none" hx-put="/previous" class="transition-colors cursor-pointer">
+        <button hx-swap="none" hx-put=url("/previous") class="transition-colors cursor-pointer">
             <Backward />
         </button>
     }
@@ -229,7 +230,7 @@ pub(crate) fn player_state(status: Status) -> impl IntoView {
         <div
             hx-trigger="status"
             data-sse="status"
-            hx-get="/status"
+            hx-get=url("/status")
             hx-swap="innerHTML"
             hx-target="this"
             class="flex justify-center"
@@ -252,34 +253,46 @@ fn now_playing(
         .as_ref()
         .and_then(|track| track.artist_name.clone());
     let artist_id = current_track.as_ref().and_then(|track| track.artist_id);
+    let track_link = current_track
+        .as_ref()
+        .map(|track| url(&format!("/track/{}", track.id)));
 
     let current_position = tracklist.current_position();
 
     let (entity_title, entity_link) = match tracklist.list_type() {
         TracklistType::Album(tracklist) => (
             Some(tracklist.title.clone()),
-            Some(format!("/album/{}", tracklist.id)),
+            Some(url(&format!("/album/{}", tracklist.id))),
         ),
         TracklistType::Playlist(tracklist) => (
             Some(tracklist.title.clone()),
-            Some(format!("/playlist/{}", tracklist.id)),
+            Some(url(&format!("/playlist/{}", tracklist.id))),
         ),
-        TracklistType::TopTracks(tracklist) => (None, Some(format!("/artist/{}", tracklist.id))),
+        TracklistType::TopTracks(tracklist) => {
+            (None, Some(url(&format!("/artist/{}", tracklist.id))))
+        }
         TracklistType::Track(tracklist) => (
             current_track
                 .as_ref()
                 .and_then(|track| track.album_title.clone()),
-            tracklist.album_id.as_ref().map(|id| format!("/album/{id}")),
+            tracklist
+                .album_id
+                .as_ref()
+                .map(|id| url(&format!("/album/{id}"))),
         ),
         TracklistType::None => (None, None),
     };
 
+    let classical_track = current_track.as_ref().filter(|track| track.is_classical());
+    let composer = classical_track.and_then(|track| track.composer.clone());
+    let work = classical_track.and_then(|track| track.work.clone());
+
     let (title, artist_link, duration_seconds, explicit, hires_available) = current_track
         .as_ref()
         .map_or((String::default(), None, None, false, false), |track| {
             (
-                track.title.clone(),
-                artist_id.map(|id| format!("/artist/{id}")),
+                track.movement_title().to_string(),
+                artist_id.map(|id| url(&format!("/artist/{id}"))),
                 Some(track.duration_seconds),
                 track.explicit,
                 track.hires_available,
@@ -292,7 +305,7 @@ fn now_playing(
         <div
             class="flex flex-col gap-4 p-4 mx-auto touch-none"
             style="max-width: calc(600px + 2rem); height: calc(100% - 4rem - env(safe-area-inset-bottom))"
-            hx-get="/now-playing"
+            hx-get=url("/now-playing")
             hx-trigger="tracklist"
             data-sse="tracklist"
             hx-swap="outerHTML"
@@ -321,13 +334,18 @@ fn now_playing(
                     </div>
                 </div>
 
+                {composer.map(|composer| html! { <span class="truncate">{composer}</span> })}
+
                 <a href=artist_link class="text-gray-400 truncate w-fit">
                     {artist_name}
                 </a>
 
                 <div class="flex flex-col gap-y-4 w-full">
+                    {work.map(|work| html! { <span class="text-gray-400 truncate">{work}</span> })}
                     <div class="flex gap-2 justify-between items-center">
-                        <span class="text-lg truncate">{title}</span>
+                        <a href=track_link class="text-lg truncate">
+                            {title}
+                        </a>
                         <Info explicit=explicit hires_available=hires_available />
                     </div>
 
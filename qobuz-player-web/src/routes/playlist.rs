@@ -2,24 +2,28 @@ use std::sync::Arc;
 
 use axum::{
     Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, put},
 };
 use leptos::prelude::*;
 use qobuz_player_models::{Playlist, Track};
+use serde::Deserialize;
 
 use crate::{
     AppState, ResponseResult,
+    base_path::url,
     components::{
         ButtonGroup, ToggleFavorite, button_class,
-        list::{ListTracks, TrackNumberDisplay},
+        list::{ListTracks, TrackActions, TrackNumberDisplay},
         parse_duration,
     },
     html,
     icons::{Link, Play},
     ok_or_broadcast, ok_or_error_component,
     page::Page,
+    routes::zones::SelectedZone,
     view::{LazyLoadComponent, render},
 };
 
@@ -28,6 +32,7 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
         .route("/playlist/{id}", get(index))
         .route("/playlist/{id}/content", get(content))
         .route("/playlist/{id}/tracks", get(tracks_partial))
+        .route("/playlist/{id}/tracks/more", get(more_tracks_partial))
         .route("/playlist/{id}/set-favorite", put(set_favorite))
         .route("/playlist/{id}/unset-favorite", put(unset_favorite))
         .route("/playlist/{id}/play", put(play))
@@ -38,13 +43,28 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
 
 async fn play_track(
     State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
     Path((id, track_position)): Path<(u32, u32)>,
-) -> impl IntoResponse {
-    state.controls.play_playlist(id, track_position, false);
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_playlist(id, track_position, false).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
-async fn play(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
-    state.controls.play_playlist(id, 0, false);
+async fn play(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Path(id): Path<u32>,
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_playlist(id, 0, false).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn link(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
@@ -59,8 +79,17 @@ async fn link(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl I
     .await;
 }
 
-async fn shuffle(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
-    state.controls.play_playlist(id, 0, true);
+async fn shuffle(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Path(id): Path<u32>,
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_playlist(id, 0, true).await,
+    )?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn set_favorite(
@@ -86,7 +115,7 @@ async fn unset_favorite(
 }
 
 async fn index(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
-    let url = format!("/playlist/{id}/content");
+    let url = url(&format!("/playlist/{id}/content"));
 
     let current_status = state.status_receiver.borrow();
     let tracklist = state.tracklist_receiver.borrow();
@@ -114,17 +143,102 @@ async fn content(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> Res
     }))
 }
 
-async fn tracks_partial(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ResponseResult {
-    let playlist = ok_or_error_component(state.client.playlist(id).await)?;
+/// Number of tracks rendered at once, the rest are loaded when scrolled into view.
+const TRACKS_PER_PAGE: u32 = 100;
+
+#[derive(Deserialize, Clone)]
+struct TracksParameters {
+    offset: Option<u32>,
+}
+
+async fn tracks_partial(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<TracksParameters>,
+) -> ResponseResult {
+    let offset = parameters.offset.unwrap_or(0);
+    let tracks = ok_or_error_component(
+        state
+            .client
+            .playlist_tracks(id, offset, TRACKS_PER_PAGE)
+            .await,
+    )?;
+    let currently_playing = state.tracklist_receiver.borrow().currently_playing();
+
+    Ok(render(html! {
+        <Tracks
+            tracks=tracks
+            playlist_id=id
+            offset=offset
+            now_playing_id=currently_playing
+        />
+    }))
+}
+
+async fn more_tracks_partial(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(parameters): Query<TracksParameters>,
+) -> ResponseResult {
+    let offset = parameters.offset.unwrap_or(0);
+    let tracks = ok_or_error_component(
+        state
+            .client
+            .playlist_tracks(id, offset, TRACKS_PER_PAGE)
+            .await,
+    )?;
     let currently_playing = state.tracklist_receiver.borrow().currently_playing();
+    // A short page is the last one.
+    let has_more = tracks.len() == TRACKS_PER_PAGE as usize;
+
+    Ok(render(html! {
+        <TrackPages
+            tracks=tracks
+            has_more=has_more
+            playlist_id=id
+            offset=offset
+            now_playing_id=currently_playing
+        />
+    }))
+}
 
-    Ok(render(
-        html! { <Tracks tracks=playlist.tracks playlist_id=playlist.id now_playing_id=currently_playing /> },
-    ))
+/// One page of tracks followed by a placeholder loading the next page once revealed.
+#[component]
+fn track_pages(
+    now_playing_id: Option<u32>,
+    tracks: Vec<Track>,
+    has_more: bool,
+    playlist_id: u32,
+    offset: u32,
+) -> impl IntoView {
+    let next_offset = offset + TRACKS_PER_PAGE;
+
+    let load_more = has_more.then(|| {
+        html! {
+            <div
+                class="p-4 text-center text-gray-400"
+                hx-get=url(&format!("/playlist/{playlist_id}/tracks/more?offset={next_offset}"))
+                hx-trigger="revealed"
+                hx-swap="outerHTML"
+            >
+                Loading...
+            </div>
+        }
+    });
+
+    html! {
+        <Tracks now_playing_id=now_playing_id tracks=tracks playlist_id=playlist_id offset=offset />
+        {load_more}
+    }
 }
 
 #[component]
-fn tracks(now_playing_id: Option<u32>, tracks: Vec<Track>, playlist_id: u32) -> impl IntoView {
+fn tracks(
+    now_playing_id: Option<u32>,
+    tracks: Vec<Track>,
+    playlist_id: u32,
+    offset: u32,
+) -> impl IntoView {
     html! {
         <div
             class="w-full"
@@ -132,14 +246,17 @@ fn tracks(now_playing_id: Option<u32>, tracks: Vec<Track>, playlist_id: u32) ->
             hx-target="this"
             data-sse="tracklist"
             hx-swap="morph:outerHTML"
-            hx-get=format!("/playlist/{}/tracks", playlist_id)
+            hx-get=url(&format!("/playlist/{}/tracks?offset={}", playlist_id, offset))
         >
             <ListTracks
                 track_number_display=TrackNumberDisplay::Cover
                 tracks=tracks
                 show_artist=true
                 dim_played=false
-                api_call=move |index: usize| format!("/playlist/{playlist_id}/play/{index}")
+                actions=TrackActions::AddToQueue
+                api_call=move |index: usize| {
+                    url(&format!("/playlist/{}/play/{}", playlist_id, offset as usize + index))
+                }
                 now_playing_id=now_playing_id
             />
         </div>
@@ -154,6 +271,12 @@ fn playlist(
     rfid: bool,
 ) -> impl IntoView {
     let duration = parse_duration(playlist.duration_seconds);
+    let has_more = playlist.tracks.len() > TRACKS_PER_PAGE as usize;
+    let first_page = playlist
+        .tracks
+        .into_iter()
+        .take(TRACKS_PER_PAGE as usize)
+        .collect::<Vec<_>>();
 
     html! {
         <div class="flex flex-wrap gap-4 justify-center items-end w-full p-safe-or-4 *:max-w-sm">
@@ -229,10 +352,12 @@ fn playlist(
         </div>
         <div class="flex flex-col gap-4 w-full">
             <div class="sm:p-4">
-                <Tracks
+                <TrackPages
                     now_playing_id=now_playing_id
-                    tracks=playlist.tracks
+                    tracks=first_page
+                    has_more=has_more
                     playlist_id=playlist.id
+                    offset=0
                 />
             </div>
         </div>
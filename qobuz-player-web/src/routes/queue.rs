@@ -1,19 +1,26 @@
 use std::sync::Arc;
 
 use axum::{
-    Router,
+    Form, Router,
     extract::{Path, State},
+    http::StatusCode,
     response::IntoResponse,
-    routing::{get, put},
+    routing::{delete, get, post, put},
 };
 use leptos::{IntoView, component, prelude::*};
-use qobuz_player_controls::tracklist::{Tracklist, TracklistType};
+use qobuz_player_controls::{
+    notification::Notification,
+    tracklist::{Tracklist, TracklistType},
+};
+use serde::Deserialize;
 
 use crate::{
-    AppState,
-    components::list::{List, ListTracks, TrackNumberDisplay},
-    html,
+    AppState, ResponseResult,
+    base_path::url,
+    components::list::{List, ListTracks, TrackActions, TrackNumberDisplay},
+    html, ok_or_broadcast,
     page::Page,
+    routes::zones::SelectedZone,
     view::render,
 };
 
@@ -22,6 +29,12 @@ pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
         .route("/queue", get(index))
         .route("/queue/list", get(queue_partial))
         .route("/queue/skip-to/{track_number}", put(skip_to))
+        .route("/queue/add/{track_id}", put(add))
+        .route("/queue/{entry}", delete(remove))
+        .route("/queue/{entry}/play-next", put(play_next))
+        .route("/queue/move", put(move_track))
+        .route("/queue/clear-upcoming", put(clear_upcoming))
+        .route("/queue/save", post(save))
 }
 
 async fn skip_to(
@@ -31,6 +44,106 @@ async fn skip_to(
     state.controls.skip_to_position(track_number, true);
 }
 
+async fn add(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Path(track_id): Path<u32>,
+) -> ResponseResult {
+    // Queueing on an empty local queue starts playback, which is feedback enough.
+    let starts_playback =
+        selected.zone.is_local() && state.tracklist_receiver.borrow().queue().is_empty();
+    ok_or_broadcast(&state.broadcast, selected.zone.add_to_queue(track_id).await)?;
+
+    if !starts_playback {
+        let message = match selected.zone.is_local() {
+            true => "Added to queue".to_string(),
+            false => format!("Added to queue on {}", selected.zone.name()),
+        };
+        state.broadcast.send(Notification::Info(message));
+    }
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn remove(State(state): State<Arc<AppState>>, Path(entry): Path<u64>) -> impl IntoResponse {
+    state.controls.remove_from_queue(entry);
+}
+
+async fn play_next(
+    State(state): State<Arc<AppState>>,
+    Path(entry): Path<u64>,
+) -> impl IntoResponse {
+    let next_position = {
+        let tracklist = state.tracklist_receiver.borrow();
+        tracklist
+            .entry_position(entry)
+            .map(|position| tracklist.play_next_position(position))
+    };
+
+    if let Some(next_position) = next_position {
+        state.controls.move_in_queue(entry, next_position);
+    }
+}
+
+#[derive(Deserialize)]
+struct MoveParameters {
+    entry: u64,
+    to: u32,
+}
+
+async fn move_track(
+    State(state): State<Arc<AppState>>,
+    Form(parameters): Form<MoveParameters>,
+) -> impl IntoResponse {
+    state
+        .controls
+        .move_in_queue(parameters.entry, parameters.to);
+}
+
+async fn clear_upcoming(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.controls.clear_upcoming();
+}
+
+#[derive(Deserialize)]
+struct SaveParameters {
+    name: String,
+}
+
+async fn save(
+    State(state): State<Arc<AppState>>,
+    Form(parameters): Form<SaveParameters>,
+) -> ResponseResult {
+    let track_ids = state
+        .tracklist_receiver
+        .borrow()
+        .queue()
+        .iter()
+        .map(|track| track.id)
+        .collect::<Vec<_>>();
+
+    if track_ids.is_empty() {
+        state
+            .broadcast
+            .send(Notification::Warning("The queue is empty".to_string()));
+        return Ok(render(html! { <SaveQueueForm /> }));
+    }
+
+    ok_or_broadcast(
+        &state.broadcast,
+        state
+            .client
+            .create_playlist(&parameters.name, &track_ids)
+            .await,
+    )?;
+
+    state.broadcast.send(Notification::Success(format!(
+        "Saved queue as {}",
+        parameters.name
+    )));
+
+    Ok(render(html! { <SaveQueueForm /> }))
+}
+
 async fn index(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let current_status = state.status_receiver.borrow();
     let tracklist = state.tracklist_receiver.borrow();
@@ -48,37 +161,56 @@ fn queue(tracklist: Tracklist) -> impl IntoView {
     let (entity_title, entity_link) = match tracklist.list_type() {
         TracklistType::Album(tracklist) => (
             tracklist.title.clone(),
-            Some(format!("/album/{}", tracklist.id)),
+            Some(url(&format!("/album/{}", tracklist.id))),
         ),
         TracklistType::Playlist(tracklist) => (
             tracklist.title.clone(),
-            Some(format!("/playlist/{}", tracklist.id)),
+            Some(url(&format!("/playlist/{}", tracklist.id))),
         ),
         TracklistType::TopTracks(tracklist) => (
             tracklist.artist_name.clone(),
-            Some(format!("/artist/{}", tracklist.id)),
+            Some(url(&format!("/artist/{}", tracklist.id))),
         ),
         TracklistType::Track(tracklist) => (
             tracklist.track_title.clone(),
-            tracklist.album_id.as_ref().map(|id| format!("/album/{id}")),
+            tracklist
+                .album_id
+                .as_ref()
+                .map(|id| url(&format!("/album/{id}"))),
         ),
         TracklistType::None => ("Empty queue".to_string(), None),
     };
 
     html! {
         <div
-            hx-get="/queue/list"
+            hx-get=url("/queue/list")
             hx-trigger="tracklist"
             data-sse="tracklist"
             hx-target="#queue-list"
         >
             <div class="flex flex-col gap-4 p-4">
-                <div class="sticky top-0 pb-2 pt-safe bg-black/20 backdrop-blur">
+                <div class="flex sticky top-0 flex-col gap-2 pb-2 pt-safe bg-black/20 backdrop-blur">
                     <a hx-target="unset" href=entity_link class="text-2xl">
                         {entity_title}
                     </a>
+                    <div class="flex gap-2 items-center">
+                        <SaveQueueForm />
+                        <button
+                            class="py-2 px-4 bg-gray-800 rounded cursor-pointer whitespace-nowrap"
+                            hx-put=url("/queue/clear-upcoming")
+                            hx-swap="none"
+                            hx-confirm="Remove all upcoming tracks?"
+                        >
+                            Clear upcoming
+                        </button>
+                    </div>
                 </div>
 
+                <form id="queue-move" hx-put=url("/queue/move") hx-trigger="queue-move" hx-swap="none">
+                    <input type="hidden" name="entry" />
+                    <input type="hidden" name="to" />
+                </form>
+
                 <div id="queue-list">
                     <QueueList tracklist=tracklist />
                 </div>
@@ -87,6 +219,33 @@ fn queue(tracklist: Tracklist) -> impl IntoView {
     }
 }
 
+/// Saves the whole queue, played tracks included, as a new private playlist.
+#[component]
+fn save_queue_form() -> impl IntoView {
+    html! {
+        <form
+            class="flex gap-2 items-center w-full"
+            hx-post=url("/queue/save")
+            hx-target="this"
+            hx-swap="outerHTML"
+        >
+            <input
+                name="name"
+                required
+                class="p-2 w-full text-black bg-white rounded"
+                autocomplete="off"
+                placeholder="Playlist name"
+            />
+            <button
+                type="submit"
+                class="py-2 px-4 bg-blue-800 rounded cursor-pointer whitespace-nowrap"
+            >
+                Save as playlist
+            </button>
+        </form>
+    }
+}
+
 async fn queue_partial(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let tracklist = state.tracklist_receiver.borrow().clone();
     render(html! { <QueueList tracklist=tracklist /> })
@@ -96,6 +255,7 @@ async fn queue_partial(State(state): State<Arc<AppState>>) -> impl IntoResponse
 fn queue_list(tracklist: Tracklist) -> impl IntoView {
     let now_playing_id = tracklist.currently_playing();
     let tracks = tracklist.queue().to_vec();
+    let entries = tracklist.entries().to_vec();
 
     html! {
         <List>
@@ -104,7 +264,9 @@ fn queue_list(tracklist: Tracklist) -> impl IntoView {
                 tracks=tracks
                 show_artist=true
                 dim_played=true
-                api_call=|index: usize| format!("/queue/skip-to/{index}")
+                actions=TrackActions::Queue
+                queue_entries=entries
+                api_call=|index: usize| url(&format!("/queue/skip-to/{index}"))
                 now_playing_id=now_playing_id
             />
         </List>
@@ -3,11 +3,12 @@ use std::sync::Arc;
 use axum::{
     Form, Router,
     extract::{Path, Query, State},
+    http::StatusCode,
     response::IntoResponse,
-    routing::{get, put},
+    routing::{get, post, put},
 };
 use leptos::{component, prelude::*};
-use qobuz_player_models::SearchResults;
+use qobuz_player_models::{Album, Artist, Playlist, SearchFilter, SearchPage, Suggestion};
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone, PartialEq)]
@@ -19,35 +20,160 @@ pub(crate) enum Tab {
     Tracks,
 }
 
+impl Tab {
+    fn path(&self) -> &'static str {
+        match self {
+            Tab::Albums => "albums",
+            Tab::Artists => "artists",
+            Tab::Playlists => "playlists",
+            Tab::Tracks => "tracks",
+        }
+    }
+}
+
 use crate::{
     AppState, ResponseResult,
+    base_path::url,
     components::{
         Info,
-        list::{List, ListAlbums, ListArtists, ListItem, ListPlaylists},
+        list::{AddToQueueButton, List, ListAlbums, ListArtists, ListItem, ListPlaylists},
     },
     html,
     icons::MagnifyingGlass,
     ok_or_broadcast,
     page::Page,
+    routes::zones::SelectedZone,
     view::render,
 };
 
 pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
     Router::new()
         .route("/search/{tab}", get(index).post(search))
+        .route("/search/{tab}/more", get(more))
+        .route("/search/suggestions", get(suggestions_partial))
         .route("/play-track/{track_id}", put(play_track))
+        .route("/open", post(open_link))
 }
 
 async fn play_track(
     State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
     Path(track_id): Path<u32>,
-) -> impl IntoResponse {
-    state.controls.play_track(track_id);
+) -> ResponseResult {
+    ok_or_broadcast(&state.broadcast, selected.zone.play_track(track_id).await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[derive(Deserialize)]
+struct OpenParameters {
+    url: String,
 }
 
-#[derive(Deserialize, Clone)]
+async fn open_link(
+    State(state): State<Arc<AppState>>,
+    selected: SelectedZone,
+    Form(parameters): Form<OpenParameters>,
+) -> ResponseResult {
+    ok_or_broadcast(
+        &state.broadcast,
+        selected.zone.play_url(&parameters.url).await,
+    )?;
+
+    Ok(render(html! { <PasteLink /> }))
+}
+
+#[derive(Deserialize, Clone, Default)]
 struct SearchParameters {
     query: Option<String>,
+    offset: Option<u32>,
+    #[serde(default)]
+    hires_only: bool,
+    #[serde(default)]
+    hide_explicit: bool,
+}
+
+impl SearchParameters {
+    fn query(&self) -> Option<String> {
+        self.query.clone().filter(|query| !query.is_empty())
+    }
+
+    fn filter(&self) -> SearchFilter {
+        SearchFilter {
+            hires_only: self.hires_only,
+            hide_explicit: self.hide_explicit,
+        }
+    }
+}
+
+/// Results of the active tab only.
+enum SearchResults {
+    Albums(SearchPage<Album>),
+    Artists(SearchPage<Artist>),
+    Playlists(SearchPage<Playlist>),
+    Tracks(SearchPage<qobuz_player_models::Track>),
+}
+
+impl SearchResults {
+    fn empty(tab: &Tab) -> Self {
+        match tab {
+            Tab::Albums => Self::Albums(Default::default()),
+            Tab::Artists => Self::Artists(Default::default()),
+            Tab::Playlists => Self::Playlists(Default::default()),
+            Tab::Tracks => Self::Tracks(Default::default()),
+        }
+    }
+
+    fn next_offset(&self) -> Option<u32> {
+        match self {
+            Self::Albums(page) => page.next_offset,
+            Self::Artists(page) => page.next_offset,
+            Self::Playlists(page) => page.next_offset,
+            Self::Tracks(page) => page.next_offset,
+        }
+    }
+}
+
+async fn search_tab(
+    state: &AppState,
+    tab: &Tab,
+    parameters: &SearchParameters,
+) -> qobuz_player_controls::Result<SearchResults> {
+    let Some(query) = parameters.query() else {
+        return Ok(SearchResults::empty(tab));
+    };
+
+    let filter = parameters.filter();
+    let offset = parameters.offset.unwrap_or(0);
+    let client = &state.client;
+
+    Ok(match tab {
+        Tab::Albums => SearchResults::Albums(client.search_albums(query, filter, offset).await?),
+        Tab::Artists => SearchResults::Artists(client.search_artists(query, offset).await?),
+        Tab::Playlists => SearchResults::Playlists(client.search_playlists(query, offset).await?),
+        Tab::Tracks => SearchResults::Tracks(client.search_tracks(query, filter, offset).await?),
+    })
+}
+
+/// Link to a tab keeping query and filters, or to the page at `offset` of it.
+fn search_url(tab: &Tab, parameters: &SearchParameters, offset: Option<u32>) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+
+    if let Some(offset) = offset {
+        query.append_pair("offset", &offset.to_string());
+    }
+    query.append_pair("query", parameters.query.as_deref().unwrap_or_default());
+    if parameters.hires_only {
+        query.append_pair("hires_only", "true");
+    }
+    if parameters.hide_explicit {
+        query.append_pair("hide_explicit", "true");
+    }
+
+    match offset {
+        Some(_) => url(&format!("/search/{}/more?{}", tab.path(), query.finish())),
+        None => url(&format!("/search/{}?{}", tab.path(), query.finish())),
+    }
 }
 
 async fn index(
@@ -55,20 +181,17 @@ async fn index(
     Path(tab): Path<Tab>,
     Query(parameters): Query<SearchParameters>,
 ) -> ResponseResult {
-    let query = parameters
-        .query
-        .and_then(|s| if s.is_empty() { None } else { Some(s) });
-    let search_results = match query {
-        Some(query) => ok_or_broadcast(&state.broadcast, state.client.search(query).await)?,
-        None => SearchResults::default(),
-    };
+    let search_results = ok_or_broadcast(
+        &state.broadcast,
+        search_tab(&state, &tab, &parameters).await,
+    )?;
 
     let current_status = state.status_receiver.borrow();
     let tracklist = state.tracklist_receiver.borrow();
 
     Ok(render(html! {
         <Page active_page=Page::Search current_status=*current_status tracklist=&tracklist>
-            <Search search_results=search_results tab=tab />
+            <Search search_results=search_results tab=tab parameters=parameters />
         </Page>
     }))
 }
@@ -78,46 +201,146 @@ async fn search(
     Path(tab): Path<Tab>,
     Form(parameters): Form<SearchParameters>,
 ) -> ResponseResult {
-    let query = parameters
-        .query
-        .and_then(|s| if s.is_empty() { None } else { Some(s) });
-    let search_results = match query.clone() {
-        Some(query) => ok_or_broadcast(&state.broadcast, state.client.search(query).await)?,
-        None => SearchResults::default(),
-    };
+    let search_results = ok_or_broadcast(
+        &state.broadcast,
+        search_tab(&state, &tab, &parameters).await,
+    )?;
 
     Ok(render(html! {
-        <SearchPartial search_results=search_results tab=tab.clone() />
+        <SearchPartial search_results=search_results tab=tab.clone() parameters=parameters.clone() />
 
-        {html! { <TabBar query=query.unwrap_or_default() tab=tab /> }.attr("hx-swap-oob", "true")}
+        {html! { <TabBar tab=tab parameters=parameters /> }.attr("hx-swap-oob", "true")}
     }))
 }
 
+async fn more(
+    State(state): State<Arc<AppState>>,
+    Path(tab): Path<Tab>,
+    Query(parameters): Query<SearchParameters>,
+) -> ResponseResult {
+    let search_results = ok_or_broadcast(
+        &state.broadcast,
+        search_tab(&state, &tab, &parameters).await,
+    )?;
+
+    Ok(render(
+        html! { <SearchPartial search_results=search_results tab=tab parameters=parameters /> },
+    ))
+}
+
+async fn suggestions_partial(
+    State(state): State<Arc<AppState>>,
+    Query(parameters): Query<SearchParameters>,
+) -> ResponseResult {
+    let query = parameters.query.unwrap_or_default();
+
+    let suggestions = ok_or_broadcast(&state.broadcast, state.client.autocomplete(query).await)?;
+
+    Ok(render(html! { <Suggestions suggestions=suggestions /> }))
+}
+
 #[component]
-fn search_partial(search_results: SearchResults, tab: Tab) -> impl IntoView {
-    match tab {
-        Tab::Albums => html! {
-            <ListAlbums
-                albums=search_results.albums
-                sort=crate::components::list::AlbumSort::Default
-            />
+fn suggestions(suggestions: Vec<Suggestion>) -> impl IntoView {
+    (!suggestions.is_empty()).then(|| {
+        let suggestions = suggestions
+            .into_iter()
+            .map(|suggestion| {
+                let (kind, detail) = match &suggestion {
+                    Suggestion::Artist { .. } => ("Artist", None),
+                    Suggestion::Album { artist, .. } => ("Album", Some(artist.clone())),
+                    Suggestion::Track { artist, .. } => ("Track", artist.clone()),
+                    Suggestion::Playlist { .. } => ("Playlist", None),
+                };
+
+                let content = html! {
+                    <span class="w-16 text-sm text-gray-400 shrink-0">{kind}</span>
+                    <span class="truncate">{suggestion.text().to_string()}</span>
+                    <span class="text-gray-400 truncate">{detail}</span>
+                };
+
+                match suggestion {
+                    Suggestion::Track { id, .. } => html! {
+                        <button
+                            class="flex gap-2 items-center py-2 px-4 w-full text-left cursor-pointer hover:bg-gray-800"
+                            hx-put=url(&format!("/play-track/{id}"))
+                            hx-swap="none"
+                        >
+                            {content}
+                        </button>
+                    }
+                    .into_any(),
+                    Suggestion::Artist { id, .. } => {
+                        suggestion_link(url(&format!("/artist/{id}")), content.into_any())
+                    }
+                    Suggestion::Album { id, .. } => {
+                        suggestion_link(url(&format!("/album/{id}")), content.into_any())
+                    }
+                    Suggestion::Playlist { id, .. } => {
+                        suggestion_link(url(&format!("/playlist/{id}")), content.into_any())
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        html! {
+            <div class="flex absolute right-0 left-0 top-full z-10 flex-col mt-1 bg-gray-900 rounded shadow-lg">
+                {suggestions}
+            </div>
+        }
+    })
+}
+
+fn suggestion_link(href: String, content: AnyView) -> AnyView {
+    html! {
+        <a class="flex gap-2 items-center py-2 px-4 w-full hover:bg-gray-800" href=href>
+            {content}
+        </a>
+    }
+    .into_any()
+}
+
+/// Results of one page followed by a button loading the next page in its place.
+#[component]
+fn search_partial(
+    search_results: SearchResults,
+    tab: Tab,
+    parameters: SearchParameters,
+) -> impl IntoView {
+    let load_more = search_results.next_offset().map(|offset| {
+        html! {
+            <button
+                class="p-4 w-full text-center text-gray-400 cursor-pointer"
+                hx-get=search_url(&tab, &parameters, Some(offset))
+                hx-target="this"
+                hx-swap="outerHTML"
+            >
+                More
+            </button>
+        }
+    });
+
+    let results = match search_results {
+        SearchResults::Albums(page) => html! {
+            <ListAlbums albums=page.items sort=crate::components::list::AlbumSort::Default />
         }
         .into_any(),
-        Tab::Artists => html! {
-            <ListArtists
-                artists=search_results.artists
-                sort=crate::components::list::ArtistSort::Default
-            />
+        SearchResults::Artists(page) => html! {
+            <ListArtists artists=page.items sort=crate::components::list::ArtistSort::Default />
         }
         .into_any(),
-        Tab::Playlists => html! {
+        SearchResults::Playlists(page) => html! {
             <ListPlaylists
-                playlists=search_results.playlists
+                playlists=page.items
                 sort=crate::components::list::PlaylistSort::Default
             />
         }
         .into_any(),
-        Tab::Tracks => html! { <ListTracks tracks=search_results.tracks /> }.into_any(),
+        SearchResults::Tracks(page) => html! { <ListTracks tracks=page.items /> }.into_any(),
+    };
+
+    html! {
+        {results}
+        {load_more}
     }
 }
 
@@ -141,123 +364,177 @@ fn list_tracks(tracks: Vec<qobuz_player_models::Track>) -> impl IntoView {
 
 #[component]
 fn track(track: qobuz_player_models::Track) -> impl IntoView {
+    let track_id = track.id;
+
     html! {
-        <button
-            class="flex gap-4 items-center w-full cursor-pointer"
-            hx-put=format!("/play-track/{}", track.id)
-            hx-swap="none"
-        >
-            <img
-                class="inline text-sm text-gray-500 bg-gray-800 rounded-md aspect-square size-12"
-                alt=track.title.clone()
-                src=track.image_thumbnail
-            />
+        <div class="flex gap-2 items-center">
+            <button
+                class="flex gap-4 items-center w-full cursor-pointer"
+                hx-put=url(&format!("/play-track/{}", track.id))
+                hx-swap="none"
+            >
+                <img
+                    class="inline text-sm text-gray-500 bg-gray-800 rounded-md aspect-square size-12"
+                    alt=track.title.clone()
+                    src=track.image_thumbnail
+                />
 
-            <div class="overflow-hidden w-full">
-                <div class="flex justify-between items-center">
-                    <h3 class="text-lg truncate">{track.title}</h3>
-                    <Info explicit=track.explicit hires_available=track.hires_available />
-                </div>
+                <div class="overflow-hidden w-full">
+                    <div class="flex justify-between items-center">
+                        <h3 class="text-lg truncate">{track.title}</h3>
+                        <Info explicit=track.explicit hires_available=track.hires_available />
+                    </div>
 
-                <h4 class="flex gap-2 text-left text-gray-400">
-                    {track
-                        .artist_name
-                        .map(|artist_name| {
-                            html! { <span class="truncate">{artist_name}</span> }
-                        })}
-                </h4>
-            </div>
-        </button>
+                    <h4 class="flex gap-2 text-left text-gray-400">
+                        {track
+                            .artist_name
+                            .map(|artist_name| {
+                                html! { <span class="truncate">{artist_name}</span> }
+                            })}
+                    </h4>
+                </div>
+            </button>
+            <AddToQueueButton track_id=track_id />
+        </div>
     }
 }
 
 #[component]
-fn tab_bar(query: String, tab: Tab) -> impl IntoView {
-    html! {
-        <div
-            id="tabs"
-            class="flex justify-between *:rounded-full *:px-2 *:py-1 *:transition-colors"
-        >
-            {html! {
-                <a
-                    href=format!("albums?query={}", query)
-                    class=(tab == Tab::Albums).then_some("bg-blue-800")
-                >
+fn tab_bar(tab: Tab, parameters: SearchParameters) -> impl IntoView {
+    let tabs = [Tab::Albums, Tab::Artists, Tab::Playlists, Tab::Tracks]
+        .into_iter()
+        .map(|link_tab| {
+            let label = match link_tab {
+                Tab::Albums => "Albums",
+                Tab::Artists => "Artists",
+                Tab::Playlists => "Playlists",
+                Tab::Tracks => "Tracks",
+            };
 
-                    Albums
-                </a>
-            }
-                .attr("preload", "mouseover")
-                .attr("preload-images", "true")}
-            {html! {
+            html! {
                 <a
-                    href=format!("artists?query={}", query)
-                    class=(tab == Tab::Artists).then_some("bg-blue-800")
+                    href=search_url(&link_tab, &parameters, None)
+                    class=(tab == link_tab).then_some("bg-blue-800")
                 >
-                    Artists
+                    {label}
                 </a>
             }
-                .attr("preload", "mouseover")
-                .attr("preload-images", "true")}
-            {html! {
-                <a
-                    href=format!("playlists?query={}", query)
-                    class=(tab == Tab::Playlists).then_some("bg-blue-800")
-                >
-                    Playlists
-                </a>
-            }
-                .attr("preload", "mouseover")
-                .attr("preload-images", "true")}
-            {html! {
-                <a
-                    href=format!("tracks?query={}", query)
-                    class=(tab == Tab::Tracks).then_some("bg-blue-800")
-                >
-                    Tracks
-                </a>
-            }
-                .attr("preload", "mouseover")
-                .attr("preload-images", "true")}
+            .attr("preload", "mouseover")
+            .attr("preload-images", "true")
+        })
+        .collect::<Vec<_>>();
+
+    html! {
+        <div
+            id="tabs"
+            class="flex justify-between *:rounded-full *:px-2 *:py-1 *:transition-colors"
+        >
+            {tabs}
+        </div>
+    }
+}
 
+#[component]
+fn search_filters(parameters: SearchParameters) -> impl IntoView {
+    html! {
+        <div id="search-filters" class="flex gap-4 text-sm text-gray-400">
+            <label class="flex gap-2 items-center">
+                <input
+                    type="checkbox"
+                    name="hires_only"
+                    value="true"
+                    checked=parameters.hires_only
+                    hx-post=""
+                    hx-include="#query, #search-filters"
+                    hx-target="#search-results"
+                    hx-swap="morph:innerHTML"
+                />
+                Hi-res only
+            </label>
+            <label class="flex gap-2 items-center">
+                <input
+                    type="checkbox"
+                    name="hide_explicit"
+                    value="true"
+                    checked=parameters.hide_explicit
+                    hx-post=""
+                    hx-include="#query, #search-filters"
+                    hx-target="#search-results"
+                    hx-swap="morph:innerHTML"
+                />
+                Hide explicit
+            </label>
         </div>
     }
 }
 
+/// Plays an album, artist, playlist or track from a pasted Qobuz share link.
+#[component]
+fn paste_link() -> impl IntoView {
+    html! {
+        <form id="paste-link" class="flex gap-2 items-center" hx-post=url("/open") hx-swap="outerHTML">
+            <input
+                name="url"
+                type="url"
+                required
+                class="p-2 w-full text-black bg-white rounded"
+                autocomplete="off"
+                placeholder="Paste a Qobuz link"
+            />
+            <button type="submit" class="py-2 px-4 bg-blue-800 rounded cursor-pointer">
+                Play
+            </button>
+        </form>
+    }
+}
+
 #[component]
-fn search(search_results: SearchResults, tab: Tab) -> impl IntoView {
-    let query = search_results.query.clone();
+fn search(search_results: SearchResults, tab: Tab, parameters: SearchParameters) -> impl IntoView {
     html! {
         <div class="flex flex-col">
             <div class="flex sticky top-0 flex-col flex-grow gap-4 pb-2 max-h-full pt-safe-or-4 bg-black/80 backdrop-blur">
                 <div class="flex flex-row gap-4 items-center" id="search-form">
-                    <input
-                        id="query"
-                        name="query"
-                        class="p-2 w-full text-black bg-white rounded"
-                        autocapitalize="off"
-                        autocomplete="off"
-                        autocorrect="off"
-                        placeholder="Search"
-                        spellcheck="false"
-                        type="search"
-                        oninput="setSearchQuery(this.value)"
-                        hx-post=""
-                        hx-trigger="input changed delay:500ms, keyup[key=='Enter']"
-                        hx-target="#search-results"
-                        hx-swap="morph:innerHTML"
-                    />
+                    <div class="relative w-full group">
+                        <input
+                            id="query"
+                            name="query"
+                            class="p-2 w-full text-black bg-white rounded"
+                            autocapitalize="off"
+                            autocomplete="off"
+                            autocorrect="off"
+                            placeholder="Search"
+                            spellcheck="false"
+                            type="search"
+                            oninput="setSearchQuery(this.value)"
+                            hx-post=""
+                            hx-include="#search-filters"
+                            hx-trigger="input changed delay:500ms, keyup[key=='Enter']"
+                            hx-target="#search-results"
+                            hx-swap="morph:innerHTML"
+                        />
+                        <div
+                            id="search-suggestions"
+                            class="hidden group-focus-within:block"
+                            hx-get=url("/search/suggestions")
+                            hx-include="#query"
+                            hx-trigger="input changed delay:100ms from:#query"
+                            hx-sync="this:replace"
+                            hx-swap="innerHTML"
+                        ></div>
+                    </div>
                     <span class="size-8">
                         <MagnifyingGlass />
                     </span>
                     <script>loadSearchInput()</script>
                 </div>
 
-                <TabBar query=query tab=tab.clone() />
+                <SearchFilters parameters=parameters.clone() />
+                <PasteLink />
+                <TabBar tab=tab.clone() parameters=parameters.clone() />
             </div>
 
             <div id="search-results" class="overflow-auto h-full">
-                <SearchPartial search_results=search_results tab=tab />
+                <SearchPartial search_results=search_results tab=tab parameters=parameters />
             </div>
         </div>
     }
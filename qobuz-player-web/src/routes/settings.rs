@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::{
+    Form, Router,
+    extract::{Path, State},
+    routing::{delete, get, post},
+};
+use leptos::{IntoView, component, prelude::*};
+use qobuz_player_controls::database::ApiToken;
+use serde::Deserialize;
+
+use crate::{
+    AppState, ResponseResult, base_path::url, components::button_class, html, ok_or_broadcast,
+    page::Page, view::render,
+};
+
+pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
+    Router::new()
+        .route("/settings", get(index))
+        .route("/settings/tokens", post(create_token))
+        .route("/settings/tokens/{id}", delete(revoke_token))
+}
+
+async fn index(State(state): State<Arc<AppState>>) -> ResponseResult {
+    let tokens = ok_or_broadcast(&state.broadcast, state.database.get_api_tokens().await)?;
+    let auth_enabled = state.secret_hash.is_some();
+
+    let current_status = state.status_receiver.borrow();
+    let tracklist = state.tracklist_receiver.borrow();
+
+    Ok(render(html! {
+        <Page active_page=Page::None current_status=*current_status tracklist=&tracklist>
+            <div class="flex flex-col gap-4 p-4 pt-safe-or-4">
+                <h1 class="text-2xl">Settings</h1>
+
+                <div class="flex flex-col gap-4 p-4 bg-gray-800 inset-shadow-lg">
+                    <h3 class="text-lg">Api tokens</h3>
+                    <p class="text-gray-400">
+                        "Scripts authenticate with "
+                        <code>"Authorization: Bearer <token>"</code>
+                        ". Revoke a token to lock its client out."
+                    </p>
+                    {(!auth_enabled)
+                        .then(|| {
+                            html! {
+                                <p class="text-yellow-500">
+                                    "No web secret is configured, so tokens are not required yet. Set one with "
+                                    <code>"qobuz-player config web-secret"</code>
+                                </p>
+                            }
+                        })}
+                    <ApiTokens tokens=tokens new_token=None />
+                </div>
+
+                {auth_enabled
+                    .then(|| {
+                        html! {
+                            <button class=button_class() hx-post=url("/auth/logout")>
+                                Log out
+                            </button>
+                        }
+                    })}
+            </div>
+        </Page>
+    }))
+}
+
+#[derive(Deserialize)]
+struct CreateTokenParameters {
+    name: String,
+}
+
+async fn create_token(
+    State(state): State<Arc<AppState>>,
+    Form(parameters): Form<CreateTokenParameters>,
+) -> ResponseResult {
+    let token = ok_or_broadcast(
+        &state.broadcast,
+        state.database.create_api_token(parameters.name).await,
+    )?;
+
+    token_list(&state, Some(token)).await
+}
+
+async fn revoke_token(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> ResponseResult {
+    ok_or_broadcast(&state.broadcast, state.database.revoke_api_token(id).await)?;
+
+    token_list(&state, None).await
+}
+
+async fn token_list(state: &AppState, new_token: Option<String>) -> ResponseResult {
+    let tokens = ok_or_broadcast(&state.broadcast, state.database.get_api_tokens().await)?;
+
+    Ok(render(
+        html! { <ApiTokens tokens=tokens new_token=new_token /> },
+    ))
+}
+
+/// The token list with a form to create another. A new token is only shown once, right after
+/// creating it.
+#[component]
+fn api_tokens(tokens: Vec<ApiToken>, new_token: Option<String>) -> impl IntoView {
+    let rows = tokens
+        .into_iter()
+        .map(|token| {
+            html! {
+                <li class="flex gap-4 justify-between items-center">
+                    <div class="flex flex-col">
+                        <span>{token.name}</span>
+                        <span class="text-sm text-gray-400">{token.created_at}</span>
+                    </div>
+                    <button
+                        class="py-2 px-4 bg-red-800 rounded cursor-pointer"
+                        hx-delete=url(&format!("/settings/tokens/{}", token.id))
+                        hx-confirm="Revoke this token?"
+                        hx-target="#api-tokens"
+                        hx-swap="outerHTML"
+                    >
+                        Revoke
+                    </button>
+                </li>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    html! {
+        <div id="api-tokens" class="flex flex-col gap-4">
+            {new_token
+                .map(|token| {
+                    html! {
+                        <div class="flex flex-col gap-2 p-4 bg-gray-900 rounded">
+                            <span class="text-gray-400">
+                                "Copy the new token now, it will not be shown again."
+                            </span>
+                            <code class="break-all select-all">{token}</code>
+                        </div>
+                    }
+                })}
+            <ul class="flex flex-col gap-4">{rows}</ul>
+            <form
+                class="flex gap-2 items-center"
+                hx-post=url("/settings/tokens")
+                hx-target="#api-tokens"
+                hx-swap="outerHTML"
+            >
+                <input
+                    name="name"
+                    required
+                    class="p-2 w-full text-black bg-white rounded"
+                    autocomplete="off"
+                    placeholder="Token name"
+                />
+                <button type="submit" class="py-2 px-4 bg-blue-800 rounded cursor-pointer">
+                    Create
+                </button>
+            </form>
+        </div>
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::IntoResponse,
+    routing::get,
+};
+use leptos::{IntoView, component, prelude::*};
+use qobuz_player_models::Track;
+
+use crate::{
+    AppState, ResponseResult,
+    base_path::url,
+    components::{Details, Info, parse_duration},
+    html, ok_or_error_component,
+    page::Page,
+    view::{LazyLoadComponent, render},
+};
+
+pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
+    Router::new()
+        .route("/track/{id}", get(index))
+        .route("/track/{id}/content", get(content))
+}
+
+async fn index(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> impl IntoResponse {
+    let url = url(&format!("/track/{id}/content"));
+    let current_status = state.status_receiver.borrow();
+    let tracklist = state.tracklist_receiver.borrow();
+
+    render(html! {
+        <Page active_page=Page::None current_status=*current_status tracklist=&tracklist>
+            <LazyLoadComponent url=url />
+        </Page>
+    })
+}
+
+async fn content(State(state): State<Arc<AppState>>, Path(id): Path<u32>) -> ResponseResult {
+    let track = ok_or_error_component(state.client.track(id).await)?;
+
+    Ok(render(html! { <TrackDetails track=track /> }))
+}
+
+#[component]
+fn track_details(track: Track) -> impl IntoView {
+    let duration = parse_duration(track.duration_seconds);
+    let details = track.detail_rows();
+    let credits = track
+        .credits
+        .iter()
+        .map(|credit| (credit.name.clone(), credit.roles.join(", ")))
+        .collect::<Vec<_>>();
+
+    html! {
+        <div class="flex flex-wrap gap-4 justify-center items-end w-full p-safe-or-4 *:max-w-sm">
+            {track
+                .image
+                .clone()
+                .map(|image| {
+                    html! {
+                        <img
+                            src=image
+                            alt=track.title.clone()
+                            class="object-contain rounded-lg size-full"
+                        />
+                    }
+                })}
+
+            <div class="flex flex-col gap-2 justify-center items-center w-full text-center">
+                <a
+                    href=track.artist_id.map(|id| url(&format!("/artist/{id}")))
+                    class="text-gray-400 rounded sm:text-lg"
+                >
+                    {track.artist_name.clone()}
+                </a>
+                <span class="flex gap-2 items-center text-lg sm:text-xl">
+                    {track.title.clone()}
+                    <Info explicit=track.explicit hires_available=track.hires_available />
+                </span>
+                <a
+                    href=track.album_id.as_ref().map(|id| url(&format!("/album/{id}")))
+                    class="text-gray-400 sm:text-lg"
+                >
+                    {track.album_title.clone()}
+                </a>
+                <span class="text-gray-400">{format!("{} minutes", duration.minutes)}</span>
+            </div>
+        </div>
+        <div class="flex flex-col gap-4 w-full">
+            <Details title="Track details".to_string() rows=details />
+            <Details title="Credits".to_string() rows=credits />
+        </div>
+    }
+}
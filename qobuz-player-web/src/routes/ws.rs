@@ -0,0 +1,204 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    Router,
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::IntoResponse,
+    routing::get,
+};
+use futures::{SinkExt, StreamExt, stream::SplitSink};
+use qobuz_player_controls::{
+    Status,
+    notification::Notification,
+    tracklist::{Tracklist, TracklistDiff},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, routes::api::volume_percent};
+
+pub(crate) fn routes() -> Router<Arc<AppState>> {
+    Router::new().route("/ws", get(ws_handler))
+}
+
+/// State pushed to the client as json text messages.
+///
+/// The whole tracklist is sent on connect and when a new list is started, after that only
+/// the queue entry ids and the entries that changed.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Event {
+    Position { position_ms: u64 },
+    Status { status: Status },
+    Volume { volume: u32 },
+    Tracklist { tracklist: Tracklist },
+    TracklistDiff(TracklistDiff),
+    Notification(Notification),
+    Error { message: String },
+}
+
+/// Commands accepted from the client as json text messages.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+enum Command {
+    Play,
+    Pause,
+    PlayPause,
+    Next,
+    Previous,
+    JumpForward,
+    JumpBackward,
+    Seek {
+        position_ms: u64,
+    },
+    SetVolume {
+        volume: u32,
+    },
+    SkipTo {
+        position: u32,
+    },
+    PlayAlbum {
+        id: String,
+        #[serde(default)]
+        index: u32,
+    },
+    PlayWork {
+        album_id: String,
+        #[serde(default)]
+        index: u32,
+    },
+    PlayPlaylist {
+        id: u32,
+        #[serde(default)]
+        index: u32,
+        #[serde(default)]
+        shuffle: bool,
+    },
+    PlayTrack {
+        id: u32,
+    },
+    PlayTopTracks {
+        artist_id: u32,
+        #[serde(default)]
+        index: u32,
+    },
+    PlayUrl {
+        url: String,
+    },
+}
+
+async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let mut position = state.position_receiver.clone();
+    let mut status = state.status_receiver.clone();
+    let mut volume = state.volume_receiver.clone();
+    let mut tracklist = state.tracklist_receiver.clone();
+    let mut notifications = state.broadcast.subscribe();
+
+    let mut current_tracklist = tracklist.borrow_and_update().clone();
+    let initial_events = [
+        Event::Status {
+            status: *status.borrow_and_update(),
+        },
+        Event::Position {
+            position_ms: position.borrow_and_update().as_millis() as u64,
+        },
+        Event::Volume {
+            volume: volume_percent(*volume.borrow_and_update()),
+        },
+        Event::Tracklist {
+            tracklist: current_tracklist.clone(),
+        },
+    ];
+
+    for event in initial_events {
+        if send(&mut sender, &event).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        let event = tokio::select! {
+            Ok(_) = position.changed() => Event::Position {
+                position_ms: position.borrow_and_update().as_millis() as u64,
+            },
+            Ok(_) = status.changed() => Event::Status {
+                status: *status.borrow_and_update(),
+            },
+            Ok(_) = volume.changed() => Event::Volume {
+                volume: volume_percent(*volume.borrow_and_update()),
+            },
+            Ok(_) = tracklist.changed() => {
+                let new_tracklist = tracklist.borrow_and_update().clone();
+                let event = match current_tracklist.list_type() == new_tracklist.list_type() {
+                    true => Event::TracklistDiff(current_tracklist.diff(&new_tracklist)),
+                    false => Event::Tracklist {
+                        tracklist: new_tracklist.clone(),
+                    },
+                };
+                current_tracklist = new_tracklist;
+                event
+            },
+            Ok(notification) = notifications.recv() => Event::Notification(notification),
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(text.as_str()) {
+                    Ok(command) => {
+                        match run_command(&state, command) {
+                            Ok(()) => continue,
+                            Err(message) => Event::Error { message },
+                        }
+                    }
+                    Err(err) => Event::Error {
+                        message: err.to_string(),
+                    },
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        if send(&mut sender, &event).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn run_command(state: &AppState, command: Command) -> Result<(), String> {
+    let controls = &state.controls;
+
+    match command {
+        Command::Play => controls.play(),
+        Command::Pause => controls.pause(),
+        Command::PlayPause => controls.play_pause(),
+        Command::Next => controls.next(),
+        Command::Previous => controls.previous(),
+        Command::JumpForward => controls.jump_forward(),
+        Command::JumpBackward => controls.jump_backward(),
+        Command::Seek { position_ms } => controls.seek(Duration::from_millis(position_ms)),
+        Command::SetVolume { volume } => controls.set_volume(volume.min(100) as f32 / 100.0),
+        Command::SkipTo { position } => controls.skip_to_position(position, true),
+        Command::PlayAlbum { id, index } => controls.play_album(&id, index),
+        Command::PlayWork { album_id, index } => controls.play_work(&album_id, index),
+        Command::PlayPlaylist { id, index, shuffle } => controls.play_playlist(id, index, shuffle),
+        Command::PlayTrack { id } => controls.play_track(id),
+        Command::PlayTopTracks { artist_id, index } => controls.play_top_tracks(artist_id, index),
+        Command::PlayUrl { url } => controls.play_url(&url).map_err(|err| err.to_string())?,
+    }
+
+    Ok(())
+}
+
+async fn send(
+    sender: &mut SplitSink<WebSocket, Message>,
+    event: &Event,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(event).expect("infailable");
+    sender.send(Message::Text(text.into())).await
+}
@@ -0,0 +1,329 @@
+use std::sync::Arc;
+
+use axum::{
+    Form, Router,
+    extract::{FromRequestParts, Path, State},
+    http::{StatusCode, request::Parts},
+    response::IntoResponse,
+    routing::{get, post, put},
+};
+use axum_extra::extract::{
+    CookieJar,
+    cookie::{Cookie, SameSite},
+};
+use futures::future::join_all;
+use leptos::{IntoView, component, prelude::*};
+use qobuz_player_controls::Status;
+use serde::Deserialize;
+
+use crate::{
+    AppState, ResponseResult,
+    base_path::url,
+    html,
+    icons::{Backward, Forward, Pause, Play},
+    ok_or_broadcast,
+    page::Page,
+    routes::api::PlayerStatus,
+    view::render,
+    zone::Zone,
+};
+
+pub(crate) fn routes() -> Router<std::sync::Arc<crate::AppState>> {
+    Router::new()
+        .route("/zones", get(index))
+        .route("/zones/{id}/card", get(card_partial))
+        .route("/zones/{id}/select", put(select))
+        .route("/zones/{id}/play-pause", put(play_pause))
+        .route("/zones/{id}/next", put(next))
+        .route("/zones/{id}/previous", put(previous))
+        .route("/zones/{id}/volume", post(set_volume))
+}
+
+const ZONE_COOKIE: &str = "zone";
+
+/// The zone picked on the zones page, this player until another one is picked. Pages play
+/// and queue music on it.
+pub(crate) struct SelectedZone {
+    pub(crate) id: usize,
+    pub(crate) zone: Arc<Zone>,
+}
+
+impl FromRequestParts<Arc<AppState>> for SelectedZone {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let id = CookieJar::from_headers(&parts.headers)
+            .get(ZONE_COOKIE)
+            .and_then(|cookie| cookie.value().parse::<usize>().ok())
+            .filter(|id| *id < state.zones.len())
+            .unwrap_or_default();
+
+        Ok(Self {
+            id,
+            zone: state.zones[id].clone(),
+        })
+    }
+}
+
+async fn select(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<usize>,
+    jar: CookieJar,
+) -> Result<(CookieJar, axum::response::Response), axum::response::Response> {
+    find_zone(&state, id)?;
+
+    let mut cookie = Cookie::new(ZONE_COOKIE, id.to_string());
+    cookie.set_same_site(SameSite::Strict);
+    cookie.set_http_only(true);
+    cookie.set_path("/");
+    cookie.set_max_age(time::Duration::days(365));
+
+    // Every card shows whether it is the selected one.
+    let response = (StatusCode::OK, [("HX-Refresh", "true")]).into_response();
+    Ok((jar.add(cookie), response))
+}
+
+async fn index(State(state): State<Arc<AppState>>, selected: SelectedZone) -> impl IntoResponse {
+    let zones = zone_summaries(&state).await;
+
+    let current_status = state.status_receiver.borrow();
+    let tracklist = state.tracklist_receiver.borrow();
+
+    render(html! {
+        <Page active_page=Page::None current_status=*current_status tracklist=&tracklist>
+            <div class="flex flex-col gap-4 p-4 pt-safe-or-4">
+                <h1 class="text-2xl">Zones</h1>
+                <ZoneList zones=zones selected=selected.id />
+            </div>
+        </Page>
+    })
+}
+
+/// A single card, redrawn when its zone changes without asking the other peers.
+async fn card_partial(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<usize>,
+    selected: SelectedZone,
+) -> ResponseResult {
+    let zone = find_zone(&state, id)?;
+    let summary = zone_summary(id, zone, zone.status().await);
+
+    Ok(render(
+        html! { <ZoneCard zone=summary selected=selected.id == id /> },
+    ))
+}
+
+struct ZoneSummary {
+    id: usize,
+    name: String,
+    url: Option<String>,
+    /// Peers that do not answer show the error instead.
+    status: Result<PlayerStatus, String>,
+}
+
+fn zone_summary(
+    id: usize,
+    zone: &Zone,
+    status: qobuz_player_controls::Result<PlayerStatus>,
+) -> ZoneSummary {
+    ZoneSummary {
+        id,
+        name: zone.name().to_string(),
+        url: zone.url().map(|url| url.to_string()),
+        status: status.map_err(|err| err.to_string()),
+    }
+}
+
+async fn zone_summaries(state: &AppState) -> Vec<ZoneSummary> {
+    let statuses = join_all(state.zones.iter().map(|zone| zone.status())).await;
+
+    state
+        .zones
+        .iter()
+        .zip(statuses)
+        .enumerate()
+        .map(|(id, (zone, status))| zone_summary(id, zone, status))
+        .collect()
+}
+
+#[allow(clippy::result_large_err)]
+fn find_zone(state: &AppState, id: usize) -> Result<&Zone, axum::response::Response> {
+    state
+        .zones
+        .get(id)
+        .map(|zone| zone.as_ref())
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+async fn play_pause(State(state): State<Arc<AppState>>, Path(id): Path<usize>) -> ResponseResult {
+    let zone = find_zone(&state, id)?;
+    ok_or_broadcast(&state.broadcast, zone.play_pause().await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn next(State(state): State<Arc<AppState>>, Path(id): Path<usize>) -> ResponseResult {
+    let zone = find_zone(&state, id)?;
+    ok_or_broadcast(&state.broadcast, zone.next().await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn previous(State(state): State<Arc<AppState>>, Path(id): Path<usize>) -> ResponseResult {
+    let zone = find_zone(&state, id)?;
+    ok_or_broadcast(&state.broadcast, zone.previous().await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[derive(Deserialize)]
+struct VolumeParameters {
+    value: u32,
+}
+
+async fn set_volume(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<usize>,
+    Form(parameters): Form<VolumeParameters>,
+) -> ResponseResult {
+    let zone = find_zone(&state, id)?;
+    ok_or_broadcast(&state.broadcast, zone.set_volume(parameters.value).await)?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[component]
+fn zone_list(zones: Vec<ZoneSummary>, selected: usize) -> impl IntoView {
+    html! {
+        <div class="flex flex-col gap-4">
+            {zones
+                .into_iter()
+                .map(|zone| {
+                    let is_selected = zone.id == selected;
+                    html! { <ZoneCard zone=zone selected=is_selected /> }
+                })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
+
+#[component]
+fn zone_card(zone: ZoneSummary, selected: bool) -> impl IntoView {
+    let ZoneSummary {
+        id,
+        name,
+        url,
+        status,
+    } = zone;
+
+    let link = match url {
+        Some(url) => html! {
+            <a href=url target="_blank" class="text-sm text-gray-400">
+                Open
+            </a>
+        }
+        .into_any(),
+        None => html! { <span class="text-sm text-gray-400">This player</span> }.into_any(),
+    };
+
+    let body = match status {
+        Ok(status) => html! { <ZoneState id=id status=status /> }.into_any(),
+        Err(err) => html! { <p class="text-gray-400">{err}</p> }.into_any(),
+    };
+
+    let select = match selected {
+        true => html! { <span class="text-sm text-blue-500">Selected</span> }.into_any(),
+        false => html! {
+            <button
+                class="text-sm text-gray-400 cursor-pointer"
+                hx-put=url(&format!("/zones/{id}/select"))
+                hx-swap="none"
+            >
+                Select
+            </button>
+        }
+        .into_any(),
+    };
+
+    html! {
+        <div
+            class="flex flex-col gap-4 p-4 bg-gray-800 rounded-md"
+            hx-get=url(&format!("/zones/{id}/card"))
+            hx-trigger="zones"
+            hx-swap="outerHTML"
+            data-sse="zones"
+            data-zone=id.to_string()
+        >
+            <div class="flex gap-4 justify-between items-center">
+                <h3 class="w-full text-lg">{name}</h3>
+                {select}
+                {link}
+            </div>
+            {body}
+        </div>
+    }
+}
+
+#[component]
+fn zone_state(id: usize, status: PlayerStatus) -> impl IntoView {
+    let (title, artist_name, image) = match status.current_track {
+        Some(track) => (track.title, track.artist_name, track.image),
+        None => ("Nothing playing".to_string(), None, None),
+    };
+
+    let status_icon = match status.status {
+        Status::Playing => html! { <Pause /> }.into_any(),
+        Status::Paused | Status::Buffering => html! { <Play /> }.into_any(),
+    };
+
+    html! {
+        <div class="flex gap-4 items-center">
+            <div
+                class="bg-gray-900 bg-center bg-no-repeat bg-cover rounded-md aspect-square size-16 shrink-0"
+                style=image.map(|image| format!("background-image: url({image});"))
+            ></div>
+            <div class="flex overflow-hidden flex-col w-full">
+                <span class="truncate">{title}</span>
+                <span class="text-sm text-gray-400 truncate">{artist_name}</span>
+            </div>
+        </div>
+        <div class="flex gap-4 items-center">
+            <button
+                class="cursor-pointer size-8 shrink-0"
+                hx-put=url(&format!("/zones/{id}/previous"))
+                hx-swap="none"
+            >
+                <Backward />
+            </button>
+            <button
+                class="cursor-pointer size-10 shrink-0"
+                hx-put=url(&format!("/zones/{id}/play-pause"))
+                hx-swap="none"
+            >
+                {status_icon}
+            </button>
+            <button
+                class="cursor-pointer size-8 shrink-0"
+                hx-put=url(&format!("/zones/{id}/next"))
+                hx-swap="none"
+            >
+                <Forward />
+            </button>
+            <input
+                class="w-full accent-blue-500"
+                autocomplete="off"
+                hx-post=url(&format!("/zones/{id}/volume"))
+                hx-trigger="change"
+                hx-swap="none"
+                value=status.volume
+                type="range"
+                name="value"
+                min="0"
+                max="100"
+            />
+        </div>
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use qobuz_player_client::testing::FixtureServer;
+use qobuz_player_controls::{
+    AudioQuality, client::Client, database::Database, notification::NotificationBroadcast,
+    output::Output, player::Player, tracklist::Tracklist,
+};
+
+use crate::{create_router, listener::ClientIp, zone::ZonesConfig};
+
+/// The web server on a player with the null output, whose client talks to a [`FixtureServer`].
+pub(crate) struct TestServer {
+    url: String,
+    // The routes watch the player's channels, which close with it.
+    _player: Player,
+    _fixtures: FixtureServer,
+}
+
+impl TestServer {
+    pub(crate) async fn start() -> Self {
+        let fixtures = FixtureServer::start().await;
+        let client = Arc::new(Client::new(
+            "user@example.com".into(),
+            "password".into(),
+            AudioQuality::CD,
+            fixtures.connection(),
+        ));
+        let database = Arc::new(Database::in_memory().await.unwrap());
+        let broadcast = Arc::new(NotificationBroadcast::new());
+
+        let player = Player::new(
+            Output::Null,
+            Tracklist::new(),
+            client.clone(),
+            1.0,
+            broadcast.clone(),
+            std::env::temp_dir().join("qobuz-player-web-test-cache"),
+            database.clone(),
+        )
+        .unwrap();
+
+        let router = create_router(
+            player.controls(),
+            player.position(),
+            player.tracklist(),
+            player.volume(),
+            player.status(),
+            ZonesConfig {
+                name: "Test".into(),
+                peers: vec![],
+            },
+            None,
+            "session key".into(),
+            vec![],
+            None,
+            broadcast,
+            client,
+            database,
+        )
+        .await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<ClientIp>(),
+            )
+            .await
+        });
+
+        Self {
+            url,
+            _player: player,
+            _fixtures: fixtures,
+        }
+    }
+
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.url)
+    }
+}
@@ -0,0 +1,428 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use qobuz_player_controls::{
+    PositionReceiver, Result, StatusReceiver, TracklistReceiver, VolumeReceiver,
+    controls::Controls, error::Error,
+};
+use tokio::sync::broadcast::Sender;
+
+use crate::{
+    ServerSentEvent,
+    routes::api::{PlayerStatus, volume_percent},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Player events that change what a zone card shows.
+const ZONE_EVENTS: [&str; 3] = ["status", "tracklist", "volume"];
+
+/// Another `qobuz-player` on the network, controlled through its json api.
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub name: String,
+    /// Root of the peer's web ui, including its base path.
+    pub url: String,
+    /// Api token, needed when the peer has a web secret.
+    pub token: Option<String>,
+}
+
+/// Parses `NAME=URL`, with the api token as url fragment: `Kitchen=http://kitchen:9888#token`.
+impl FromStr for Peer {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, url) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=URL, got {value}"))?;
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("peer name is empty in {value}"));
+        }
+
+        let (url, token) = match url.split_once('#') {
+            Some((_, "")) => return Err(format!("api token is empty in {value}")),
+            Some((url, token)) => (url, Some(token.to_string())),
+            None => (url, None),
+        };
+
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!(
+                "peer url must start with http:// or https://, got {url}"
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+}
+
+/// The zones shown in the zone switcher: this player and its peers.
+#[derive(Debug, Clone)]
+pub struct ZonesConfig {
+    /// Name of the player running in this process.
+    pub name: String,
+    pub peers: Vec<Peer>,
+}
+
+/// A player the web ui can control, either the one in this process or a peer.
+pub(crate) enum Zone {
+    Local(LocalZone),
+    Remote(RemoteZone),
+}
+
+pub(crate) struct LocalZone {
+    pub(crate) name: String,
+    pub(crate) controls: Controls,
+    pub(crate) position_receiver: PositionReceiver,
+    pub(crate) tracklist_receiver: TracklistReceiver,
+    pub(crate) volume_receiver: VolumeReceiver,
+    pub(crate) status_receiver: StatusReceiver,
+}
+
+impl LocalZone {
+    fn status(&self) -> PlayerStatus {
+        PlayerStatus {
+            status: *self.status_receiver.borrow(),
+            position_ms: self.position_receiver.borrow().as_millis() as u64,
+            volume: volume_percent(*self.volume_receiver.borrow()),
+            current_track: self.tracklist_receiver.borrow().current_track().cloned(),
+        }
+    }
+}
+
+pub(crate) struct RemoteZone {
+    peer: Peer,
+    http: reqwest::Client,
+}
+
+impl RemoteZone {
+    pub(crate) fn new(peer: Peer) -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("infailable");
+
+        Self { peer, http }
+    }
+
+    fn error(&self, message: impl Display) -> Error {
+        Error::Zone {
+            zone: self.peer.name.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{path}", self.peer.url));
+
+        match &self.peer.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request.send().await.map_err(|err| self.error(err))?;
+
+        match response.status().is_success() {
+            true => Ok(response),
+            false => Err(self.error(response.status())),
+        }
+    }
+
+    async fn command(&self, path: &str) -> Result<()> {
+        let request = self
+            .request(reqwest::Method::POST, path)
+            .timeout(REQUEST_TIMEOUT);
+
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn play_url(&self, url: &str) -> Result<()> {
+        let request = self
+            .request(reqwest::Method::POST, "/api/v1/play/url")
+            .timeout(REQUEST_TIMEOUT)
+            .json(&serde_json::json!({ "url": url }));
+
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn status(&self) -> Result<PlayerStatus> {
+        let request = self
+            .request(reqwest::Method::GET, "/api/v1/status")
+            .timeout(REQUEST_TIMEOUT);
+
+        let response = self.send(request).await?;
+
+        response.json().await.map_err(|err| self.error(err))
+    }
+
+    async fn set_volume(&self, volume: u32) -> Result<()> {
+        let request = self
+            .request(reqwest::Method::PUT, "/api/v1/volume")
+            .timeout(REQUEST_TIMEOUT)
+            .json(&serde_json::json!({ "volume": volume }));
+
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Follow the peer's server sent events until the connection drops, calling `changed`
+    /// for every event that affects the zone card.
+    async fn follow_events(&self, changed: impl Fn()) -> Result<()> {
+        // No timeout, the event stream stays open for as long as the peer runs.
+        let mut response = self
+            .send(self.request(reqwest::Method::GET, "/api/v1/events"))
+            .await?;
+
+        let mut buffer = String::new();
+        while let Some(chunk) = response.chunk().await.map_err(|err| self.error(err))? {
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(end) = buffer.find("\n\n") {
+                let event = buffer[..end].to_string();
+                buffer.drain(..end + 2);
+
+                let is_zone_event = event
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("event:"))
+                    .any(|name| ZONE_EVENTS.contains(&name.trim()));
+
+                if is_zone_event {
+                    changed();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Zone {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Zone::Local(zone) => &zone.name,
+            Zone::Remote(zone) => &zone.peer.name,
+        }
+    }
+
+    pub(crate) fn is_local(&self) -> bool {
+        matches!(self, Zone::Local(_))
+    }
+
+    /// Address of the peer's own web ui.
+    pub(crate) fn url(&self) -> Option<&str> {
+        match self {
+            Zone::Local(_) => None,
+            Zone::Remote(zone) => Some(&zone.peer.url),
+        }
+    }
+
+    pub(crate) async fn status(&self) -> Result<PlayerStatus> {
+        match self {
+            Zone::Local(zone) => Ok(zone.status()),
+            Zone::Remote(zone) => zone.status().await,
+        }
+    }
+
+    pub(crate) async fn play_pause(&self) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_pause();
+                Ok(())
+            }
+            Zone::Remote(zone) => zone.command("/api/v1/controls/play-pause").await,
+        }
+    }
+
+    pub(crate) async fn next(&self) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.next();
+                Ok(())
+            }
+            Zone::Remote(zone) => zone.command("/api/v1/controls/next").await,
+        }
+    }
+
+    pub(crate) async fn previous(&self) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.previous();
+                Ok(())
+            }
+            Zone::Remote(zone) => zone.command("/api/v1/controls/previous").await,
+        }
+    }
+
+    /// Volume in percent.
+    pub(crate) async fn set_volume(&self, volume: u32) -> Result<()> {
+        let volume = volume.min(100);
+
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.set_volume(volume as f32 / 100.0);
+                Ok(())
+            }
+            Zone::Remote(zone) => zone.set_volume(volume).await,
+        }
+    }
+
+    pub(crate) async fn play_album(&self, id: &str, index: u32) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_album(id, index);
+                Ok(())
+            }
+            Zone::Remote(zone) => {
+                zone.command(&format!("/api/v1/play/album/{id}?index={index}"))
+                    .await
+            }
+        }
+    }
+
+    pub(crate) async fn play_work(&self, album_id: &str, index: u32) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_work(album_id, index);
+                Ok(())
+            }
+            Zone::Remote(zone) => {
+                zone.command(&format!("/api/v1/play/album/{album_id}/work?index={index}"))
+                    .await
+            }
+        }
+    }
+
+    pub(crate) async fn play_playlist(&self, id: u32, index: u32, shuffle: bool) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_playlist(id, index, shuffle);
+                Ok(())
+            }
+            Zone::Remote(zone) => {
+                zone.command(&format!(
+                    "/api/v1/play/playlist/{id}?index={index}&shuffle={shuffle}"
+                ))
+                .await
+            }
+        }
+    }
+
+    pub(crate) async fn play_track(&self, id: u32) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_track(id);
+                Ok(())
+            }
+            Zone::Remote(zone) => zone.command(&format!("/api/v1/play/track/{id}")).await,
+        }
+    }
+
+    pub(crate) async fn play_top_tracks(&self, artist_id: u32, index: u32) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.play_top_tracks(artist_id, index);
+                Ok(())
+            }
+            Zone::Remote(zone) => {
+                zone.command(&format!("/api/v1/play/artist/{artist_id}?index={index}"))
+                    .await
+            }
+        }
+    }
+
+    /// Play what a Qobuz share link points to.
+    pub(crate) async fn play_url(&self, url: &str) -> Result<()> {
+        match self {
+            Zone::Local(zone) => zone.controls.play_url(url),
+            Zone::Remote(zone) => zone.play_url(url).await,
+        }
+    }
+
+    pub(crate) async fn add_to_queue(&self, track_id: u32) -> Result<()> {
+        match self {
+            Zone::Local(zone) => {
+                zone.controls.add_to_queue(track_id);
+                Ok(())
+            }
+            Zone::Remote(zone) => {
+                zone.command(&format!("/api/v1/queue/tracks/{track_id}"))
+                    .await
+            }
+        }
+    }
+
+    /// Send a `zones` event carrying the zone's id whenever its card needs to be redrawn.
+    pub(crate) async fn watch(&self, id: usize, tx: Sender<ServerSentEvent>) {
+        let changed = || {
+            _ = tx.send(ServerSentEvent {
+                event_name: "zones".into(),
+                event_data: id.to_string(),
+            });
+        };
+
+        match self {
+            Zone::Local(zone) => {
+                let mut tracklist = zone.tracklist_receiver.clone();
+                let mut volume = zone.volume_receiver.clone();
+                let mut status = zone.status_receiver.clone();
+
+                loop {
+                    tokio::select! {
+                        Ok(_) = tracklist.changed() => {}
+                        Ok(_) = volume.changed() => {}
+                        Ok(_) = status.changed() => {}
+                        else => return,
+                    }
+
+                    changed();
+                }
+            }
+            Zone::Remote(zone) => loop {
+                if let Err(err) = zone.follow_events(&changed).await {
+                    tracing::debug!("{err}");
+                }
+
+                // Redraw the card, so it shows whether the peer went away.
+                changed();
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_peers() {
+        let peer: Peer = "Kitchen=http://kitchen:9888/music/#secret".parse().unwrap();
+        assert_eq!(peer.name, "Kitchen");
+        assert_eq!(peer.url, "http://kitchen:9888/music");
+        assert_eq!(peer.token.as_deref(), Some("secret"));
+
+        let peer: Peer = "Living room=https://living-room".parse().unwrap();
+        assert_eq!(peer.name, "Living room");
+        assert_eq!(peer.url, "https://living-room");
+        assert_eq!(peer.token, None);
+    }
+
+    #[test]
+    fn rejects_invalid_peers() {
+        assert!("http://kitchen:9888".parse::<Peer>().is_err());
+        assert!("Kitchen=kitchen:9888".parse::<Peer>().is_err());
+        assert!("=http://kitchen:9888".parse::<Peer>().is_err());
+        assert!("Kitchen=http://kitchen:9888#".parse::<Peer>().is_err());
+    }
+}